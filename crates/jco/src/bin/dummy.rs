@@ -1,3 +0,0 @@
-// TODO(yosh): remove this file once we figure out what to do with `build.rs` in
-// the project root
-fn main() {}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use js_component_bindgen::{
+    AsyncDeterminismProfile, AsyncMode, BindingsMode, ImportJournalMode, InstantiationMode,
+    ModuleFormat, ResultMode, TypeRepresentations,
+};
+use structopt::StructOpt;
+use wit_parser::{PackageId, Resolve};
+
+pub(crate) mod transpile;
+pub(crate) mod types;
+pub(crate) mod wasm_tools;
+
+/// Parse an instantiation mode as accepted on the command line
+pub(crate) fn parse_instantiation_mode(s: &str) -> Result<InstantiationMode> {
+    match s {
+        "async" => Ok(InstantiationMode::Async),
+        "sync" => Ok(InstantiationMode::Sync),
+        _ => bail!("invalid instantiation mode [{s}], expected one of 'async' or 'sync'"),
+    }
+}
+
+/// Parse a bindings mode as accepted on the command line
+pub(crate) fn parse_bindings_mode(s: &str) -> Result<BindingsMode> {
+    match s {
+        "js" => Ok(BindingsMode::Js),
+        "hybrid" => Ok(BindingsMode::Hybrid),
+        "optimized" => Ok(BindingsMode::Optimized),
+        "direct-optimized" => Ok(BindingsMode::DirectOptimized),
+        _ => bail!(
+            "invalid import bindings mode [{s}], expected one of 'js', 'hybrid', 'optimized' or 'direct-optimized'"
+        ),
+    }
+}
+
+/// Parse a module format as accepted on the command line
+pub(crate) fn parse_module_format(s: &str) -> Result<ModuleFormat> {
+    match s {
+        "esm" => Ok(ModuleFormat::Esm),
+        "commonjs" | "cjs" => Ok(ModuleFormat::CommonJs),
+        _ => bail!("invalid module format [{s}], expected one of 'esm' or 'commonjs'"),
+    }
+}
+
+/// Parse an async determinism profile as accepted on the command line
+pub(crate) fn parse_async_determinism(s: &str) -> Result<AsyncDeterminismProfile> {
+    match s {
+        "random" => Ok(AsyncDeterminismProfile::Random),
+        "deterministic" => Ok(AsyncDeterminismProfile::Deterministic),
+        _ => match s.strip_prefix("seed=").map(str::parse) {
+            Some(Ok(seed)) => Ok(AsyncDeterminismProfile::Seeded { seed }),
+            _ => bail!(
+                "invalid async determinism [{s}], expected one of 'random', 'deterministic' or 'seed=<u32>'"
+            ),
+        },
+    }
+}
+
+/// Parse an import journal mode as accepted on the command line
+pub(crate) fn parse_import_journal_mode(s: &str) -> Result<ImportJournalMode> {
+    match s {
+        "record" => Ok(ImportJournalMode::Record),
+        "replay" => Ok(ImportJournalMode::Replay),
+        _ => bail!("invalid import journal mode [{s}], expected one of 'record' or 'replay'"),
+    }
+}
+
+/// Parse a `specifier=mapping` pair, as used by `--map`
+pub(crate) fn parse_key_value(s: &str) -> Result<(String, String)> {
+    let Some((key, value)) = s.split_once('=') else {
+        bail!("invalid mapping [{s}], expected the form 'specifier=mapping'");
+    };
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Arguments controlling async imports & exports that are shared between commands
+#[derive(StructOpt)]
+pub(crate) struct AsyncArgs {
+    /// EXPERIMENTAL: use async imports and exports ('sync', 'jspi' or 'asyncify')
+    #[structopt(long = "async-mode")]
+    async_mode: Option<String>,
+    /// EXPERIMENTAL: async component imports (e.g. "wasi:io/poll@0.2.0#poll" or "wasi:filesystem/*#*")
+    #[structopt(long = "async-imports")]
+    async_imports: Vec<String>,
+    /// EXPERIMENTAL: async component exports (e.g. "wasi:cli/run@0.2.3#run")
+    #[structopt(long = "async-exports")]
+    async_exports: Vec<String>,
+}
+
+impl AsyncArgs {
+    /// Build the [`AsyncMode`] that corresponds to these arguments, if any
+    pub(crate) fn async_mode(&self) -> Result<Option<AsyncMode>> {
+        match self.async_mode.as_deref() {
+            None => Ok(None),
+            Some("sync") => Ok(Some(AsyncMode::Sync)),
+            Some("jspi") => Ok(Some(AsyncMode::JavaScriptPromiseIntegration {
+                imports: self.async_imports.clone(),
+                exports: self.async_exports.clone(),
+            })),
+            Some("asyncify") => Ok(Some(AsyncMode::Asyncify {
+                imports: self.async_imports.clone(),
+                exports: self.async_exports.clone(),
+            })),
+            Some(other) => {
+                bail!("invalid async mode [{other}], expected one of 'sync', 'jspi' or 'asyncify'")
+            }
+        }
+    }
+}
+
+/// Arguments controlling how `result<T, E>` values are mapped, shared between commands
+#[derive(StructOpt)]
+pub(crate) struct ResultArgs {
+    /// Component imports that return `{ tag, val }` result objects rather than
+    /// throwing (e.g. "my:pkg/store#get" or "my:pkg/store")
+    #[structopt(long = "result-imports")]
+    result_imports: Vec<String>,
+    /// Component exports that return `{ tag, val }` result objects rather than
+    /// throwing `ComponentError`s
+    #[structopt(long = "result-exports")]
+    result_exports: Vec<String>,
+}
+
+impl ResultArgs {
+    /// Build the [`ResultMode`] that corresponds to these arguments, if any
+    pub(crate) fn result_mode(&self) -> Option<ResultMode> {
+        if self.result_imports.is_empty() && self.result_exports.is_empty() {
+            return None;
+        }
+        Some(ResultMode::Explicit {
+            imports: self.result_imports.clone(),
+            exports: self.result_exports.clone(),
+        })
+    }
+}
+
+/// Arguments selecting alternative JS representations of WIT types, shared between commands
+#[derive(StructOpt)]
+pub(crate) struct TypeReprArgs {
+    /// Enums represented as numbers (e.g. "my:pkg/types#color", "color" or "*")
+    #[structopt(long = "numeric-enums")]
+    numeric_enums: Vec<String>,
+    /// Flags represented as a numeric bitmask (e.g. "my:pkg/types#perms")
+    #[structopt(long = "bitmask-flags")]
+    bitmask_flags: Vec<String>,
+    /// Variants represented as class instances (e.g. "my:pkg/types#shape")
+    #[structopt(long = "class-variants")]
+    class_variants: Vec<String>,
+}
+
+impl TypeReprArgs {
+    /// Build the [`TypeRepresentations`] that correspond to these arguments
+    pub(crate) fn type_representations(&self) -> TypeRepresentations {
+        TypeRepresentations {
+            numeric_enums: self.numeric_enums.clone(),
+            bitmask_flags: self.bitmask_flags.clone(),
+            class_variants: self.class_variants.clone(),
+        }
+    }
+}
+
+/// Arguments used to select a WIT package/world from disk
+#[derive(StructOpt)]
+pub(crate) struct WorldArgs {
+    /// WIT world to use
+    #[structopt(short = "n", long = "world-name")]
+    pub(crate) world_name: Option<String>,
+    /// Enable one specific WIT feature (repeatable)
+    #[structopt(long = "feature")]
+    pub(crate) features: Vec<String>,
+    /// Enable all WIT features
+    #[structopt(long = "all-features")]
+    pub(crate) all_features: bool,
+}
+
+impl WorldArgs {
+    /// Build a [`Resolve`] from the WIT file or directory at the given path,
+    /// returning the resolve along with the selected package
+    pub(crate) fn resolve(&self, wit_path: &Path) -> Result<(Resolve, PackageId)> {
+        let mut resolve = Resolve {
+            all_features: self.all_features,
+            ..Default::default()
+        };
+        for f in self.features.iter() {
+            resolve.features.insert(f.to_string());
+        }
+
+        let id = if wit_path.is_dir() {
+            resolve
+                .push_dir(wit_path)
+                .with_context(|| format!("reading WIT dir at [{}]", wit_path.display()))?
+                .0
+        } else {
+            resolve
+                .push_file(wit_path)
+                .with_context(|| format!("reading WIT file at [{}]", wit_path.display()))?
+        };
+
+        Ok((resolve, id))
+    }
+}
+
+/// Read a Wasm binary from disk, accepting the WebAssembly text format as well
+pub(crate) fn read_wasm(path: &Path) -> Result<Vec<u8>> {
+    let bytes = fs::read(path).with_context(|| format!("reading [{}]", path.display()))?;
+    let bytes = wat::parse_bytes(&bytes)
+        .with_context(|| format!("parsing [{}]", path.display()))?
+        .into_owned();
+    Ok(bytes)
+}
+
+/// Write a list of generated files into the output directory, printing a
+/// summary unless `quiet` is set
+pub(crate) fn write_files(
+    files: &[(String, Vec<u8>)],
+    out_dir: &Path,
+    summary_title: &str,
+    quiet: bool,
+) -> Result<()> {
+    for (name, contents) in files {
+        let path = out_dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating dir [{}]", parent.display()))?;
+        }
+        fs::write(&path, contents).with_context(|| format!("writing [{}]", path.display()))?;
+    }
+
+    if !quiet {
+        println!("\n  {summary_title}:\n");
+        for (name, contents) in files {
+            println!(
+                " - {}  {}",
+                out_dir.join(name).display(),
+                size_str(contents.len())
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Write a single output file, or print it to stdout if no path was provided
+pub(crate) fn write_output(output: Option<&PathBuf>, contents: &[u8]) -> Result<()> {
+    match output {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("creating dir [{}]", parent.display()))?;
+            }
+            fs::write(path, contents).with_context(|| format!("writing [{}]", path.display()))
+        }
+        None => {
+            use std::io::Write as _;
+            std::io::stdout()
+                .write_all(contents)
+                .context("writing to stdout")
+        }
+    }
+}
+
+/// Collect `--map` arguments into the mapping table expected by the transpiler
+pub(crate) fn collect_map(map: Vec<(String, String)>) -> Option<HashMap<String, String>> {
+    if map.is_empty() {
+        None
+    } else {
+        Some(map.into_iter().collect())
+    }
+}
+
+/// Render a byte count in the same style as the JS CLI summary
+fn size_str(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.2} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
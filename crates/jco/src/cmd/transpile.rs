@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use js_component_bindgen::{
+    AsyncDeterminismProfile, BindingsMode, ImportJournalMode, InstantiationMode, ModuleFormat,
+    TranspileOpts, Transpiled,
+};
+use structopt::StructOpt;
+
+use crate::cmd::{
+    AsyncArgs, ResultArgs, TypeReprArgs, collect_map, parse_async_determinism, parse_bindings_mode,
+    parse_import_journal_mode, parse_instantiation_mode, parse_key_value, parse_module_format,
+    read_wasm, write_files,
+};
+
+/// Arguments for `jco transpile`
+#[derive(StructOpt)]
+pub(crate) struct TranspileArgs {
+    /// Path to the WebAssembly component (binary or text format)
+    #[structopt(parse(from_os_str))]
+    component_path: PathBuf,
+    /// Output directory
+    #[structopt(short = "o", long = "out-dir", parse(from_os_str))]
+    out_dir: PathBuf,
+    /// Custom output name (defaults to the component file name)
+    #[structopt(long)]
+    name: Option<String>,
+    /// Do not output TypeScript .d.ts types
+    #[structopt(long = "no-typescript")]
+    no_typescript: bool,
+    /// Output for custom module instantiation ('async' or 'sync')
+    #[structopt(short = "I", long, parse(try_from_str = parse_instantiation_mode))]
+    instantiation: Option<InstantiationMode>,
+    /// Module format of the generated JS ('esm' or 'commonjs')
+    #[structopt(long = "module-format", parse(try_from_str = parse_module_format))]
+    module_format: Option<ModuleFormat>,
+    /// Bindings mode for imports ('js', 'hybrid', 'optimized' or 'direct-optimized')
+    #[structopt(long = "import-bindings", parse(try_from_str = parse_bindings_mode))]
+    import_bindings: Option<BindingsMode>,
+    #[structopt(flatten)]
+    async_args: AsyncArgs,
+    /// EXPERIMENTAL: scheduling of async tasks ('random', 'deterministic' or 'seed=<u32>')
+    #[structopt(long = "async-determinism", parse(try_from_str = parse_async_determinism))]
+    async_determinism: Option<AsyncDeterminismProfile>,
+    /// Record calls to host imports into a journal, or replay them from one ('record' or 'replay')
+    #[structopt(long = "import-journal", parse(try_from_str = parse_import_journal_mode))]
+    import_journal: Option<ImportJournalMode>,
+    /// EXPERIMENTAL: record async task, subtask, waitable, backpressure and stream events
+    #[structopt(long = "async-trace")]
+    async_trace: bool,
+    /// Milliseconds async tasks must stay deadlocked before they fail (default 1000, 0 disables)
+    #[structopt(long = "async-deadlock-timeout")]
+    async_deadlock_timeout: Option<u32>,
+    #[structopt(flatten)]
+    result_args: ResultArgs,
+    #[structopt(flatten)]
+    type_repr_args: TypeReprArgs,
+    /// Emit `tracing` calls on function entry/exit
+    #[structopt(long)]
+    tracing: bool,
+    /// Set the byte size under which core Wasm binaries will be inlined as base64
+    #[structopt(short = "b", long = "base64-cutoff", default_value = "5000")]
+    base64_cutoff: usize,
+    /// Enables compatibility for JS environments without top-level await support
+    /// via an async $init promise export
+    #[structopt(long = "tla-compat")]
+    tla_compat: bool,
+    /// Avoid emitting validation checks when lifting component values
+    #[structopt(long = "valid-lifting-optimization")]
+    valid_lifting_optimization: bool,
+    /// Disables compatibility in Node.js without a fetch global
+    #[structopt(long = "no-nodejs-compat")]
+    no_nodejs_compat: bool,
+    /// specifier=./output custom mappings for the component imports
+    #[structopt(short = "M", long = "map", parse(try_from_str = parse_key_value))]
+    map: Vec<(String, String)>,
+    /// Disable namespaced exports for typescript compatibility
+    #[structopt(long = "no-namespaced-exports")]
+    no_namespaced_exports: bool,
+    /// Optimized output for Wasm multi-memory
+    #[structopt(long = "multi-memory")]
+    multi_memory: bool,
+    /// Enable bindgen output that uses Wasm exception references (exnref)
+    #[structopt(long = "bindgen-enable-wasm-exnref")]
+    bindgen_enable_wasm_exnref: bool,
+    /// Generate guest types (module declarations) rather than host types
+    #[structopt(long)]
+    guest: bool,
+    /// Generate bindings with strict type checking
+    #[structopt(long)]
+    strict: bool,
+    /// Treat the core modules as having been compiled to JS (asm.js)
+    #[structopt(long)]
+    asmjs: bool,
+    /// Emit a source map for the generated bindings
+    #[structopt(long = "source-map")]
+    source_map: bool,
+    /// Import intrinsics from a shared runtime module instead of inlining them
+    #[structopt(long = "shared-runtime")]
+    shared_runtime: bool,
+    /// Load core Wasm modules through source phase imports
+    #[structopt(long = "source-phase-imports")]
+    source_phase_imports: bool,
+    /// Compile core Wasm modules to JS, for engines without WebAssembly
+    #[structopt(long = "compile-core-to-js")]
+    compile_core_to_js: bool,
+    /// Disable output summary
+    #[structopt(short = "q", long)]
+    quiet: bool,
+}
+
+/// Transpile a component from disk, writing the generated files into the output directory
+pub(crate) fn run(args: TranspileArgs) -> Result<()> {
+    let component = read_wasm(&args.component_path)?;
+
+    let name = match args.name {
+        Some(name) => name,
+        None => args
+            .component_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .with_context(|| {
+                format!(
+                    "failed to determine output name from [{}]",
+                    args.component_path.display()
+                )
+            })?,
+    };
+
+    let opts = TranspileOpts::builder()
+        .name(name)
+        .no_typescript(args.no_typescript)
+        .maybe_instantiation_mode(args.instantiation)
+        .maybe_import_bindings(args.import_bindings)
+        .maybe_map(collect_map(args.map))
+        .nodejs_compat_disabled(args.no_nodejs_compat)
+        .base64_cutoff(args.base64_cutoff)
+        .tla_compat(args.tla_compat)
+        .valid_lifting_optimization(args.valid_lifting_optimization)
+        .tracing(args.tracing)
+        .no_namespaced_exports(args.no_namespaced_exports)
+        .multi_memory(args.multi_memory)
+        .guest(args.guest)
+        .maybe_async_mode(args.async_args.async_mode()?)
+        .async_determinism(args.async_determinism.unwrap_or_default())
+        .maybe_import_journal(args.import_journal)
+        .async_trace(args.async_trace)
+        .maybe_async_deadlock_timeout(args.async_deadlock_timeout)
+        .maybe_result_mode(args.result_args.result_mode())
+        .type_representations(args.type_repr_args.type_representations())
+        .strict(args.strict)
+        .asmjs(args.asmjs)
+        .supports_wasm_exnref(args.bindgen_enable_wasm_exnref)
+        .source_map(args.source_map)
+        .shared_runtime(args.shared_runtime)
+        .maybe_module_format(args.module_format)
+        .source_phase_imports(args.source_phase_imports)
+        .compile_core_to_js(args.compile_core_to_js)
+        .build();
+
+    let Transpiled { files, .. } = js_component_bindgen::transpile(&component, opts)
+        .with_context(|| format!("transpiling [{}]", args.component_path.display()))?;
+
+    write_files(
+        &files,
+        &args.out_dir,
+        "Transpiled JS Component Files",
+        args.quiet,
+    )
+}
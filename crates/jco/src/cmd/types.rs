@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use js_component_bindgen::{InstantiationMode, TranspileOpts};
+use structopt::StructOpt;
+
+use crate::cmd::{
+    AsyncArgs, ResultArgs, TypeReprArgs, WorldArgs, parse_instantiation_mode, write_files,
+};
+
+/// Arguments for `jco types`
+#[derive(StructOpt)]
+pub(crate) struct TypesArgs {
+    /// Path to a WIT file or directory
+    #[structopt(parse(from_os_str))]
+    wit_path: PathBuf,
+    /// Output directory
+    #[structopt(short = "o", long = "out-dir", parse(from_os_str), default_value = ".")]
+    out_dir: PathBuf,
+    /// Custom output name (defaults to the world name)
+    #[structopt(long)]
+    name: Option<String>,
+    #[structopt(flatten)]
+    world: WorldArgs,
+    /// Generates types for the TLA compat output with an async $init promise export
+    #[structopt(long = "tla-compat")]
+    tla_compat: bool,
+    /// Type output for custom module instantiation ('async' or 'sync')
+    #[structopt(short = "I", long, parse(try_from_str = parse_instantiation_mode))]
+    instantiation: Option<InstantiationMode>,
+    #[structopt(flatten)]
+    async_args: AsyncArgs,
+    #[structopt(flatten)]
+    result_args: ResultArgs,
+    #[structopt(flatten)]
+    type_repr_args: TypeReprArgs,
+    /// Generate guest types (module declarations) rather than host types
+    #[structopt(long)]
+    guest: bool,
+    /// Generate bindings with strict type checking
+    #[structopt(long)]
+    strict: bool,
+    /// Disable output summary
+    #[structopt(short = "q", long)]
+    quiet: bool,
+}
+
+/// Generate TypeScript declarations for a WIT world on disk
+pub(crate) fn run(args: TypesArgs) -> Result<()> {
+    let (resolve, id) = args.world.resolve(&args.wit_path)?;
+    let world_id = resolve.select_world(&[id], args.world.world_name.as_deref())?;
+    let name = args
+        .name
+        .unwrap_or_else(|| resolve.worlds[world_id].name.clone());
+
+    let opts = TranspileOpts::builder()
+        .name(name.clone())
+        .maybe_instantiation_mode(args.instantiation)
+        .tla_compat(args.tla_compat)
+        .guest(args.guest)
+        .maybe_async_mode(args.async_args.async_mode()?)
+        .maybe_result_mode(args.result_args.result_mode())
+        .type_representations(args.type_repr_args.type_representations())
+        .strict(args.strict)
+        .build();
+
+    let files = js_component_bindgen::generate_types(&name, resolve, world_id, opts)
+        .with_context(|| format!("generating types for [{name}]"))?;
+
+    write_files(&files, &args.out_dir, "Generated Type Files", args.quiet)
+}
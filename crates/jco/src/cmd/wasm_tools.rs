@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use structopt::StructOpt;
+use wasm_encoder::{Encode, Section};
+use wasm_metadata::Producers;
+use wit_component::{ComponentEncoder, DecodedWasm, StringEncoding, WitPrinter};
+
+use crate::cmd::{WorldArgs, read_wasm, write_output};
+
+/// Producer fields that may be embedded in (or added to) a binary
+const PRODUCERS_FIELDS: [&str; 3] = ["language", "processed-by", "sdk"];
+
+/// Arguments for `jco wit`
+#[derive(StructOpt)]
+pub(crate) struct WitArgs {
+    /// Path to the WebAssembly component
+    #[structopt(parse(from_os_str))]
+    component_path: PathBuf,
+    /// Output file (defaults to stdout)
+    #[structopt(short = "o", long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// Arguments for `jco embed`
+#[derive(StructOpt)]
+pub(crate) struct EmbedArgs {
+    /// Path to the core Wasm module to embed types into
+    #[structopt(parse(from_os_str))]
+    core_module: Option<PathBuf>,
+    /// Output file
+    #[structopt(short = "o", long, parse(from_os_str))]
+    output: PathBuf,
+    /// Path to a WIT file or directory
+    #[structopt(long, parse(from_os_str))]
+    wit: PathBuf,
+    #[structopt(flatten)]
+    world: WorldArgs,
+    /// Generate a dummy core module instead of reading one from disk
+    #[structopt(long)]
+    dummy: bool,
+    /// String encoding to use ('utf8', 'utf16' or 'compact-utf16')
+    #[structopt(long = "string-encoding", parse(try_from_str = parse_string_encoding))]
+    string_encoding: Option<StringEncoding>,
+    /// Producers metadata to embed, in the form `field=name@version`
+    #[structopt(short = "m", long = "metadata", parse(try_from_str = parse_producer))]
+    metadata: Vec<(String, String, String)>,
+}
+
+/// Arguments for `jco new`
+#[derive(StructOpt)]
+pub(crate) struct NewArgs {
+    /// Path to the core Wasm module
+    #[structopt(parse(from_os_str))]
+    core_module: PathBuf,
+    /// Output file
+    #[structopt(short = "o", long, parse(from_os_str))]
+    output: PathBuf,
+    /// Adapter to use when building the component, in the form `[NAME=]path`
+    #[structopt(long = "adapt")]
+    adapt: Vec<String>,
+}
+
+/// Subcommands of `jco metadata`
+#[derive(StructOpt)]
+pub(crate) enum MetadataCmd {
+    /// Extract the producer metadata of a Wasm binary
+    Show(MetadataShowArgs),
+    /// Add producer metadata to a Wasm binary
+    Add(MetadataAddArgs),
+}
+
+/// Arguments for `jco metadata show`
+#[derive(StructOpt)]
+pub(crate) struct MetadataShowArgs {
+    /// Path to the Wasm binary
+    #[structopt(parse(from_os_str))]
+    module: PathBuf,
+}
+
+/// Arguments for `jco metadata add`
+#[derive(StructOpt)]
+pub(crate) struct MetadataAddArgs {
+    /// Path to the Wasm binary
+    #[structopt(parse(from_os_str))]
+    module: PathBuf,
+    /// Output file
+    #[structopt(short = "o", long, parse(from_os_str))]
+    output: PathBuf,
+    /// Producers metadata to add, in the form `field=name@version`
+    #[structopt(short = "m", long = "metadata", parse(try_from_str = parse_producer))]
+    metadata: Vec<(String, String, String)>,
+}
+
+/// Print the WIT world of a component
+pub(crate) fn wit(args: WitArgs) -> Result<()> {
+    let binary = read_wasm(&args.component_path)?;
+    let decoded = wit_component::decode(&binary).context("failed to decode wit component")?;
+
+    let pkg = match &decoded {
+        DecodedWasm::WitPackage(_, pkg) => *pkg,
+        DecodedWasm::Component(resolve, world) => resolve.worlds[*world]
+            .package
+            .context("decoded world is missing a package")?,
+    };
+
+    let mut printer = WitPrinter::default();
+    printer
+        .print(decoded.resolve(), pkg, &[])
+        .context("unable to print wit")?;
+
+    write_output(args.output.as_ref(), printer.output.to_string().as_bytes())
+}
+
+/// Embed the `component-type` custom section into a core module
+pub(crate) fn embed(args: EmbedArgs) -> Result<()> {
+    let (resolve, id) = args.world.resolve(&args.wit)?;
+    let world = resolve.select_world(&[id], args.world.world_name.as_deref())?;
+
+    let mut core_binary = match (&args.core_module, args.dummy) {
+        (_, true) => {
+            wit_component::dummy_module(&resolve, world, wit_parser::ManglingAndAbi::Standard32)
+        }
+        (Some(path), false) => read_wasm(path)?,
+        (None, false) => {
+            bail!("no core binary provided. Use the `--dummy` option to generate an empty binary.")
+        }
+    };
+
+    let producers = if args.metadata.is_empty() {
+        None
+    } else {
+        Some(producers_from_args(&args.metadata)?)
+    };
+
+    let encoded = wit_component::metadata::encode(
+        &resolve,
+        world,
+        args.string_encoding.unwrap_or(StringEncoding::UTF8),
+        producers.as_ref(),
+    )?;
+
+    let section = wasm_encoder::CustomSection {
+        name: "component-type".into(),
+        data: encoded.into(),
+    };
+    core_binary.push(section.id());
+    section.encode(&mut core_binary);
+
+    write_output(Some(&args.output), &core_binary)
+}
+
+/// Create a component from a core module, applying any adapters
+pub(crate) fn new(args: NewArgs) -> Result<()> {
+    let binary = read_wasm(&args.core_module)?;
+
+    let mut encoder = ComponentEncoder::default()
+        .validate(true)
+        .module(&binary)
+        .context("failed to decode Wasm")?;
+
+    for adapt in args.adapt.iter() {
+        let (name, path) = match adapt.split_once('=') {
+            Some((name, path)) => (name.to_string(), PathBuf::from(path)),
+            None => {
+                let path = PathBuf::from(adapt);
+                let name = path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .with_context(|| format!("invalid adapter path [{adapt}]"))?;
+                (name, path)
+            }
+        };
+        let adapter = read_wasm(&path)?;
+        encoder = encoder
+            .adapter(&name, &adapter)
+            .with_context(|| format!("adding adapter [{name}]"))?;
+    }
+
+    let bytes = encoder
+        .encode()
+        .context("failed to encode a component from module")?;
+
+    write_output(Some(&args.output), &bytes)
+}
+
+/// Print the producers metadata of every module and component in a binary
+pub(crate) fn metadata_show(args: MetadataShowArgs) -> Result<()> {
+    let binary = read_wasm(&args.module)?;
+    let payload = wasm_metadata::Payload::from_binary(&binary)?;
+
+    let mut to_print: VecDeque<(usize, wasm_metadata::Payload)> = VecDeque::new();
+    to_print.push_back((0, payload));
+    while let Some((depth, payload)) = to_print.pop_front() {
+        let indent = "  ".repeat(depth);
+        let (kind, metadata) = match payload {
+            wasm_metadata::Payload::Component { metadata, children } => {
+                for child in children {
+                    to_print.push_back((depth + 1, child));
+                }
+                ("component", metadata)
+            }
+            wasm_metadata::Payload::Module(metadata) => ("module", metadata),
+        };
+
+        println!(
+            "{indent}[{kind}] {}",
+            metadata.name.as_deref().unwrap_or("<unnamed>")
+        );
+        if let Some(producers) = metadata.producers {
+            for (field, values) in producers.iter() {
+                for (name, version) in values.iter() {
+                    println!("{indent}  {field}: {name} {version}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add producers metadata to a binary
+pub(crate) fn metadata_add(args: MetadataAddArgs) -> Result<()> {
+    let binary = read_wasm(&args.module)?;
+    let producers = producers_from_args(&args.metadata)?;
+    let output = producers.add_to_wasm(&binary)?;
+    write_output(Some(&args.output), &output)
+}
+
+/// Build a [`Producers`] section from `field=name@version` arguments
+fn producers_from_args(metadata: &[(String, String, String)]) -> Result<Producers> {
+    let mut producers = Producers::default();
+    for (field, name, version) in metadata {
+        if !PRODUCERS_FIELDS.contains(&field.as_str()) {
+            bail!(
+                "'{field}' is not a valid field to embed in the metadata. Must be one of 'language', 'processed-by' or 'sdk'."
+            );
+        }
+        producers.add(field, name, version);
+    }
+    Ok(producers)
+}
+
+/// Parse a `field=name@version` producers argument
+fn parse_producer(s: &str) -> Result<(String, String, String)> {
+    let (field, data) = s.split_once('=').unwrap_or((s, ""));
+    let (name, version) = data.split_once('@').unwrap_or((data, ""));
+    Ok((field.into(), name.into(), version.into()))
+}
+
+/// Parse a string encoding as accepted on the command line
+fn parse_string_encoding(s: &str) -> Result<StringEncoding> {
+    match s {
+        "utf8" => Ok(StringEncoding::UTF8),
+        "utf16" => Ok(StringEncoding::UTF16),
+        "compact-utf16" => Ok(StringEncoding::CompactUTF16),
+        _ => bail!("invalid string encoding [{s}], expected 'utf8', 'utf16' or 'compact-utf16'"),
+    }
+}
@@ -0,0 +1,37 @@
+use structopt::StructOpt;
+
+mod cmd;
+
+/// Native Jco CLI, built directly on `js-component-bindgen` and `wit-component`
+#[derive(StructOpt)]
+#[structopt(name = "jco")]
+enum Cmd {
+    /// Transpile a WebAssembly Component to JS + core Wasm for JavaScript execution
+    Transpile(cmd::transpile::TranspileArgs),
+    /// Generate types for the given WIT
+    Types(cmd::types::TypesArgs),
+    /// Extract the WIT from a WebAssembly Component
+    Wit(cmd::wasm_tools::WitArgs),
+    /// Embed the component typing section into a core Wasm module
+    Embed(cmd::wasm_tools::EmbedArgs),
+    /// Create a WebAssembly component from a core Wasm module
+    New(cmd::wasm_tools::NewArgs),
+    /// Inspect or update the producers metadata of a Wasm binary
+    Metadata(cmd::wasm_tools::MetadataCmd),
+}
+
+fn main() -> anyhow::Result<()> {
+    match Cmd::from_args() {
+        Cmd::Transpile(args) => cmd::transpile::run(args),
+        Cmd::Types(args) => cmd::types::run(args),
+        Cmd::Wit(args) => cmd::wasm_tools::wit(args),
+        Cmd::Embed(args) => cmd::wasm_tools::embed(args),
+        Cmd::New(args) => cmd::wasm_tools::new(args),
+        Cmd::Metadata(cmd::wasm_tools::MetadataCmd::Show(args)) => {
+            cmd::wasm_tools::metadata_show(args)
+        }
+        Cmd::Metadata(cmd::wasm_tools::MetadataCmd::Add(args)) => {
+            cmd::wasm_tools::metadata_add(args)
+        }
+    }
+}
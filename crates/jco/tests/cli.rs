@@ -0,0 +1,207 @@
+//! Smoke tests for the native `jco` binary, run against the shared test fixtures
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Component used by the smoke tests
+const ADDER_COMPONENT: &str =
+    "packages/jco-transpile/test/fixtures/components/adder.component.wasm";
+
+/// Component in the WebAssembly text format used by the smoke tests
+const RESULTS_WAT_COMPONENT: &str =
+    "packages/jco-transpile/test/fixtures/components/runtime/explicit-results.wat";
+
+/// WIT used by the smoke tests
+const RESULT_WIT: &str = "packages/jco-transpile/test/fixtures/wit/bare-export-with-result.wit";
+
+/// Resolve a path relative to the root of the repository
+fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(path)
+}
+
+/// Create an empty scratch directory for a single test
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("jco-cli")
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("failed to create scratch dir");
+    dir
+}
+
+/// Run the `jco` binary with the given arguments, failing the test if it does not succeed
+fn jco(args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_jco"))
+        .args(args)
+        .output()
+        .expect("failed to run jco");
+    assert!(
+        output.status.success(),
+        "jco {} failed:\n{}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn transpile() {
+    let out_dir = scratch_dir("transpile");
+    let component = fixture(ADDER_COMPONENT);
+    jco(&[
+        "transpile",
+        component.to_str().unwrap(),
+        "-o",
+        out_dir.to_str().unwrap(),
+        "--name",
+        "adder",
+        "-q",
+    ]);
+
+    let js = fs::read_to_string(out_dir.join("adder.js")).unwrap();
+    assert!(js.contains("export {"));
+    assert!(out_dir.join("adder.d.ts").is_file());
+    assert!(out_dir.join("adder.core.wasm").is_file());
+}
+
+#[test]
+fn transpile_text_format() {
+    let out_dir = scratch_dir("transpile_text_format");
+    let component = fixture(RESULTS_WAT_COMPONENT);
+    jco(&[
+        "transpile",
+        component.to_str().unwrap(),
+        "-o",
+        out_dir.to_str().unwrap(),
+        "--name",
+        "results",
+        "-q",
+    ]);
+
+    let js = fs::read_to_string(out_dir.join("results.js")).unwrap();
+    assert!(js.contains("checked"));
+}
+
+#[test]
+fn transpile_guest() {
+    let out_dir = scratch_dir("transpile_guest");
+    let component = fixture(ADDER_COMPONENT);
+    jco(&[
+        "transpile",
+        component.to_str().unwrap(),
+        "-o",
+        out_dir.to_str().unwrap(),
+        "--name",
+        "adder",
+        "--guest",
+        "-q",
+    ]);
+
+    let dts = fs::read_to_string(out_dir.join("adder.d.ts")).unwrap();
+    assert!(dts.contains("declare module"));
+}
+
+#[test]
+fn types() {
+    let out_dir = scratch_dir("types");
+    let wit = fixture(RESULT_WIT);
+    jco(&[
+        "types",
+        wit.to_str().unwrap(),
+        "-o",
+        out_dir.to_str().unwrap(),
+        "-q",
+    ]);
+
+    let dts = fs::read_to_string(out_dir.join("component.d.ts")).unwrap();
+    assert!(dts.contains("export function foo("));
+}
+
+#[test]
+fn wit() {
+    let component = fixture(ADDER_COMPONENT);
+    let output = jco(&["wit", component.to_str().unwrap()]);
+
+    let wit = String::from_utf8(output.stdout).unwrap();
+    assert!(wit.contains("world "));
+    assert!(wit.contains("export docs:adder/add@0.1.0;"));
+}
+
+#[test]
+fn embed_and_new() {
+    let out_dir = scratch_dir("embed_and_new");
+    let wit = fixture(RESULT_WIT);
+    let embedded = out_dir.join("embedded.wasm");
+    let component = out_dir.join("component.wasm");
+
+    jco(&[
+        "embed",
+        "--dummy",
+        "--wit",
+        wit.to_str().unwrap(),
+        "-o",
+        embedded.to_str().unwrap(),
+    ]);
+    let core = fs::read(&embedded).unwrap();
+    assert!(wasmparser::Parser::is_core_wasm(&core));
+
+    jco(&[
+        "new",
+        embedded.to_str().unwrap(),
+        "-o",
+        component.to_str().unwrap(),
+    ]);
+    let bytes = fs::read(&component).unwrap();
+    assert!(wasmparser::Parser::is_component(&bytes));
+
+    let output = jco(&["wit", component.to_str().unwrap()]);
+    let wit = String::from_utf8(output.stdout).unwrap();
+    assert!(wit.contains("export foo: func(res: result<string, string>);"));
+}
+
+#[test]
+fn metadata() {
+    let out_dir = scratch_dir("metadata");
+    let component = fixture(ADDER_COMPONENT);
+    let output_path = out_dir.join("adder.wasm");
+
+    jco(&[
+        "metadata",
+        "add",
+        component.to_str().unwrap(),
+        "-o",
+        output_path.to_str().unwrap(),
+        "-m",
+        "processed-by=jco-smoke-test@1.2.3",
+    ]);
+
+    let output = jco(&["metadata", "show", output_path.to_str().unwrap()]);
+    let shown = String::from_utf8(output.stdout).unwrap();
+    assert!(shown.starts_with("[component]"));
+    assert!(shown.contains("processed-by: jco-smoke-test 1.2.3"));
+}
+
+#[test]
+fn transpile_commonjs() {
+    let out_dir = scratch_dir("transpile_commonjs");
+    let component = fixture(ADDER_COMPONENT);
+    jco(&[
+        "transpile",
+        component.to_str().unwrap(),
+        "-o",
+        out_dir.to_str().unwrap(),
+        "--name",
+        "adder",
+        "--module-format",
+        "commonjs",
+        "-q",
+    ]);
+
+    let js = fs::read_to_string(out_dir.join("adder.cjs")).unwrap();
+    assert!(js.contains("module.exports"));
+    assert!(out_dir.join("adder.d.cts").is_file());
+    assert!(!out_dir.join("adder.js").exists());
+}
@@ -4,6 +4,7 @@ use anyhow::{Context as _, Result};
 
 use js_component_bindgen::source::wit_parser::Resolve;
 use js_component_bindgen::transpile;
+use wit_component::DecodedWasm;
 
 /// Calls [`write!`] with the passed arguments and unwraps the result.
 ///
@@ -42,66 +43,69 @@ mod bindings {
     export!(JsComponentBindgenComponent);
 }
 use bindings::{
-    AsyncImportsExports, AsyncMode, BindingsMode, EnabledFeatureSet, ExportType, GenerateOptions,
-    InstantiationMode, Transpiled, TypeGenerationOptions, Wit,
+    AsyncDeterminism, AsyncImportsExports, AsyncMode, BindingsMode, ComponentInfo,
+    EnabledFeatureSet, ExportType, FunctionDirection, FunctionInfo, GenerateOptions,
+    ImportJournalMode, InstantiationMode, ModuleFormat, ResultImportsExports, ResultMode,
+    TranspileError, TranspileErrorDetails, Transpiled, TypeGenerationOptions, TypeRepresentations,
+    Wit,
 };
 
 /// Implementation of the `js-component-bindgen` world
 struct JsComponentBindgenComponent;
 
 impl bindings::Guest for JsComponentBindgenComponent {
-    fn generate(component: Vec<u8>, options: GenerateOptions) -> Result<Transpiled, String> {
-        let component = wat::parse_bytes(&component).map_err(|e| format!("{e}"))?;
-        let opts = js_component_bindgen::TranspileOpts::builder()
-            .name(options.name)
-            .no_typescript(options.no_typescript.unwrap_or(false))
-            .maybe_instantiation_mode(options.instantiation.map(Into::into))
-            .maybe_map(options.map.map(|map| map.into_iter().collect()))
-            .nodejs_compat_disabled(options.no_nodejs_compat.unwrap_or(false))
-            .base64_cutoff(options.base64_cutoff.unwrap_or(5000) as usize)
-            .tla_compat(
-                options
-                    .tla_compat
-                    .unwrap_or(options.compat.unwrap_or(false)),
-            )
-            .valid_lifting_optimization(options.valid_lifting_optimization.unwrap_or(false))
-            .tracing(options.tracing.unwrap_or(false))
-            .no_namespaced_exports(options.no_namespaced_exports.unwrap_or(false))
-            .multi_memory(options.multi_memory.unwrap_or(false))
-            .supports_wasm_exnref(options.bindgen_enable_wasm_exnref.unwrap_or(false))
-            .maybe_import_bindings(options.import_bindings.map(Into::into))
-            .guest(options.guest.unwrap_or(false))
-            .maybe_async_mode(options.async_mode.map(Into::into))
-            .strict(options.strict.unwrap_or(false))
-            .asmjs(options.asmjs.unwrap_or(false))
-            .build();
+    fn generate(
+        component: Vec<u8>,
+        options: GenerateOptions,
+    ) -> Result<Transpiled, TranspileError> {
+        let component = wat::parse_bytes(&component).map_err(|e| {
+            TranspileError::InvalidComponent(TranspileErrorDetails {
+                message: format!("{e}"),
+                item: None,
+            })
+        })?;
+        let opts = transpile_opts(options);
 
         let js_component_bindgen::Transpiled {
             files,
             imports,
             mut exports,
-        } = transpile(&component, opts)
-            .map_err(|e| format!("{e:?}"))
-            .map_err(|e| e.to_string())?;
+        } = transpile(&component, opts)?;
 
         Ok(Transpiled {
             files,
             imports,
             exports: exports
                 .drain(..)
-                .map(|(name, expt)| {
-                    (
-                        name,
-                        match expt {
-                            js_component_bindgen::ExportKind::LiftedFunction => {
-                                ExportType::Function
-                            }
-                            js_component_bindgen::ExportKind::Instance => ExportType::Instance,
-                            _ => panic!("Unexpected export type"),
-                        },
-                    )
-                })
-                .collect(),
+                .map(export_type)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn inspect(
+        component: Vec<u8>,
+        options: GenerateOptions,
+    ) -> Result<ComponentInfo, TranspileError> {
+        let component = wat::parse_bytes(&component).map_err(|e| {
+            TranspileError::InvalidComponent(TranspileErrorDetails {
+                message: format!("{e}"),
+                item: None,
+            })
+        })?;
+
+        let js_component_bindgen::ComponentInfo {
+            imports,
+            exports,
+            functions,
+        } = js_component_bindgen::inspect(&component, transpile_opts(options))?;
+
+        Ok(ComponentInfo {
+            imports,
+            exports: exports
+                .into_iter()
+                .map(export_type)
+                .collect::<Result<_, _>>()?,
+            functions: functions.into_iter().map(Into::into).collect(),
         })
     }
 
@@ -109,28 +113,25 @@ impl bindings::Guest for JsComponentBindgenComponent {
         name: String,
         opts: TypeGenerationOptions,
     ) -> Result<Vec<(String, Vec<u8>)>, String> {
-        let mut resolve = Resolve::default();
-
-        // Add features if specified
-        match opts.features {
-            Some(EnabledFeatureSet::List(ref features)) => {
-                for f in features.iter() {
-                    resolve.features.insert(f.to_string());
-                }
-            }
-            Some(EnabledFeatureSet::All) => {
-                resolve.all_features = true;
-            }
-            _ => {}
-        }
+        let world_string = opts.world.map(|world| world.to_string());
 
-        let ids = match opts.wit {
-            Wit::Source(source) => resolve
-                .push_str(format!("{name}.wit"), &source)
-                .map_err(|e| e.to_string())?,
+        let (resolve, world) = match opts.wit {
+            Wit::Source(source) => {
+                let mut resolve = Resolve::default();
+                enable_features(&mut resolve, opts.features.as_ref());
+                let id = resolve
+                    .push_str(format!("{name}.wit"), &source)
+                    .map_err(|e| e.to_string())?;
+                let world = resolve
+                    .select_world(&[id], world_string.as_deref())
+                    .map_err(|e| e.to_string())?;
+                (resolve, world)
+            }
             Wit::Path(path) => {
+                let mut resolve = Resolve::default();
+                enable_features(&mut resolve, opts.features.as_ref());
                 let path = PathBuf::from(path);
-                if path.is_dir() {
+                let id = if path.is_dir() {
                     resolve
                         .push_dir(&path)
                         .with_context(|| format!("reading WIT dir at [{}]", path.display()))
@@ -141,16 +142,40 @@ impl bindings::Guest for JsComponentBindgenComponent {
                         .push_file(&path)
                         .with_context(|| format!("reading WIT file at [{}]", path.display()))
                         .map_err(|e| format!("{e:?}"))?
-                }
+                };
+                let world = resolve
+                    .select_world(&[id], world_string.as_deref())
+                    .map_err(|e| e.to_string())?;
+                (resolve, world)
+            }
+            Wit::Binary(binary) => {
+                // Both binary-encoded WIT packages and full components are accepted,
+                // in the latter case the component's own world is used by default
+                let (mut resolve, id, world) = match wit_component::decode(&binary)
+                    .context("failed to decode WIT from binary")
+                    .map_err(|e| format!("{e:?}"))?
+                {
+                    DecodedWasm::WitPackage(resolve, id) => (resolve, id, None),
+                    DecodedWasm::Component(resolve, world) => {
+                        let id = resolve.worlds[world]
+                            .package
+                            .ok_or_else(|| "decoded world is missing a package".to_string())?;
+                        (resolve, id, Some(world))
+                    }
+                };
+                // Feature gates are checked during type generation, so they
+                // must be applied to the decoded resolve as well
+                enable_features(&mut resolve, opts.features.as_ref());
+                let world = match (world, world_string.as_deref()) {
+                    (Some(world), None) => world,
+                    (_, world_string) => resolve
+                        .select_world(&[id], world_string)
+                        .map_err(|e| e.to_string())?,
+                };
+                (resolve, world)
             }
-            Wit::Binary(_) => todo!("reading WIT from binary not yet implemented"),
         };
 
-        let world_string = opts.world.map(|world| world.to_string());
-        let world = resolve
-            .select_world(&[ids], world_string.as_deref())
-            .map_err(|e| e.to_string())?;
-
         let opts = js_component_bindgen::TranspileOpts::builder()
             .name("component".into())
             .no_typescript(false)
@@ -165,6 +190,12 @@ impl bindings::Guest for JsComponentBindgenComponent {
             .multi_memory(false)
             .guest(opts.guest.unwrap_or(false))
             .maybe_async_mode(opts.async_mode.map(Into::into))
+            .maybe_result_mode(opts.result_mode.map(Into::into))
+            .type_representations(
+                opts.type_representations
+                    .map(Into::into)
+                    .unwrap_or_default(),
+            )
             .strict(opts.strict.unwrap_or(false))
             .asmjs(false)
             .build();
@@ -177,6 +208,105 @@ impl bindings::Guest for JsComponentBindgenComponent {
     }
 }
 
+/// Enable the given WIT features on a [`Resolve`]
+fn enable_features(resolve: &mut Resolve, features: Option<&EnabledFeatureSet>) {
+    match features {
+        Some(EnabledFeatureSet::List(features)) => {
+            for f in features.iter() {
+                resolve.features.insert(f.to_string());
+            }
+        }
+        Some(EnabledFeatureSet::All) => {
+            resolve.all_features = true;
+        }
+        _ => {}
+    }
+}
+
+/// Build the transpilation options corresponding to the given generate options
+fn transpile_opts(options: GenerateOptions) -> js_component_bindgen::TranspileOpts {
+    js_component_bindgen::TranspileOpts::builder()
+        .name(options.name)
+        .no_typescript(options.no_typescript.unwrap_or(false))
+        .maybe_instantiation_mode(options.instantiation.map(Into::into))
+        .maybe_map(options.map.map(|map| map.into_iter().collect()))
+        .nodejs_compat_disabled(options.no_nodejs_compat.unwrap_or(false))
+        .base64_cutoff(options.base64_cutoff.unwrap_or(5000) as usize)
+        .tla_compat(
+            options
+                .tla_compat
+                .unwrap_or(options.compat.unwrap_or(false)),
+        )
+        .valid_lifting_optimization(options.valid_lifting_optimization.unwrap_or(false))
+        .tracing(options.tracing.unwrap_or(false))
+        .no_namespaced_exports(options.no_namespaced_exports.unwrap_or(false))
+        .multi_memory(options.multi_memory.unwrap_or(false))
+        .supports_wasm_exnref(options.bindgen_enable_wasm_exnref.unwrap_or(false))
+        .maybe_import_bindings(options.import_bindings.map(Into::into))
+        .guest(options.guest.unwrap_or(false))
+        .maybe_async_mode(options.async_mode.map(Into::into))
+        .async_determinism(
+            options
+                .async_determinism
+                .map(Into::into)
+                .unwrap_or_default(),
+        )
+        .maybe_import_journal(options.import_journal.map(Into::into))
+        .async_trace(options.async_trace.unwrap_or(false))
+        .maybe_async_deadlock_timeout(options.async_deadlock_timeout)
+        .maybe_result_mode(options.result_mode.map(Into::into))
+        .type_representations(
+            options
+                .type_representations
+                .map(Into::into)
+                .unwrap_or_default(),
+        )
+        .strict(options.strict.unwrap_or(false))
+        .asmjs(options.asmjs.unwrap_or(false))
+        .source_map(options.source_map.unwrap_or(false))
+        .shared_runtime(options.shared_runtime.unwrap_or(false))
+        .maybe_module_format(options.module_format.map(Into::into))
+        .source_phase_imports(options.source_phase_imports.unwrap_or(false))
+        .compile_core_to_js(options.compile_core_to_js.unwrap_or(false))
+        .build()
+}
+
+/// Convert an export produced by the transpiler into its WIT representation
+fn export_type(
+    (name, kind): (String, js_component_bindgen::ExportKind),
+) -> Result<(String, ExportType), TranspileError> {
+    let expt = match kind {
+        js_component_bindgen::ExportKind::LiftedFunction => ExportType::Function,
+        js_component_bindgen::ExportKind::Instance => ExportType::Instance,
+        js_component_bindgen::ExportKind::Module => ExportType::Module,
+        _ => {
+            return Err(TranspileError::UnsupportedFeature(TranspileErrorDetails {
+                message: "unexpected export type".into(),
+                item: Some(name),
+            }));
+        }
+    };
+    Ok((name, expt))
+}
+
+impl From<js_component_bindgen::FunctionInfo> for FunctionInfo {
+    fn from(value: js_component_bindgen::FunctionInfo) -> Self {
+        FunctionInfo {
+            direction: match value.direction {
+                js_component_bindgen::FunctionDirection::Import => FunctionDirection::Import,
+                js_component_bindgen::FunctionDirection::Export => FunctionDirection::Export,
+            },
+            interface: value.interface,
+            name: value.name,
+            params: value.params,
+            result: value.result,
+            is_async: value.is_async,
+            async_porcelain: value.async_porcelain,
+            resources: value.resources,
+        }
+    }
+}
+
 impl From<InstantiationMode> for js_component_bindgen::InstantiationMode {
     fn from(value: InstantiationMode) -> Self {
         match value {
@@ -186,6 +316,15 @@ impl From<InstantiationMode> for js_component_bindgen::InstantiationMode {
     }
 }
 
+impl From<ModuleFormat> for js_component_bindgen::ModuleFormat {
+    fn from(value: ModuleFormat) -> Self {
+        match value {
+            ModuleFormat::Esm => js_component_bindgen::ModuleFormat::Esm,
+            ModuleFormat::Commonjs => js_component_bindgen::ModuleFormat::CommonJs,
+        }
+    }
+}
+
 impl From<BindingsMode> for js_component_bindgen::BindingsMode {
     fn from(value: BindingsMode) -> Self {
         match value {
@@ -204,6 +343,76 @@ impl From<AsyncMode> for js_component_bindgen::AsyncMode {
             AsyncMode::Jspi(AsyncImportsExports { imports, exports }) => {
                 js_component_bindgen::AsyncMode::JavaScriptPromiseIntegration { imports, exports }
             }
+            AsyncMode::Asyncify(AsyncImportsExports { imports, exports }) => {
+                js_component_bindgen::AsyncMode::Asyncify { imports, exports }
+            }
+        }
+    }
+}
+
+impl From<AsyncDeterminism> for js_component_bindgen::AsyncDeterminismProfile {
+    fn from(value: AsyncDeterminism) -> Self {
+        match value {
+            AsyncDeterminism::Random => js_component_bindgen::AsyncDeterminismProfile::Random,
+            AsyncDeterminism::Deterministic => {
+                js_component_bindgen::AsyncDeterminismProfile::Deterministic
+            }
+            AsyncDeterminism::Seeded(seed) => {
+                js_component_bindgen::AsyncDeterminismProfile::Seeded { seed }
+            }
+        }
+    }
+}
+
+impl From<ImportJournalMode> for js_component_bindgen::ImportJournalMode {
+    fn from(value: ImportJournalMode) -> Self {
+        match value {
+            ImportJournalMode::Record => js_component_bindgen::ImportJournalMode::Record,
+            ImportJournalMode::Replay => js_component_bindgen::ImportJournalMode::Replay,
+        }
+    }
+}
+
+impl From<ResultMode> for js_component_bindgen::ResultMode {
+    fn from(value: ResultMode) -> Self {
+        match value {
+            ResultMode::Throw => js_component_bindgen::ResultMode::Throw,
+            ResultMode::Explicit(ResultImportsExports { imports, exports }) => {
+                js_component_bindgen::ResultMode::Explicit { imports, exports }
+            }
+        }
+    }
+}
+
+impl From<TypeRepresentations> for js_component_bindgen::TypeRepresentations {
+    fn from(value: TypeRepresentations) -> Self {
+        js_component_bindgen::TypeRepresentations {
+            numeric_enums: value.numeric_enums,
+            bitmask_flags: value.bitmask_flags,
+            class_variants: value.class_variants,
+        }
+    }
+}
+
+impl From<js_component_bindgen::TranspileError> for TranspileError {
+    fn from(err: js_component_bindgen::TranspileError) -> Self {
+        let details = TranspileErrorDetails {
+            message: err.message().to_string(),
+            item: err.item().map(String::from),
+        };
+        match err {
+            js_component_bindgen::TranspileError::UnsupportedFeature { .. } => {
+                TranspileError::UnsupportedFeature(details)
+            }
+            js_component_bindgen::TranspileError::Mapping { .. } => {
+                TranspileError::Mapping(details)
+            }
+            js_component_bindgen::TranspileError::TypeGeneration { .. } => {
+                TranspileError::TypeGeneration(details)
+            }
+            js_component_bindgen::TranspileError::InvalidComponent { .. } | _ => {
+                TranspileError::InvalidComponent(details)
+            }
         }
     }
 }
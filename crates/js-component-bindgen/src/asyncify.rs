@@ -0,0 +1,1057 @@
+//! Asyncify-style instrumentation of core modules, so that core Wasm can be
+//! suspended on a host `Promise` without JavaScript Promise Integration (JSPI).
+//!
+//! Instrumented modules import a mutable `i32` global (`$asyncify.state`) that
+//! is shared by all core instances, along with functions that push values to
+//! and pop values from a stack maintained by JS (`$asyncify.push_i32`,
+//! `$asyncify.pop_i32` and so on). The state is one of:
+//!
+//! * normal execution,
+//! * unwinding, set by JS when an async host import returns a `Promise`. Every
+//!   instrumented function on the call stack then saves its locals along with
+//!   the call it was suspended at, and returns immediately.
+//! * rewinding, set by JS once the `Promise` settles, after which the export is
+//!   called again. Every instrumented function restores its locals and skips
+//!   straight to the call it was suspended at, until the host import is called
+//!   again and returns the settled value.
+//!
+//! Any function that calls an import that may suspend, makes an indirect call,
+//! or calls such a function (transitively) is instrumented. Only the lowered
+//! async imports (and exports of other core instances that may call them) are
+//! considered to suspend, but as the targets of indirect calls are not known,
+//! every indirect call is assumed to suspend. Components calling their imports
+//! through a table (such as the shims generated by `wit-component`) therefore
+//! have every function reaching any of those imports instrumented, which grows
+//! each of them considerably. To be able to skip
+//! code while rewinding, instrumented functions are first flattened so that
+//! the Wasm value stack is empty between instructions: each slot of the value
+//! stack is held in a local, keyed by its depth and type. Straight-line code is
+//! then guarded on the normal state, and calls that may suspend are guarded on
+//! the normal state or being the call that is being rewound to.
+//!
+//! The JS side of this transform (driving the unwind/rewind loop from async
+//! exports, and suspending from async imports) is provided by the
+//! `Asyncify*` intrinsics.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
+use anyhow::{Context as _, Result, bail};
+use wasm_encoder::reencode::Reencode;
+use wasm_encoder::{
+    BlockType as EncodedBlockType, CodeSection, DataCountSection, DataSection, ElementSection,
+    EntityType, ExportSection, Function, FunctionSection, GlobalSection, ImportSection,
+    Instruction, MemorySection, Module as EncodedModule, StartSection, TableSection, TypeSection,
+};
+use wasmparser::{
+    BlockType, DataSectionReader, ElementSectionReader, ExportSectionReader, ExternalKind,
+    FrameKind, FuncType, FuncValidator, FunctionBody, FunctionSectionReader, GlobalSectionReader,
+    ImportSectionReader, MemorySectionReader, Operator, Parser, Payload, TableSectionReader,
+    TypeRef, ValType, ValidPayload, Validator, ValidatorResources, WasmFeatures,
+};
+
+/// Name of the import module providing the asyncify state and stack
+pub(crate) const IMPORT_MODULE: &str = "$asyncify";
+
+/// Types of values that can be saved to the asyncify stack, along with the
+/// names of the imports pushing and popping values of each
+pub(crate) const STACK_TYPES: [(ValType, &str, &str); 6] = [
+    (ValType::I32, "push_i32", "pop_i32"),
+    (ValType::I64, "push_i64", "pop_i64"),
+    (ValType::F32, "push_f32", "pop_f32"),
+    (ValType::F64, "push_f64", "pop_f64"),
+    (ValType::FUNCREF, "push_funcref", "pop_funcref"),
+    (ValType::EXTERNREF, "push_externref", "pop_externref"),
+];
+
+/// Value of `$asyncify.state` while unwinding the stack
+const UNWINDING: i32 = 1;
+
+/// Value of `$asyncify.state` while rewinding the stack
+const REWINDING: i32 = 2;
+
+/// Instrument a core module for asyncify, returning it unmodified if none of
+/// its functions can suspend
+///
+/// `suspends` determines whether the imported function with the given module
+/// and name may suspend.
+pub(crate) fn instrument<'a>(
+    wasm: &'a [u8],
+    suspends: impl Fn(&str, &str) -> bool,
+) -> Result<Cow<'a, [u8]>> {
+    let module = Module::parse(wasm, &suspends)?;
+    if !module.unwinds[module.imported_funcs as usize..]
+        .iter()
+        .any(|unwinds| *unwinds)
+    {
+        return Ok(Cow::Borrowed(wasm));
+    }
+    Ok(Cow::Owned(module.encode()?))
+}
+
+/// Names of the functions exported by a core module that may suspend, given
+/// whether each of its imported functions may suspend (as with [`instrument`])
+pub(crate) fn suspending_exports(
+    wasm: &[u8],
+    suspends: impl Fn(&str, &str) -> bool,
+) -> Result<HashSet<String>> {
+    let module = Module::parse(wasm, &suspends)?;
+    let mut exports = HashSet::new();
+    if let Some(section) = module.exports.clone() {
+        for export in section {
+            let export = export?;
+            if export.kind == ExternalKind::Func && module.unwinds[export.index as usize] {
+                exports.insert(export.name.to_string());
+            }
+        }
+    }
+    Ok(exports)
+}
+
+/// Structure of a core module, as needed to instrument it
+#[derive(Default)]
+struct Module<'a> {
+    types: Vec<FuncType>,
+    /// Type index of each function, imported functions first
+    funcs: Vec<u32>,
+    /// Whether each function may unwind, imported functions first
+    unwinds: Vec<bool>,
+    imported_funcs: u32,
+    imported_globals: u32,
+    imports: Option<ImportSectionReader<'a>>,
+    functions: Option<FunctionSectionReader<'a>>,
+    tables: Option<TableSectionReader<'a>>,
+    memories: Option<MemorySectionReader<'a>>,
+    globals: Option<GlobalSectionReader<'a>>,
+    exports: Option<ExportSectionReader<'a>>,
+    start: Option<u32>,
+    elements: Option<ElementSectionReader<'a>>,
+    data_count: Option<u32>,
+    data: Option<DataSectionReader<'a>>,
+    bodies: Vec<(FuncValidator<ValidatorResources>, FunctionBody<'a>)>,
+}
+
+impl<'a> Module<'a> {
+    fn parse(wasm: &'a [u8], suspends: &dyn Fn(&str, &str) -> bool) -> Result<Self> {
+        let mut module = Module::default();
+        let mut suspending_imports = Vec::new();
+        let mut validator = Validator::new_with_features(WasmFeatures::all());
+        for payload in Parser::new(0).parse_all(wasm) {
+            let payload = payload?;
+            if let ValidPayload::Func(func, body) = validator.payload(&payload)? {
+                module
+                    .bodies
+                    .push((func.into_validator(Default::default()), body));
+            }
+            match payload {
+                Payload::TypeSection(s) => {
+                    for ty in s.into_iter_err_on_gc_types() {
+                        module.types.push(ty?);
+                    }
+                }
+                Payload::ImportSection(s) => {
+                    for import in s.clone().into_imports() {
+                        let import = import?;
+                        match import.ty {
+                            TypeRef::Func(ty) => {
+                                module.funcs.push(ty);
+                                suspending_imports.push(suspends(import.module, import.name));
+                                module.imported_funcs += 1;
+                            }
+                            TypeRef::Global(_) => module.imported_globals += 1,
+                            TypeRef::Tag(_) => bail!("exception tags are not supported"),
+                            _ => {}
+                        }
+                    }
+                    module.imports = Some(s);
+                }
+                Payload::FunctionSection(s) => {
+                    for ty in s.clone() {
+                        module.funcs.push(ty?);
+                    }
+                    module.functions = Some(s);
+                }
+                Payload::TableSection(s) => module.tables = Some(s),
+                Payload::MemorySection(s) => module.memories = Some(s),
+                Payload::GlobalSection(s) => module.globals = Some(s),
+                Payload::ExportSection(s) => module.exports = Some(s),
+                Payload::StartSection { func, .. } => module.start = Some(func),
+                Payload::ElementSection(s) => module.elements = Some(s),
+                Payload::DataCountSection { count, .. } => module.data_count = Some(count),
+                Payload::DataSection(s) => module.data = Some(s),
+                Payload::TagSection(_) => bail!("exception tags are not supported"),
+                Payload::UnknownSection { id, .. } => bail!("unknown section {id}"),
+                _ => {}
+            }
+        }
+
+        // Determine the functions that may unwind, which are those calling
+        // imported functions that may suspend or making indirect calls,
+        // directly or through other local functions
+        let mut calls = Vec::with_capacity(module.bodies.len());
+        for (_, body) in module.bodies.iter() {
+            let mut callees = Vec::new();
+            let mut indirect = false;
+            let mut ops = body.get_operators_reader()?;
+            while !ops.eof() {
+                match ops.read()? {
+                    Operator::Call { function_index } | Operator::ReturnCall { function_index } => {
+                        callees.push(function_index)
+                    }
+                    Operator::CallIndirect { .. }
+                    | Operator::ReturnCallIndirect { .. }
+                    | Operator::CallRef { .. }
+                    | Operator::ReturnCallRef { .. } => indirect = true,
+                    _ => {}
+                }
+            }
+            calls.push((callees, indirect));
+        }
+        module.unwinds = vec![false; module.funcs.len()];
+        module.unwinds[..module.imported_funcs as usize].copy_from_slice(&suspending_imports);
+        loop {
+            let mut changed = false;
+            for (i, (callees, indirect)) in calls.iter().enumerate() {
+                let func = module.imported_funcs as usize + i;
+                if !module.unwinds[func]
+                    && (*indirect || callees.iter().any(|f| module.unwinds[*f as usize]))
+                {
+                    module.unwinds[func] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        Ok(module)
+    }
+
+    fn encode(mut self) -> Result<Vec<u8>> {
+        let mut reencoder = Remap {
+            imported_funcs: self.imported_funcs,
+            imported_globals: self.imported_globals,
+        };
+
+        // The types of the stack functions are added after the existing types,
+        // so that existing type indices are unchanged
+        let mut types = TypeSection::new();
+        for ty in self.types.iter() {
+            types.ty().func_type(&reencoder.func_type(ty.clone())?);
+        }
+        for (ty, _, _) in STACK_TYPES {
+            let ty = reencoder.val_type(ty)?;
+            types.ty().function([ty], []);
+            types.ty().function([], [ty]);
+        }
+
+        // The state global and stack functions are imported after the
+        // existing imports, shifting defined functions and globals
+        let mut imports = ImportSection::new();
+        if let Some(section) = self.imports.clone() {
+            reencoder.parse_import_section(&mut imports, section)?;
+        }
+        imports.import(
+            IMPORT_MODULE,
+            "state",
+            EntityType::Global(wasm_encoder::GlobalType {
+                val_type: wasm_encoder::ValType::I32,
+                mutable: true,
+                shared: false,
+            }),
+        );
+        for (i, (_, push, pop)) in STACK_TYPES.iter().enumerate() {
+            let ty = (self.types.len() + 2 * i) as u32;
+            imports.import(IMPORT_MODULE, push, EntityType::Function(ty));
+            imports.import(IMPORT_MODULE, pop, EntityType::Function(ty + 1));
+        }
+
+        let mut module = EncodedModule::new();
+        module.section(&types);
+        module.section(&imports);
+        if let Some(section) = self.functions.clone() {
+            let mut functions = FunctionSection::new();
+            reencoder.parse_function_section(&mut functions, section)?;
+            module.section(&functions);
+        }
+        if let Some(section) = self.tables.clone() {
+            let mut tables = TableSection::new();
+            reencoder.parse_table_section(&mut tables, section)?;
+            module.section(&tables);
+        }
+        if let Some(section) = self.memories.clone() {
+            let mut memories = MemorySection::new();
+            reencoder.parse_memory_section(&mut memories, section)?;
+            module.section(&memories);
+        }
+        if let Some(section) = self.globals.clone() {
+            let mut globals = GlobalSection::new();
+            reencoder.parse_global_section(&mut globals, section)?;
+            module.section(&globals);
+        }
+        if let Some(section) = self.exports.clone() {
+            let mut exports = ExportSection::new();
+            reencoder.parse_export_section(&mut exports, section)?;
+            module.section(&exports);
+        }
+        if let Some(start) = self.start {
+            module.section(&StartSection {
+                function_index: reencoder.function_index(start)?,
+            });
+        }
+        if let Some(section) = self.elements.clone() {
+            let mut elements = ElementSection::new();
+            reencoder.parse_element_section(&mut elements, section)?;
+            module.section(&elements);
+        }
+        if let Some(count) = self.data_count {
+            module.section(&DataCountSection { count });
+        }
+
+        let mut code = CodeSection::new();
+        let bodies = std::mem::take(&mut self.bodies);
+        for (i, (validator, body)) in bodies.into_iter().enumerate() {
+            let func = self.imported_funcs + i as u32;
+            if self.unwinds[func as usize] {
+                let f = FunctionInstrumenter::new(&self, validator, func)
+                    .instrument(&body)
+                    .with_context(|| format!("failed to instrument function {func}"))?;
+                code.function(&f);
+            } else {
+                reencoder.parse_function_body(&mut code, body)?;
+            }
+        }
+        module.section(&code);
+
+        if let Some(section) = self.data.clone() {
+            let mut data = DataSection::new();
+            reencoder.parse_data_section(&mut data, section)?;
+            module.section(&data);
+        }
+
+        Ok(module.finish())
+    }
+
+    /// Index of the `$asyncify.state` global
+    fn state_global(&self) -> u32 {
+        self.imported_globals
+    }
+
+    /// Index of the function pushing (or popping) values of the given type
+    fn stack_func(&self, ty: ValType, pop: bool) -> Result<u32> {
+        let Some(i) = STACK_TYPES.iter().position(|(t, _, _)| *t == ty) else {
+            bail!("values of type {ty} cannot be saved")
+        };
+        Ok(self.imported_funcs + 2 * i as u32 + u32::from(pop))
+    }
+}
+
+/// Reencoder shifting function and global indices past the added imports
+#[derive(Clone, Copy)]
+struct Remap {
+    imported_funcs: u32,
+    imported_globals: u32,
+}
+
+impl Reencode for Remap {
+    type Error = Infallible;
+
+    fn function_index(
+        &mut self,
+        func: u32,
+    ) -> Result<u32, wasm_encoder::reencode::Error<Self::Error>> {
+        if func < self.imported_funcs {
+            Ok(func)
+        } else {
+            Ok(func + 2 * STACK_TYPES.len() as u32)
+        }
+    }
+
+    fn global_index(
+        &mut self,
+        global: u32,
+    ) -> Result<u32, wasm_encoder::reencode::Error<Self::Error>> {
+        if global < self.imported_globals {
+            Ok(global)
+        } else {
+            Ok(global + 1)
+        }
+    }
+}
+
+/// Control frame of the function being instrumented
+struct Frame {
+    /// Index of the corresponding frame in the instrumented function
+    out: usize,
+    /// Whether the frame contains calls that may unwind, in which case code
+    /// within it is guarded rather than the frame as a whole
+    instrumented: bool,
+}
+
+/// Instrumentation of a single function that may unwind
+struct FunctionInstrumenter<'m, 'a> {
+    module: &'m Module<'a>,
+    reencoder: Remap,
+    validator: FuncValidator<ValidatorResources>,
+    func: u32,
+    /// Types of all locals, starting with the parameters
+    locals: Vec<ValType>,
+    /// Locals holding value stack slots, by depth and type
+    slots: HashMap<(usize, ValType), u32>,
+    /// Local holding the index of the call being rewound to
+    resume: u32,
+    /// Number of calls that may unwind so far
+    resume_points: i32,
+    /// Whether each block, loop and if of the function (in order) contains
+    /// calls that may unwind
+    blocks: Vec<bool>,
+    next_block: usize,
+    frames: Vec<Frame>,
+    /// Number of frames in the instrumented function
+    out_frames: usize,
+    /// Whether straight-line code guarded on the normal state is being emitted
+    region: bool,
+    /// Depth of the unreachable blocks being skipped
+    dead: u32,
+    body: Vec<Instruction<'a>>,
+}
+
+impl<'m, 'a> FunctionInstrumenter<'m, 'a> {
+    fn new(
+        module: &'m Module<'a>,
+        validator: FuncValidator<ValidatorResources>,
+        func: u32,
+    ) -> Self {
+        FunctionInstrumenter {
+            module,
+            reencoder: Remap {
+                imported_funcs: module.imported_funcs,
+                imported_globals: module.imported_globals,
+            },
+            validator,
+            func,
+            locals: Vec::new(),
+            slots: HashMap::new(),
+            resume: 0,
+            resume_points: 0,
+            blocks: Vec::new(),
+            next_block: 0,
+            // The function body is wrapped in a block that unwinding branches
+            // out of, and a block standing in for the function frame
+            frames: vec![Frame {
+                out: 1,
+                instrumented: true,
+            }],
+            out_frames: 2,
+            region: false,
+            dead: 0,
+            body: Vec::new(),
+        }
+    }
+
+    fn instrument(mut self, body: &FunctionBody<'a>) -> Result<Function> {
+        let ty = self.func_type(self.func).clone();
+        self.locals.extend(ty.params().iter().copied());
+        let mut groups = Vec::new();
+        let mut locals = body.get_locals_reader()?;
+        for _ in 0..locals.get_count() {
+            let offset = locals.original_position();
+            let (count, ty) = locals.read()?;
+            self.validator.define_locals(offset, count, ty)?;
+            self.locals.extend(std::iter::repeat_n(ty, count as usize));
+            groups.push((count, self.reencoder.val_type(ty)?));
+        }
+        let original_locals = self.locals.len();
+        self.resume = self.local(ValType::I32);
+
+        // Find the blocks that contain calls that may unwind
+        let mut open = Vec::new();
+        let mut ops = body.get_operators_reader()?;
+        while !ops.eof() {
+            let op = ops.read()?;
+            match op {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    open.push(self.blocks.len());
+                    self.blocks.push(false);
+                }
+                Operator::End => {
+                    open.pop();
+                }
+                Operator::Try { .. }
+                | Operator::TryTable { .. }
+                | Operator::Catch { .. }
+                | Operator::CatchAll
+                | Operator::Delegate { .. }
+                | Operator::Rethrow { .. } => bail!("exception handling is not supported"),
+                Operator::BrOnNull { .. }
+                | Operator::BrOnNonNull { .. }
+                | Operator::BrOnCast { .. }
+                | Operator::BrOnCastFail { .. } => bail!("unsupported operator {op:?}"),
+                _ => {}
+            }
+            if self.may_unwind(&op) {
+                for block in open.iter() {
+                    self.blocks[*block] = true;
+                }
+            }
+        }
+
+        let mut ops = body.get_operators_reader()?;
+        while !ops.eof() {
+            let (op, offset) = ops.read_with_offset()?;
+            if self.dead > 0 {
+                match op {
+                    Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                        self.dead += 1;
+                        self.next_block += 1;
+                    }
+                    Operator::End => self.dead -= 1,
+                    _ => {}
+                }
+                self.validator.op(offset, &op)?;
+                continue;
+            }
+            let unreachable = self.validator.get_control_frame(0).unwrap().unreachable;
+            if unreachable && !matches!(op, Operator::Else | Operator::End) {
+                if matches!(
+                    op,
+                    Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. }
+                ) {
+                    self.dead += 1;
+                    self.next_block += 1;
+                }
+                self.validator.op(offset, &op)?;
+                continue;
+            }
+            self.operator(offset, op)?;
+        }
+
+        // Return the results left by the function frame
+        let results = ty.results().to_vec();
+        self.load(0, &results);
+        self.body.push(Instruction::Return);
+        self.body.push(Instruction::End);
+
+        let saved = (0..self.locals.len() as u32)
+            .filter(|local| *local != self.resume)
+            .collect::<Vec<_>>();
+        for local in saved.iter() {
+            if !STACK_TYPES
+                .iter()
+                .any(|(ty, _, _)| *ty == self.locals[*local as usize])
+            {
+                bail!(
+                    "locals of type {} cannot be saved",
+                    self.locals[*local as usize]
+                );
+            }
+        }
+
+        for ty in self.locals[original_locals..].iter() {
+            groups.push((1, self.reencoder.val_type(*ty)?));
+        }
+        let mut f = Function::new(groups);
+        let state = self.module.state_global();
+
+        // When rewinding, restore the locals and the call to resume at
+        f.instruction(&Instruction::GlobalGet(state));
+        f.instruction(&Instruction::I32Const(REWINDING));
+        f.instruction(&Instruction::I32Eq);
+        f.instruction(&Instruction::If(EncodedBlockType::Empty));
+        f.instruction(&Instruction::Call(
+            self.module.stack_func(ValType::I32, true)?,
+        ));
+        f.instruction(&Instruction::LocalSet(self.resume));
+        for local in saved.iter().rev() {
+            let ty = self.locals[*local as usize];
+            f.instruction(&Instruction::Call(self.module.stack_func(ty, true)?));
+            f.instruction(&Instruction::LocalSet(*local));
+        }
+        f.instruction(&Instruction::End);
+
+        f.instruction(&Instruction::Block(EncodedBlockType::Empty));
+        f.instruction(&Instruction::Block(EncodedBlockType::Empty));
+        for instruction in self.body.iter() {
+            f.instruction(instruction);
+        }
+
+        // When unwinding, save the locals and the call to resume at
+        for local in saved.iter() {
+            let ty = self.locals[*local as usize];
+            f.instruction(&Instruction::LocalGet(*local));
+            f.instruction(&Instruction::Call(self.module.stack_func(ty, false)?));
+        }
+        f.instruction(&Instruction::LocalGet(self.resume));
+        f.instruction(&Instruction::Call(
+            self.module.stack_func(ValType::I32, false)?,
+        ));
+        for ty in results {
+            f.instruction(&match ty {
+                ValType::I32 => Instruction::I32Const(0),
+                ValType::I64 => Instruction::I64Const(0),
+                ValType::F32 => Instruction::F32Const(0.0.into()),
+                ValType::F64 => Instruction::F64Const(0.0.into()),
+                ValType::Ref(ty) if ty.is_nullable() => {
+                    Instruction::RefNull(self.reencoder.heap_type(ty.heap_type())?)
+                }
+                ty => bail!("results of type {ty} are not supported"),
+            });
+        }
+        f.instruction(&Instruction::End);
+        Ok(f)
+    }
+
+    fn operator(&mut self, offset: usize, op: Operator<'a>) -> Result<()> {
+        let height = self.validator.operand_stack_height() as usize;
+        match op {
+            Operator::Block { .. } | Operator::Loop { .. } => {
+                let instrumented = self.blocks[self.next_block];
+                self.next_block += 1;
+                if instrumented {
+                    self.close_region();
+                } else {
+                    self.open_region();
+                }
+                let block = if matches!(op, Operator::Loop { .. }) {
+                    Instruction::Loop(EncodedBlockType::Empty)
+                } else {
+                    Instruction::Block(EncodedBlockType::Empty)
+                };
+                self.validator.op(offset, &op)?;
+                self.body.push(block);
+                self.push_frame(instrumented);
+            }
+
+            Operator::If { blockty } => {
+                if let BlockType::FuncType(ty) = blockty
+                    && !self.module.types[ty as usize].params().is_empty()
+                {
+                    bail!("`if` blocks with parameters are not supported");
+                }
+                let instrumented = self.blocks[self.next_block];
+                self.next_block += 1;
+                let cond = self.slot(height - 1, ValType::I32);
+                self.open_region();
+                if instrumented {
+                    // The condition is kept in a dedicated local, as the slot
+                    // may be reused within the block by the time it is saved
+                    let local = self.local(ValType::I32);
+                    self.body.push(Instruction::LocalGet(cond));
+                    self.body.push(Instruction::LocalSet(local));
+                    self.close_region();
+                    self.body.push(Instruction::LocalGet(local));
+                } else {
+                    self.body.push(Instruction::LocalGet(cond));
+                }
+                self.validator.op(offset, &op)?;
+                self.body.push(Instruction::If(EncodedBlockType::Empty));
+                self.push_frame(instrumented);
+            }
+
+            Operator::Else => {
+                if self.frames.last().unwrap().instrumented {
+                    self.close_region();
+                }
+                self.validator.op(offset, &op)?;
+                self.body.push(Instruction::Else);
+            }
+
+            Operator::End => {
+                if self.frames.pop().unwrap().instrumented {
+                    self.close_region();
+                }
+                self.validator.op(offset, &op)?;
+                self.body.push(Instruction::End);
+                self.out_frames -= 1;
+            }
+
+            Operator::Br { relative_depth } => {
+                self.open_region();
+                self.branch(relative_depth, height)?;
+                self.validator.op(offset, &op)?;
+            }
+
+            Operator::BrIf { relative_depth } => {
+                self.open_region();
+                let cond = self.slot(height - 1, ValType::I32);
+                self.body.push(Instruction::LocalGet(cond));
+                self.body.push(Instruction::If(EncodedBlockType::Empty));
+                self.out_frames += 1;
+                self.branch(relative_depth, height - 1)?;
+                self.body.push(Instruction::End);
+                self.out_frames -= 1;
+                self.validator.op(offset, &op)?;
+            }
+
+            Operator::BrTable { ref targets } => {
+                // Each distinct target is branched to from the end of its own
+                // block, after copying the values it takes to its slots
+                self.open_region();
+                let mut unique = Vec::new();
+                let mut index = |depth: u32| match unique.iter().position(|d| *d == depth) {
+                    Some(i) => i as u32,
+                    None => {
+                        unique.push(depth);
+                        unique.len() as u32 - 1
+                    }
+                };
+                let mut table = Vec::new();
+                for target in targets.targets() {
+                    table.push(index(target?));
+                }
+                let default = index(targets.default());
+                let slot = self.slot(height - 1, ValType::I32);
+                for _ in unique.iter() {
+                    self.body.push(Instruction::Block(EncodedBlockType::Empty));
+                    self.out_frames += 1;
+                }
+                self.body.push(Instruction::LocalGet(slot));
+                self.body.push(Instruction::BrTable(table.into(), default));
+                for depth in unique {
+                    self.body.push(Instruction::End);
+                    self.out_frames -= 1;
+                    self.branch(depth, height - 1)?;
+                }
+                self.validator.op(offset, &op)?;
+            }
+
+            Operator::Return => {
+                self.open_region();
+                let results = self.func_type(self.func).results().to_vec();
+                self.load(height - results.len(), &results);
+                self.body.push(Instruction::Return);
+                self.validator.op(offset, &op)?;
+            }
+
+            // Values are left in their slots when dropped
+            Operator::Nop | Operator::Drop => self.validator.op(offset, &op)?,
+
+            Operator::Call { function_index } | Operator::ReturnCall { function_index }
+                if self.module.unwinds[function_index as usize] =>
+            {
+                let ty = self.func_type(function_index).clone();
+                self.call(offset, op, &ty)?;
+            }
+            Operator::CallIndirect { type_index, .. }
+            | Operator::ReturnCallIndirect { type_index, .. }
+            | Operator::CallRef { type_index }
+            | Operator::ReturnCallRef { type_index } => {
+                let ty = self.module.types[type_index as usize].clone();
+                self.call(offset, op, &ty)?;
+            }
+
+            op => {
+                let (pops, pushes) = op
+                    .operator_arity(&self.validator)
+                    .with_context(|| format!("unsupported operator {op:?}"))?;
+                let base = height - pops as usize;
+                let params = self.operand_types(pops as usize)?;
+                self.open_region();
+                self.load(base, &params);
+                self.validator.op(offset, &op)?;
+                let instruction = self.reencoder.instruction(op)?;
+                self.body.push(instruction);
+                let results = self.operand_types(pushes as usize)?;
+                self.store(base, &results);
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit a call that may unwind, which is resumed at when rewinding
+    fn call(&mut self, offset: usize, op: Operator<'a>, ty: &FuncType) -> Result<()> {
+        let (pops, _) = op
+            .operator_arity(&self.validator)
+            .context("unknown call arity")?;
+        let base = self.validator.operand_stack_height() as usize - pops as usize;
+        let params = self.operand_types(pops as usize)?;
+        let state = self.module.state_global();
+        let index = self.resume_points;
+        self.resume_points += 1;
+
+        self.close_region();
+        self.body.push(Instruction::GlobalGet(state));
+        self.body.push(Instruction::I32Eqz);
+        self.body.push(Instruction::LocalGet(self.resume));
+        self.body.push(Instruction::I32Const(index));
+        self.body.push(Instruction::I32Eq);
+        self.body.push(Instruction::I32Or);
+        self.body.push(Instruction::If(EncodedBlockType::Empty));
+        self.out_frames += 1;
+
+        self.load(base, &params);
+        self.validator.op(offset, &op)?;
+        // Tail calls are made as regular calls, so that they can be resumed
+        let (tail, call) = match op {
+            Operator::ReturnCall { function_index } => (true, Operator::Call { function_index }),
+            Operator::ReturnCallIndirect {
+                type_index,
+                table_index,
+            } => (
+                true,
+                Operator::CallIndirect {
+                    type_index,
+                    table_index,
+                },
+            ),
+            Operator::ReturnCallRef { type_index } => (true, Operator::CallRef { type_index }),
+            op => (false, op),
+        };
+        let instruction = self.reencoder.instruction(call)?;
+        self.body.push(instruction);
+        self.store(base, ty.results());
+
+        self.body.push(Instruction::GlobalGet(state));
+        self.body.push(Instruction::I32Const(UNWINDING));
+        self.body.push(Instruction::I32Eq);
+        self.body.push(Instruction::If(EncodedBlockType::Empty));
+        self.out_frames += 1;
+        self.body.push(Instruction::I32Const(index));
+        self.body.push(Instruction::LocalSet(self.resume));
+        self.body.push(Instruction::Br(self.out_frames as u32 - 1));
+        self.body.push(Instruction::End);
+        self.out_frames -= 1;
+
+        if tail {
+            self.load(base, ty.results());
+            self.body.push(Instruction::Return);
+        }
+        self.body.push(Instruction::End);
+        self.out_frames -= 1;
+        Ok(())
+    }
+
+    /// Emit a branch, copying the values taken by the target label from the
+    /// top of the stack (below `height`) to its slots
+    fn branch(&mut self, relative_depth: u32, height: usize) -> Result<()> {
+        let frame = self
+            .validator
+            .get_control_frame(relative_depth as usize)
+            .context("invalid branch depth")?;
+        let (kind, block_type, target) = (frame.kind, frame.block_type, frame.height);
+        let types = match block_type {
+            BlockType::Empty => Vec::new(),
+            BlockType::Type(ty) => {
+                if kind == FrameKind::Loop {
+                    Vec::new()
+                } else {
+                    vec![ty]
+                }
+            }
+            BlockType::FuncType(idx) => {
+                let ty = &self.module.types[idx as usize];
+                if kind == FrameKind::Loop {
+                    ty.params().to_vec()
+                } else {
+                    ty.results().to_vec()
+                }
+            }
+        };
+        let base = height - types.len();
+        if base != target {
+            for (i, ty) in types.iter().enumerate() {
+                let from = self.slot(base + i, *ty);
+                let to = self.slot(target + i, *ty);
+                self.body.push(Instruction::LocalGet(from));
+                self.body.push(Instruction::LocalSet(to));
+            }
+        }
+        let out = self.frames[self.frames.len() - 1 - relative_depth as usize].out;
+        self.body
+            .push(Instruction::Br((self.out_frames - 1 - out) as u32));
+        Ok(())
+    }
+
+    /// Whether an operator is a call that may unwind
+    fn may_unwind(&self, op: &Operator<'_>) -> bool {
+        match op {
+            Operator::Call { function_index } | Operator::ReturnCall { function_index } => {
+                self.module.unwinds[*function_index as usize]
+            }
+            Operator::CallIndirect { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::CallRef { .. }
+            | Operator::ReturnCallRef { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn func_type(&self, func: u32) -> &FuncType {
+        &self.module.types[self.module.funcs[func as usize] as usize]
+    }
+
+    /// Types of the top `count` operands, starting with the deepest
+    fn operand_types(&self, count: usize) -> Result<Vec<ValType>> {
+        (0..count)
+            .rev()
+            .map(|depth| match self.validator.get_operand_type(depth) {
+                Some(Some(ty)) => Ok(ty),
+                _ => bail!("operand of unknown type"),
+            })
+            .collect()
+    }
+
+    fn push_frame(&mut self, instrumented: bool) {
+        self.frames.push(Frame {
+            out: self.out_frames,
+            instrumented,
+        });
+        self.out_frames += 1;
+    }
+
+    /// Guard the following code on the normal state, unless it is already
+    /// guarded
+    fn open_region(&mut self) {
+        if !self.region && self.frames.last().unwrap().instrumented {
+            self.body
+                .push(Instruction::GlobalGet(self.module.state_global()));
+            self.body.push(Instruction::I32Eqz);
+            self.body.push(Instruction::If(EncodedBlockType::Empty));
+            self.out_frames += 1;
+            self.region = true;
+        }
+    }
+
+    fn close_region(&mut self) {
+        if self.region {
+            self.body.push(Instruction::End);
+            self.out_frames -= 1;
+            self.region = false;
+        }
+    }
+
+    fn local(&mut self, ty: ValType) -> u32 {
+        self.locals.push(ty);
+        self.locals.len() as u32 - 1
+    }
+
+    fn slot(&mut self, depth: usize, ty: ValType) -> u32 {
+        if let Some(local) = self.slots.get(&(depth, ty)) {
+            return *local;
+        }
+        let local = self.local(ty);
+        self.slots.insert((depth, ty), local);
+        local
+    }
+
+    fn load(&mut self, base: usize, types: &[ValType]) {
+        for (i, ty) in types.iter().enumerate() {
+            let slot = self.slot(base + i, *ty);
+            self.body.push(Instruction::LocalGet(slot));
+        }
+    }
+
+    fn store(&mut self, base: usize, types: &[ValType]) {
+        for (i, ty) in types.iter().enumerate().rev() {
+            let slot = self.slot(base + i, *ty);
+            self.body.push(Instruction::LocalSet(slot));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasm_encoder::{
+        CodeSection, EntityType, ExportKind, ExportSection, Function, FunctionSection,
+        ImportSection, Instruction, TypeSection, ValType::I32,
+    };
+
+    fn module(body: &[Instruction<'_>]) -> Vec<u8> {
+        let mut types = TypeSection::new();
+        types.ty().function([I32], [I32]);
+        let mut imports = ImportSection::new();
+        imports.import("env", "f", EntityType::Function(0));
+        let mut funcs = FunctionSection::new();
+        funcs.function(0);
+        let mut exports = ExportSection::new();
+        exports.export("g", ExportKind::Func, 1);
+        let mut func = Function::new([(1, I32)]);
+        for instr in body {
+            func.instruction(instr);
+        }
+        func.instruction(&Instruction::End);
+        let mut code = CodeSection::new();
+        code.function(&func);
+
+        let mut module = wasm_encoder::Module::new();
+        module
+            .section(&types)
+            .section(&imports)
+            .section(&funcs)
+            .section(&exports)
+            .section(&code);
+        module.finish()
+    }
+
+    #[test]
+    fn leaves_non_unwinding_modules() {
+        let wasm = module(&[
+            Instruction::LocalGet(0),
+            Instruction::I32Const(1),
+            Instruction::I32Add,
+        ]);
+        assert!(matches!(
+            instrument(&wasm, |_, _| true).unwrap(),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn instruments_import_calls() {
+        let wasm = module(&[
+            Instruction::Block(wasm_encoder::BlockType::Empty),
+            Instruction::Loop(wasm_encoder::BlockType::Empty),
+            Instruction::LocalGet(1),
+            Instruction::Call(0),
+            Instruction::LocalGet(1),
+            Instruction::I32Add,
+            Instruction::LocalTee(1),
+            Instruction::LocalGet(0),
+            Instruction::I32LtU,
+            Instruction::BrIf(0),
+            Instruction::End,
+            Instruction::End,
+            Instruction::LocalGet(1),
+        ]);
+        let out = instrument(&wasm, |_, _| true).unwrap();
+        assert!(matches!(out, Cow::Owned(_)));
+        Validator::new_with_features(WasmFeatures::all())
+            .validate_all(&out)
+            .unwrap();
+
+        let mut imports = Vec::new();
+        for payload in wasmparser::Parser::new(0).parse_all(&out) {
+            if let wasmparser::Payload::ImportSection(reader) = payload.unwrap() {
+                for import in reader.into_imports() {
+                    let import = import.unwrap();
+                    imports.push(format!("{}.{}", import.module, import.name));
+                }
+            }
+        }
+        assert_eq!(imports[0], "env.f");
+        assert_eq!(imports[1], "$asyncify.state");
+        assert_eq!(imports.len(), 2 + 2 * STACK_TYPES.len());
+    }
+
+    #[test]
+    fn leaves_calls_to_non_suspending_imports() {
+        let wasm = module(&[Instruction::LocalGet(0), Instruction::Call(0)]);
+        let suspends = |module: &str, name: &str| module == "env" && name == "f";
+        assert!(matches!(
+            instrument(&wasm, suspends).unwrap(),
+            Cow::Owned(_)
+        ));
+        assert!(suspending_exports(&wasm, suspends).unwrap().contains("g"));
+
+        let suspends = |_: &str, _: &str| false;
+        assert!(matches!(
+            instrument(&wasm, suspends).unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert!(suspending_exports(&wasm, suspends).unwrap().is_empty());
+    }
+}
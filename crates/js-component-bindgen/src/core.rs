@@ -27,26 +27,31 @@
 //! entries listed in [`AugmentedOp`], likely through the `DataView` class in
 //! JS.
 //!
+//! Active data segments for memory index 1 or greater can't be initialized by
+//! the module itself, so they are turned into empty passive segments and their
+//! contents are instead written by JS after instantiation (see
+//! [`Translation::data_inits`]).
+//!
 //! Note that at this time this module is not intended to be a complete and
 //! general purpose method of compiling multiple memories to single-memory
-//! modules. This does not handle all instructions that use memory for example,
-//! but only those that Wasmtime's adapter modules emits. It's possible to add
-//! support for more instructions but such support isn't required at this time.
-//! Examples of unsupported instructions are `i64.load8_u` and `memory.copy`.
-//! Additionally core wasm sections such as data sections and tables are not
-//! supported because, again, Wasmtime doesn't use it at this time.
+//! modules. Scalar loads and stores as well as the bulk memory instructions are
+//! supported, but SIMD and atomic instructions on memory index 1 or greater are
+//! not, and neither are modules that define (rather than import) their
+//! memories.
 
 use std::collections::{HashMap, HashSet};
 
 use anyhow::{Result, bail};
 use wasm_encoder::{
-    CodeSection, EntityType, ExportKind, ExportSection, Function, FunctionSection, ImportSection,
-    Module, TypeSection, reencode::Reencode,
+    CodeSection, DataCountSection, DataSection, ElementSection, EntityType, ExportKind,
+    ExportSection, Function, FunctionSection, GlobalSection, ImportSection, Module, StartSection,
+    TableSection, TagSection, TypeSection, reencode::Reencode,
 };
 use wasmparser::collections::IndexMap;
 use wasmparser::{
-    Export, ExternalKind, FunctionBody, Import, Parser, Payload, TypeRef, Validator, VisitOperator,
-    VisitSimdOperator, WasmFeatures,
+    DataKind, DataSectionReader, ElementSectionReader, Export, ExternalKind, FunctionBody,
+    GlobalSectionReader, Import, Operator, Parser, Payload, TableSectionReader, TagSectionReader,
+    TypeRef, Validator, VisitOperator, VisitSimdOperator, WasmFeatures,
 };
 use wasmtime_environ::component::CoreDef;
 use wasmtime_environ::{EntityIndex, MemoryIndex, ModuleTranslation, PrimaryMap};
@@ -58,13 +63,30 @@ pub enum Translation<'a> {
         wasm: Vec<u8>,
         imports_removed: HashSet<(String, String)>,
         imports_added: Vec<(String, String, MemoryIndex, AugmentedOp)>,
+        /// Contents of every data segment, by data index
+        data: Vec<&'a [u8]>,
+        /// Active data segments for removed memories, as the memory, offset and data index
+        data_inits: Vec<(MemoryIndex, u32, u32)>,
     },
 }
 
 #[derive(Debug)]
 pub enum AugmentedImport<'a> {
     CoreDef(&'a CoreDef),
-    Memory { mem: &'a CoreDef, op: AugmentedOp },
+    Memory {
+        mem: &'a CoreDef,
+        op: AugmentedOp,
+    },
+    /// `memory.copy` where at least one of the memories was removed
+    MemoryCopy {
+        dst: &'a CoreDef,
+        src: &'a CoreDef,
+    },
+    /// `memory.init` on a removed memory, along with the contents of the data segment
+    MemoryInit {
+        mem: &'a CoreDef,
+        data: &'a [u8],
+    },
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -75,15 +97,34 @@ pub enum AugmentedOp {
     I32Load16U,
     I32Load16S,
     I64Load,
+    I64Load8U,
+    I64Load8S,
+    I64Load16U,
+    I64Load16S,
+    I64Load32U,
+    I64Load32S,
     F32Load,
     F64Load,
     I32Store,
     I32Store8,
     I32Store16,
     I64Store,
+    I64Store8,
+    I64Store16,
+    I64Store32,
     F32Store,
     F64Store,
     MemorySize,
+    MemoryGrow,
+    MemoryFill,
+    /// Copy between two memories, given as their original memory indices
+    MemoryCopy {
+        dst: u32,
+        src: u32,
+    },
+    MemoryInit {
+        data: u32,
+    },
 }
 
 impl<'a> Translation<'a> {
@@ -115,7 +156,7 @@ impl<'a> Translation<'a> {
         }
 
         let mut augmenter = Augmenter {
-            translation: &translation,
+            wasm: translation.wasm,
             imports_removed: Default::default(),
             imports_added: Default::default(),
             imported_funcs: Default::default(),
@@ -126,12 +167,23 @@ impl<'a> Translation<'a> {
             local_funcs: Default::default(),
             types: Default::default(),
             augments: Default::default(),
+            tables: Default::default(),
+            globals: Default::default(),
+            elements: Default::default(),
+            tags: Default::default(),
+            start: Default::default(),
+            data_count: Default::default(),
+            data: Default::default(),
+            data_inits: Default::default(),
+            unsupported_op: Default::default(),
         };
         let wasm = augmenter.run()?;
         Ok(Translation::Augmented {
             wasm,
             imports_removed: augmenter.imports_removed,
             imports_added: augmenter.imports_added,
+            data: augmenter.data.iter().map(|data| data.data).collect(),
+            data_inits: augmenter.data_inits,
             original: translation,
         })
     }
@@ -169,32 +221,93 @@ impl<'a> Translation<'a> {
                 original,
                 imports_removed,
                 imports_added,
+                data,
                 ..
             } => {
                 let mut ret = Vec::new();
-                let mut memories: PrimaryMap<MemoryIndex, &'b CoreDef> = PrimaryMap::new();
                 for ((module, name, _), arg) in original.module.imports().zip(args) {
-                    if imports_removed.contains(&(module.to_string(), name.to_string())) {
-                        memories.push(arg);
-                    } else {
+                    if !imports_removed.contains(&(module.to_string(), name.to_string())) {
                         ret.push((module, name, AugmentedImport::CoreDef(arg)));
                     }
                 }
+                let (memory0, memories) = self.memories(args);
+                let memory = |idx: u32| match idx {
+                    0 => memory0.expect("missing memory 0"),
+                    idx => memories[MemoryIndex::from_u32(idx - 1)],
+                };
                 for (module, name, index, op) in imports_added {
-                    ret.push((
-                        module,
-                        name,
-                        AugmentedImport::Memory {
+                    let import = match *op {
+                        AugmentedOp::MemoryCopy { dst, src } => AugmentedImport::MemoryCopy {
+                            dst: memory(dst),
+                            src: memory(src),
+                        },
+                        AugmentedOp::MemoryInit { data: idx } => AugmentedImport::MemoryInit {
+                            mem: memories[*index],
+                            data: data[idx as usize],
+                        },
+                        op => AugmentedImport::Memory {
                             mem: memories[*index],
-                            op: *op,
+                            op,
                         },
-                    ));
+                    };
+                    ret.push((module, name, import));
                 }
                 ret
             }
         }
     }
 
+    /// Returns the active data segments that must be written by JS after
+    /// instantiation, as the memory they are written to along with the offset
+    /// and contents of the segment.
+    ///
+    /// These are the active data segments for memories that were removed by
+    /// augmentation, using the `args` as supplied to the original module.
+    pub fn data_inits<'b>(&'b self, args: &'b [CoreDef]) -> Vec<(&'b CoreDef, u32, &'b [u8])> {
+        let Translation::Augmented {
+            data, data_inits, ..
+        } = self
+        else {
+            return Vec::new();
+        };
+        let (_, memories) = self.memories(args);
+        data_inits
+            .iter()
+            .map(|(mem, offset, idx)| (memories[*mem], *offset, data[*idx as usize]))
+            .collect()
+    }
+
+    /// Returns the definitions of the memory that is kept in the module, if any,
+    /// and of the memories that were removed by augmentation.
+    fn memories<'b>(
+        &'b self,
+        args: &'b [CoreDef],
+    ) -> (Option<&'b CoreDef>, PrimaryMap<MemoryIndex, &'b CoreDef>) {
+        let (original, imports_removed) = match self {
+            Translation::Normal(translation) => (translation, None),
+            Translation::Augmented {
+                original,
+                imports_removed,
+                ..
+            } => (original, Some(imports_removed)),
+        };
+        let mut memory0 = None;
+        let mut memories = PrimaryMap::new();
+        for ((module, name, ty), arg) in original.module.imports().zip(args) {
+            if !matches!(ty, wasmtime_environ::EntityType::Memory(_)) {
+                continue;
+            }
+            if imports_removed
+                .is_some_and(|removed| removed.contains(&(module.to_string(), name.to_string())))
+            {
+                memories.push(arg);
+            } else {
+                memory0 = Some(arg);
+            }
+        }
+        (memory0, memories)
+    }
+
     /// Returns the exports of this module, which are not modified by
     /// augmentation.
     pub fn exports(&self) -> IndexMap<String, EntityIndex> {
@@ -213,7 +326,7 @@ impl<'a> Translation<'a> {
 }
 
 pub struct Augmenter<'a> {
-    translation: &'a ModuleTranslation<'a>,
+    wasm: &'a [u8],
     imports_removed: HashSet<(String, String)>,
     imports_added: Vec<(String, String, MemoryIndex, AugmentedOp)>,
     augments: HashMap<(MemoryIndex, AugmentedOp), u32>,
@@ -225,14 +338,27 @@ pub struct Augmenter<'a> {
     exports: Vec<Export<'a>>,
     local_funcs: Vec<FunctionBody<'a>>,
     local_func_tys: Vec<u32>,
+
+    tables: Option<TableSectionReader<'a>>,
+    globals: Option<GlobalSectionReader<'a>>,
+    elements: Option<ElementSectionReader<'a>>,
+    tags: Option<TagSectionReader<'a>>,
+    start: Option<u32>,
+    data_count: Option<u32>,
+    data: Vec<wasmparser::Data<'a>>,
+    data_inits: Vec<(MemoryIndex, u32, u32)>,
+
+    /// The first instruction found that accesses memory index 1 or greater, but
+    /// that can't be augmented
+    unsupported_op: Option<&'static str>,
 }
 
-impl Augmenter<'_> {
+impl<'a> Augmenter<'a> {
     fn run(&mut self) -> Result<Vec<u8>> {
         // The first step is to parse the input original wasm and learn about
         // its structure. This validates that all the sections are supported and
         // records various bits of information about the module within `self`.
-        for payload in Parser::new(0).parse_all(self.translation.wasm) {
+        for payload in Parser::new(0).parse_all(self.wasm) {
             match payload? {
                 Payload::TypeSection(s) => {
                     for grp in s.into_iter_err_on_gc_types() {
@@ -274,18 +400,17 @@ impl Augmenter<'_> {
                 Payload::CodeSectionEntry(body) => {
                     self.local_funcs.push(body);
                 }
+                Payload::TableSection(s) => self.tables = Some(s),
+                Payload::GlobalSection(s) => self.globals = Some(s),
+                Payload::ElementSection(s) => self.elements = Some(s),
+                Payload::TagSection(s) => self.tags = Some(s),
+                Payload::StartSection { func, .. } => self.start = Some(func),
+                Payload::DataCountSection { count, .. } => self.data_count = Some(count),
+                Payload::DataSection(s) => self.parse_data_section(s)?,
 
-                // NB: these sections are theoretically possible to handle but
-                // are not required at this time.
-                Payload::DataCountSection { .. }
-                | Payload::GlobalSection(_)
-                | Payload::TableSection(_)
-                | Payload::MemorySection(_)
-                | Payload::ElementSection(_)
-                | Payload::DataSection(_)
-                | Payload::StartSection { .. }
-                | Payload::TagSection(_)
-                | Payload::UnknownSection { .. } => {
+                // NB: memories defined by the module itself would be indexed after
+                // all imported memories, and can't be removed like those are.
+                Payload::MemorySection(_) | Payload::UnknownSection { .. } => {
                     bail!("unsupported section found in module using multiple memories")
                 }
 
@@ -320,12 +445,62 @@ impl Augmenter<'_> {
                 reader.visit_operator(&mut CollectMemOps(self))?;
             }
         }
+        if let Some(op) = self.unsupported_op {
+            bail!(
+                "unsupported instruction `{op}` found on a secondary memory in module using multiple memories"
+            );
+        }
+
+        // Active data segments are written from JS after instantiation, so the
+        // module's own start function would run before they are in place.
+        if self.start.is_some() && !self.data_inits.is_empty() {
+            bail!(
+                "start function with data segments for secondary memories found in module using multiple memories"
+            );
+        }
 
         // And now at the end we've got all the information for encoding so
         // begin that process.
         self.encode()
     }
 
+    /// Records the data segments of the module, determining which must be
+    /// written by JS as they target memory index 1 or greater.
+    fn parse_data_section(&mut self, section: DataSectionReader<'a>) -> Result<()> {
+        for data in section {
+            let data = data?;
+            if let DataKind::Active {
+                memory_index,
+                offset_expr,
+            } = &data.kind
+                && *memory_index > 0
+            {
+                let mut ops = offset_expr.get_operators_reader();
+                let offset = match (ops.read()?, ops.read()?) {
+                    (Operator::I32Const { value }, Operator::End) => value as u32,
+                    _ => bail!(
+                        "unsupported offset for data segment of secondary memory in module using multiple memories"
+                    ),
+                };
+                self.data_inits.push((
+                    MemoryIndex::from_u32(memory_index - 1),
+                    offset,
+                    self.data.len() as u32,
+                ));
+            }
+            self.data.push(data);
+        }
+        Ok(())
+    }
+
+    /// Records an instruction on memory index 1 or greater that can't be
+    /// augmented, which fails the augmentation once all functions are visited.
+    fn unsupported(&mut self, mem: u32, op: &'static str) {
+        if mem > 0 && self.unsupported_op.is_none() {
+            self.unsupported_op = Some(op);
+        }
+    }
+
     fn augment_op(&mut self, mem: u32, op: AugmentedOp) {
         // Memory 0 stays in the module and isn't removed, so no need to
         // register an augmentation.
@@ -439,11 +614,64 @@ impl Augmenter<'_> {
             code.function(&f);
         }
 
+        // The remaining sections are passed through, remapping function
+        // indices as usual. The only exception is active data segments for
+        // removed memories which are replaced by empty passive segments to keep
+        // the data index space intact, as their contents are written from JS.
+        let mut tables = TableSection::new();
+        if let Some(section) = self.tables.clone() {
+            reencoder.parse_table_section(&mut tables, section)?;
+        }
+        let mut tags = TagSection::new();
+        if let Some(section) = self.tags.clone() {
+            reencoder.parse_tag_section(&mut tags, section)?;
+        }
+        let mut globals = GlobalSection::new();
+        if let Some(section) = self.globals.clone() {
+            reencoder.parse_global_section(&mut globals, section)?;
+        }
+        let mut elements = ElementSection::new();
+        if let Some(section) = self.elements.clone() {
+            reencoder.parse_element_section(&mut elements, section)?;
+        }
+        let mut data = DataSection::new();
+        for datum in self.data.iter() {
+            match datum.kind {
+                DataKind::Active { memory_index, .. } if memory_index > 0 => {
+                    data.passive([]);
+                }
+                _ => reencoder.parse_data(&mut data, datum.clone())?,
+            }
+        }
+
         module.section(&types);
         module.section(&imports);
         module.section(&funcs);
+        if !tables.is_empty() {
+            module.section(&tables);
+        }
+        if !tags.is_empty() {
+            module.section(&tags);
+        }
+        if !globals.is_empty() {
+            module.section(&globals);
+        }
         module.section(&exports);
+        if let Some(start) = self.start {
+            module.section(&StartSection {
+                function_index: self.remap_func(start),
+            });
+        }
+        if !elements.is_empty() {
+            module.section(&elements);
+        }
+        if let Some(count) = self.data_count {
+            module.section(&DataCountSection { count });
+        }
         module.section(&code);
+        if !data.is_empty() {
+            module.section(&data);
+        }
 
         Ok(module.finish())
     }
@@ -519,23 +747,73 @@ macro_rules! define_visit {
         $self.0.augment_op($memarg.memory, AugmentedOp::I32Store16);
     };
 
+    (augment $self:ident I64Load8U $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Load8U);
+    };
+    (augment $self:ident I64Load8S $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Load8S);
+    };
+    (augment $self:ident I64Load16U $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Load16U);
+    };
+    (augment $self:ident I64Load16S $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Load16S);
+    };
+    (augment $self:ident I64Load32U $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Load32U);
+    };
+    (augment $self:ident I64Load32S $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Load32S);
+    };
+    (augment $self:ident I64Store8 $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Store8);
+    };
+    (augment $self:ident I64Store16 $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Store16);
+    };
+    (augment $self:ident I64Store32 $memarg:ident) => {
+        $self.0.augment_op($memarg.memory, AugmentedOp::I64Store32);
+    };
+
     (augment $self:ident MemorySize $mem:ident) => {
         $self.0.augment_op($mem, AugmentedOp::MemorySize);
     };
+    (augment $self:ident MemoryGrow $mem:ident) => {
+        $self.0.augment_op($mem, AugmentedOp::MemoryGrow);
+    };
+    (augment $self:ident MemoryFill $mem:ident) => {
+        $self.0.augment_op($mem, AugmentedOp::MemoryFill);
+    };
+    (augment $self:ident MemoryInit $data:ident $mem:ident) => {
+        $self.0.augment_op($mem, AugmentedOp::MemoryInit { data: $data });
+    };
+    // Copies are registered with whichever of the two memories was removed,
+    // or with the greater one if both were.
+    (augment $self:ident MemoryCopy $dst:ident $src:ident) => {
+        $self.0.augment_op($dst.max($src), AugmentedOp::MemoryCopy { dst: $dst, src: $src });
+    };
 
-    // Catch-all which asserts that none of the `$arg` looks like a memory
-    // index ty catch any missing instructions from the list above.
+    // Catch-all which records any of the `$arg` which looks like a memory
+    // index to catch instructions not augmented in the list above.
     (augment $self:ident $op:ident $($arg:ident)*) => {
         $(
-            define_visit!(assert_not_mem $op $arg);
+            define_visit!(unsupported $self $op $arg $arg);
         )*
     };
 
-    (assert_not_mem $op:ident mem) => {panic!(concat!("missed case ", stringify!($op)));};
-    (assert_not_mem $op:ident src_mem) => {panic!(concat!("missed case ", stringify!($op)));};
-    (assert_not_mem $op:ident dst_mem) => {panic!(concat!("missed case ", stringify!($op)));};
-    (assert_not_mem $op:ident memarg) => {panic!(concat!("missed case ", stringify!($op)));};
-    (assert_not_mem $op:ident $other:ident) => {};
+    (unsupported $self:ident $op:ident mem $mem:ident) => {
+        $self.0.unsupported($mem, stringify!($op));
+    };
+    (unsupported $self:ident $op:ident src_mem $mem:ident) => {
+        $self.0.unsupported($mem, stringify!($op));
+    };
+    (unsupported $self:ident $op:ident dst_mem $mem:ident) => {
+        $self.0.unsupported($mem, stringify!($op));
+    };
+    (unsupported $self:ident $op:ident memarg $memarg:ident) => {
+        $self.0.unsupported($memarg.memory, stringify!($op));
+    };
+    (unsupported $self:ident $op:ident $other:ident $arg:ident) => {};
 }
 
 impl<'a> VisitOperator<'a> for CollectMemOps<'_, 'a> {
@@ -562,7 +840,13 @@ impl AugmentedOp {
             | AugmentedOp::I32Load16S => {
                 section.ty().function([I32, I32], [I32]);
             }
-            AugmentedOp::I64Load => {
+            AugmentedOp::I64Load
+            | AugmentedOp::I64Load8U
+            | AugmentedOp::I64Load8S
+            | AugmentedOp::I64Load16U
+            | AugmentedOp::I64Load16S
+            | AugmentedOp::I64Load32U
+            | AugmentedOp::I64Load32S => {
                 section.ty().function([I32, I32], [I64]);
             }
             AugmentedOp::F32Load => {
@@ -577,7 +861,10 @@ impl AugmentedOp {
             AugmentedOp::I32Store | AugmentedOp::I32Store8 | AugmentedOp::I32Store16 => {
                 section.ty().function([I32, I32, I32], []);
             }
-            AugmentedOp::I64Store => {
+            AugmentedOp::I64Store
+            | AugmentedOp::I64Store8
+            | AugmentedOp::I64Store16
+            | AugmentedOp::I64Store32 => {
                 section.ty().function([I32, I64, I32], []);
             }
             AugmentedOp::F32Store => {
@@ -590,6 +877,18 @@ impl AugmentedOp {
             AugmentedOp::MemorySize => {
                 section.ty().function([], [I32]);
             }
+            AugmentedOp::MemoryGrow => {
+                section.ty().function([I32], [I32]);
+            }
+
+            // Bulk memory instructions take the same arguments as the original
+            // instruction, which are always a destination address followed by
+            // a value or source address and a length.
+            AugmentedOp::MemoryFill
+            | AugmentedOp::MemoryCopy { .. }
+            | AugmentedOp::MemoryInit { .. } => {
+                section.ty().function([I32, I32, I32], []);
+            }
         }
     }
 }
@@ -650,6 +949,24 @@ impl MultiMemoryCoreReencoder<'_, '_> {
             I64Load { memarg } if memarg.memory > 0 => {
                 self.augment(func, AugmentedOp::I64Load, memarg)
             }
+            I64Load8U { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Load8U, memarg)
+            }
+            I64Load8S { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Load8S, memarg)
+            }
+            I64Load16U { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Load16U, memarg)
+            }
+            I64Load16S { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Load16S, memarg)
+            }
+            I64Load32U { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Load32U, memarg)
+            }
+            I64Load32S { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Load32S, memarg)
+            }
             F32Load { memarg } if memarg.memory > 0 => {
                 self.augment(func, AugmentedOp::F32Load, memarg)
             }
@@ -668,17 +985,35 @@ impl MultiMemoryCoreReencoder<'_, '_> {
             I64Store { memarg } if memarg.memory > 0 => {
                 self.augment(func, AugmentedOp::I64Store, memarg)
             }
+            I64Store8 { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Store8, memarg)
+            }
+            I64Store16 { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Store16, memarg)
+            }
+            I64Store32 { memarg } if memarg.memory > 0 => {
+                self.augment(func, AugmentedOp::I64Store32, memarg)
+            }
             F32Store { memarg } if memarg.memory > 0 => {
                 self.augment(func, AugmentedOp::F32Store, memarg)
             }
             F64Store { memarg } if memarg.memory > 0 => {
                 self.augment(func, AugmentedOp::F64Store, memarg)
             }
-            MemorySize { mem } if mem > 0 => {
-                let mem = MemoryIndex::from_u32(mem - 1);
-                let function = self.augmenter.augments[&(mem, AugmentedOp::MemorySize)];
-                func.instruction(&wasm_encoder::Instruction::Call(function));
+            MemorySize { mem } if mem > 0 => self.call(func, mem, AugmentedOp::MemorySize),
+            MemoryGrow { mem } if mem > 0 => self.call(func, mem, AugmentedOp::MemoryGrow),
+            MemoryFill { mem } if mem > 0 => self.call(func, mem, AugmentedOp::MemoryFill),
+            MemoryInit { data_index, mem } if mem > 0 => {
+                self.call(func, mem, AugmentedOp::MemoryInit { data: data_index })
             }
+            MemoryCopy { dst_mem, src_mem } if dst_mem > 0 || src_mem > 0 => self.call(
+                func,
+                dst_mem.max(src_mem),
+                AugmentedOp::MemoryCopy {
+                    dst: dst_mem,
+                    src: src_mem,
+                },
+            ),
             operator => {
                 func.instruction(&self.instruction(operator)?);
             }
@@ -687,11 +1022,15 @@ impl MultiMemoryCoreReencoder<'_, '_> {
     }
 
     fn augment(&self, func: &mut Function, op: AugmentedOp, memarg: wasmparser::MemArg) {
-        use wasm_encoder::Instruction::{Call, I32Const};
+        use wasm_encoder::Instruction::I32Const;
 
-        let memory = MemoryIndex::from_u32(memarg.memory - 1);
-        let function = self.augmenter.augments[&(memory, op)];
         func.instruction(&I32Const(memarg.offset as i32));
-        func.instruction(&Call(function));
+        self.call(func, memarg.memory, op);
+    }
+
+    fn call(&self, func: &mut Function, mem: u32, op: AugmentedOp) {
+        let memory = MemoryIndex::from_u32(mem - 1);
+        let function = self.augmenter.augments[&(memory, op)];
+        func.instruction(&wasm_encoder::Instruction::Call(function));
     }
 }
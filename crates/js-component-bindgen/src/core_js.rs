@@ -0,0 +1,1575 @@
+//! Compilation of core Wasm modules to JS, for engines without `WebAssembly`.
+//!
+//! Each core module is translated to a JS module whose default export is a
+//! function taking an import object (in the same shape as the JS embedding API)
+//! and returning the exports of the new instance. Memories, tables and globals
+//! are represented by small JS objects that mirror the parts of
+//! `WebAssembly.Memory`, `WebAssembly.Table` and `WebAssembly.Global` used by
+//! the generated bindings (`buffer`, `get`/`set` and `value` respectively).
+//!
+//! Within compiled functions `i64` values are `BigInt`s, as they would be for
+//! calls crossing the JS embedding API. Exported functions using `i64` are
+//! legalized following the asm.js (`wasm2js`) conventions that the bindings
+//! already use for `--js` output: each `i64` parameter is passed as a pair of
+//! low/high `i32`s, and the high bits of an `i64` result are passed to the
+//! `env.setTempRet0` import. The unlegalized function is kept on the export
+//! under `__wasm`, so that compiled modules importing it can call it directly.
+//!
+//! Functions are translated instruction by instruction, with each slot of the
+//! Wasm value stack held in a JS local (`s{depth}`). Blocks and loops map to
+//! labeled statements, so branches become labeled `break`/`continue`s.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use anyhow::{Context as _, Result, bail};
+use base64::{Engine as _, engine::general_purpose};
+use wasmparser::{
+    BlockType, ConstExpr, DataKind, ElementItems, ElementKind, ExternalKind, FuncType, GlobalType,
+    MemArg, MemoryType, Operator, Parser, Payload, TableInit, TableType, TypeRef, ValType,
+};
+
+use crate::ModuleFormat;
+use crate::{uwrite, uwriteln};
+
+/// Helpers shared by all compiled functions of a module
+const PRELUDE: &str = r#"class $RuntimeError extends Error {
+  constructor(message) { super(message); this.name = 'RuntimeError'; }
+}
+function $trap(message) { throw new $RuntimeError(message); }
+
+class $Memory {
+  constructor(initial, maximum) {
+    this.maximum = maximum;
+    this.$set(new ArrayBuffer(initial * 65536));
+  }
+  $set(buffer) {
+    this.buffer = buffer;
+    this.dv = new DataView(buffer);
+    this.u8 = new Uint8Array(buffer);
+  }
+  size() { return this.buffer.byteLength / 65536; }
+  grow(delta) {
+    const old = this.size();
+    const size = old + (delta >>> 0);
+    if (size > (this.maximum === undefined ? 65536 : this.maximum)) return -1;
+    if (size !== old) {
+      let buffer;
+      try { buffer = new ArrayBuffer(size * 65536); } catch { return -1; }
+      new Uint8Array(buffer).set(this.u8);
+      this.$set(buffer);
+    }
+    return old;
+  }
+}
+
+class $Table {
+  constructor(initial, maximum, init = null) {
+    this.maximum = maximum;
+    this.a = new Array(initial).fill(init);
+  }
+  get length() { return this.a.length; }
+  get(i) {
+    i >>>= 0;
+    if (i >= this.a.length) $trap('table index out of bounds');
+    return this.a[i];
+  }
+  set(i, v) {
+    i >>>= 0;
+    if (i >= this.a.length) $trap('table index out of bounds');
+    this.a[i] = v;
+  }
+  grow(delta, init = null) {
+    const old = this.a.length;
+    delta >>>= 0;
+    if (old + delta > (this.maximum === undefined ? 0xffffffff : this.maximum)) return -1;
+    for (let i = 0; i < delta; i++) this.a.push(init);
+    return old;
+  }
+}
+
+function $import(imports, module, name) {
+  const ns = imports[module];
+  if (ns === undefined || ns === null) throw new TypeError(`missing import module '${module}'`);
+  return ns[name];
+}
+function $func(imports, module, name) {
+  const f = $import(imports, module, name);
+  if (typeof f !== 'function') throw new TypeError(`import '${module}' '${name}' is not a function`);
+  return f.__wasm || f;
+}
+function $global(imports, module, name) {
+  const g = $import(imports, module, name);
+  return typeof g === 'object' && g !== null ? g : { value: g };
+}
+
+function $data(s) {
+  const chars = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/';
+  const lookup = new Uint8Array(128);
+  for (let i = 0; i < 64; i++) lookup[chars.charCodeAt(i)] = i;
+  const out = new Uint8Array((s.length * 3) >> 2);
+  for (let i = 0, j = 0; i < s.length; i += 4) {
+    const a = lookup[s.charCodeAt(i)], b = lookup[s.charCodeAt(i + 1)];
+    const c = lookup[s.charCodeAt(i + 2)], d = lookup[s.charCodeAt(i + 3)];
+    out[j++] = (a << 2) | (b >> 4);
+    if (j < out.length) out[j++] = ((b & 15) << 4) | (c >> 2);
+    if (j < out.length) out[j++] = ((c & 3) << 6) | d;
+  }
+  return out;
+}
+const $empty = new Uint8Array(0);
+
+function $fill(m, d, v, n) {
+  d >>>= 0; n >>>= 0;
+  if (d + n > m.u8.length) $trap('out of bounds memory access');
+  m.u8.fill(v, d, d + n);
+}
+function $copy(dm, sm, d, s, n) {
+  d >>>= 0; s >>>= 0; n >>>= 0;
+  if (s + n > sm.u8.length || d + n > dm.u8.length) $trap('out of bounds memory access');
+  if (dm === sm) dm.u8.copyWithin(d, s, s + n);
+  else dm.u8.set(sm.u8.subarray(s, s + n), d);
+}
+function $minit(m, seg, d, s, n) {
+  d >>>= 0; s >>>= 0; n >>>= 0;
+  if (s + n > seg.length || d + n > m.u8.length) $trap('out of bounds memory access');
+  m.u8.set(seg.subarray(s, s + n), d);
+}
+
+function $tfill(t, d, v, n) {
+  d >>>= 0; n >>>= 0;
+  if (d + n > t.a.length) $trap('out of bounds table access');
+  t.a.fill(v, d, d + n);
+}
+function $tcopy(dt, st, d, s, n) {
+  d >>>= 0; s >>>= 0; n >>>= 0;
+  if (s + n > st.a.length || d + n > dt.a.length) $trap('out of bounds table access');
+  if (dt === st) dt.a.copyWithin(d, s, s + n);
+  else for (let i = 0; i < n; i++) dt.a[d + i] = st.a[s + i];
+}
+function $tinit(t, seg, d, s, n) {
+  d >>>= 0; s >>>= 0; n >>>= 0;
+  if (s + n > seg.length || d + n > t.a.length) $trap('out of bounds table access');
+  for (let i = 0; i < n; i++) t.a[d + i] = seg[s + i];
+}
+function $callee(t, i, sig) {
+  i >>>= 0;
+  if (i >= t.a.length) $trap('undefined element');
+  const f = t.a[i];
+  if (f === null || f === undefined) $trap('uninitialized element');
+  if (f.$t !== undefined && f.$t !== sig) $trap('indirect call type mismatch');
+  return f;
+}
+
+function $i32_div_s(a, b) {
+  if (b === 0) $trap('integer divide by zero');
+  if (a === -2147483648 && b === -1) $trap('integer overflow');
+  return (a / b) | 0;
+}
+function $i32_div_u(a, b) {
+  if (b === 0) $trap('integer divide by zero');
+  return ((a >>> 0) / (b >>> 0)) | 0;
+}
+function $i32_rem_s(a, b) {
+  if (b === 0) $trap('integer divide by zero');
+  return (a % b) | 0;
+}
+function $i32_rem_u(a, b) {
+  if (b === 0) $trap('integer divide by zero');
+  return ((a >>> 0) % (b >>> 0)) | 0;
+}
+const $i32_rotl = (a, b) => (a << b) | (a >>> ((32 - b) & 31));
+const $i32_rotr = (a, b) => (a >>> b) | (a << ((32 - b) & 31));
+const $i32_ctz = a => a === 0 ? 32 : 31 - Math.clz32(a & -a);
+function $i32_popcnt(a) {
+  a = a - ((a >>> 1) & 0x55555555);
+  a = (a & 0x33333333) + ((a >>> 2) & 0x33333333);
+  return Math.imul((a + (a >>> 4)) & 0x0f0f0f0f, 0x01010101) >>> 24;
+}
+
+const $i64_join = (lo, hi) => BigInt.asIntN(64, (BigInt(hi | 0) << 32n) | BigInt(lo >>> 0));
+function $i64_div_s(a, b) {
+  if (b === 0n) $trap('integer divide by zero');
+  if (a === -0x8000000000000000n && b === -1n) $trap('integer overflow');
+  return a / b;
+}
+function $i64_div_u(a, b) {
+  if (b === 0n) $trap('integer divide by zero');
+  return BigInt.asIntN(64, BigInt.asUintN(64, a) / BigInt.asUintN(64, b));
+}
+function $i64_rem_s(a, b) {
+  if (b === 0n) $trap('integer divide by zero');
+  return a % b;
+}
+function $i64_rem_u(a, b) {
+  if (b === 0n) $trap('integer divide by zero');
+  return BigInt.asIntN(64, BigInt.asUintN(64, a) % BigInt.asUintN(64, b));
+}
+function $i64_rotl(a, b) {
+  b &= 63n;
+  const u = BigInt.asUintN(64, a);
+  return BigInt.asIntN(64, (u << b) | (u >> ((64n - b) & 63n)));
+}
+function $i64_rotr(a, b) {
+  b &= 63n;
+  const u = BigInt.asUintN(64, a);
+  return BigInt.asIntN(64, (u >> b) | (u << ((64n - b) & 63n)));
+}
+function $i64_clz(a) {
+  const hi = Number((a >> 32n) & 0xffffffffn);
+  return BigInt(hi !== 0 ? Math.clz32(hi) : 32 + Math.clz32(Number(a & 0xffffffffn)));
+}
+function $i64_ctz(a) {
+  const lo = Number(a & 0xffffffffn) | 0;
+  if (lo !== 0) return BigInt($i32_ctz(lo));
+  return BigInt(32 + $i32_ctz(Number((a >> 32n) & 0xffffffffn) | 0));
+}
+const $i64_popcnt = a =>
+  BigInt($i32_popcnt(Number(a & 0xffffffffn) | 0) + $i32_popcnt(Number((a >> 32n) & 0xffffffffn) | 0));
+
+const $scratch = new DataView(new ArrayBuffer(8));
+function $copysign(a, b) {
+  $scratch.setFloat64(0, b);
+  return $scratch.getUint8(0) & 0x80 ? -Math.abs(a) : Math.abs(a);
+}
+function $nearest(a) {
+  if (!Number.isFinite(a) || Math.trunc(a) === a) return a;
+  return Math.abs(a - Math.trunc(a)) === 0.5 ? 2 * Math.round(a / 2) : Math.round(a);
+}
+function $f32_from_u64(u) {
+  const n = u.toString(2).length;
+  if (n <= 53) return Math.fround(Number(u));
+  const shift = BigInt(n - 53);
+  let top = u >> shift;
+  // Keep a sticky bit for the shifted out bits, so that rounding to f32 is not
+  // affected by an intermediate rounding to f64
+  if (top << shift !== u) top |= 1n;
+  return Math.fround(Number(top) * 2 ** (n - 53));
+}
+const $f32_from_i64 = a => a < 0n ? -$f32_from_u64(-a) : $f32_from_u64(a);
+
+function $trunc_i32_s(a) {
+  if (a !== a) $trap('invalid conversion to integer');
+  a = Math.trunc(a);
+  if (a < -2147483648 || a > 2147483647) $trap('integer overflow');
+  return a | 0;
+}
+function $trunc_i32_u(a) {
+  if (a !== a) $trap('invalid conversion to integer');
+  a = Math.trunc(a);
+  if (a < 0 || a > 4294967295) $trap('integer overflow');
+  return a | 0;
+}
+function $trunc_i64_s(a) {
+  if (a !== a) $trap('invalid conversion to integer');
+  a = Math.trunc(a);
+  if (a < -9223372036854775808 || a >= 9223372036854775808) $trap('integer overflow');
+  return BigInt(a);
+}
+function $trunc_i64_u(a) {
+  if (a !== a) $trap('invalid conversion to integer');
+  a = Math.trunc(a);
+  if (a < 0 || a >= 18446744073709551616) $trap('integer overflow');
+  return BigInt.asIntN(64, BigInt(a));
+}
+const $trunc_sat_i32_s = a =>
+  a !== a ? 0 : a <= -2147483648 ? -2147483648 : a >= 2147483647 ? 2147483647 : Math.trunc(a) | 0;
+const $trunc_sat_i32_u = a =>
+  a !== a || a <= 0 ? 0 : a >= 4294967295 ? -1 : Math.trunc(a) | 0;
+const $trunc_sat_i64_s = a =>
+  a !== a ? 0n : a <= -9223372036854775808 ? -0x8000000000000000n
+    : a >= 9223372036854775808 ? 0x7fffffffffffffffn : BigInt(Math.trunc(a));
+const $trunc_sat_i64_u = a =>
+  a !== a || a <= 0 ? 0n : a >= 18446744073709551616 ? -1n : BigInt.asIntN(64, BigInt(Math.trunc(a)));
+
+const $i32_from_f32 = a => ($scratch.setFloat32(0, a, true), $scratch.getInt32(0, true));
+const $f32_from_i32 = a => ($scratch.setInt32(0, a, true), $scratch.getFloat32(0, true));
+const $i64_from_f64 = a => ($scratch.setFloat64(0, a, true), $scratch.getBigInt64(0, true));
+const $f64_from_i64 = a => ($scratch.setBigInt64(0, a, true), $scratch.getFloat64(0, true));
+"#;
+
+/// Compile a core Wasm module to a JS module in the given format
+pub(crate) fn compile(wasm: &[u8], module_format: ModuleFormat) -> Result<String> {
+    let module = Module::parse(wasm)?;
+
+    let mut body = String::new();
+    module.render_instantiate(&mut body)?;
+
+    let mut out = String::new();
+    out.push_str("// Compiled from a core Wasm module by jco\n");
+    out.push_str(PRELUDE);
+    match module_format {
+        ModuleFormat::Esm => uwriteln!(out, "\nexport default function instantiate(imports) {{"),
+        ModuleFormat::CommonJs => {
+            uwriteln!(out, "\nmodule.exports = function instantiate(imports) {{")
+        }
+    }
+    out.push_str(&body);
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Structure of a core module, as needed to compile it
+#[derive(Default)]
+struct Module<'a> {
+    types: Vec<FuncType>,
+    /// Type index of each function, imported functions first
+    funcs: Vec<u32>,
+    memories: Vec<MemoryType>,
+    tables: Vec<(TableType, Option<ConstExpr<'a>>)>,
+    globals: Vec<(GlobalType, Option<ConstExpr<'a>>)>,
+    imports: Vec<(&'a str, &'a str, TypeRef)>,
+    exports: Vec<(&'a str, ExternalKind, u32)>,
+    elements: Vec<(ElementKind<'a>, ElementItems<'a>)>,
+    data: Vec<(DataKind<'a>, &'a [u8])>,
+    bodies: Vec<wasmparser::FunctionBody<'a>>,
+    start: Option<u32>,
+    imported_funcs: u32,
+    imported_globals: u32,
+}
+
+impl<'a> Module<'a> {
+    fn parse(wasm: &'a [u8]) -> Result<Self> {
+        let mut module = Module::default();
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::TypeSection(s) => {
+                    for ty in s.into_iter_err_on_gc_types() {
+                        module.types.push(ty?);
+                    }
+                }
+                Payload::ImportSection(s) => {
+                    for import in s.into_imports() {
+                        let import = import?;
+                        match import.ty {
+                            TypeRef::Func(ty) | TypeRef::FuncExact(ty) => {
+                                module.funcs.push(ty);
+                                module.imported_funcs += 1;
+                            }
+                            TypeRef::Memory(ty) => module.memories.push(ty),
+                            TypeRef::Table(ty) => module.tables.push((ty, None)),
+                            TypeRef::Global(ty) => {
+                                module.globals.push((ty, None));
+                                module.imported_globals += 1;
+                            }
+                            TypeRef::Tag(_) => bail!("exception handling is not supported"),
+                        }
+                        module.imports.push((import.module, import.name, import.ty));
+                    }
+                }
+                Payload::FunctionSection(s) => {
+                    for ty in s {
+                        module.funcs.push(ty?);
+                    }
+                }
+                Payload::TableSection(s) => {
+                    for table in s {
+                        let table = table?;
+                        let init = match table.init {
+                            TableInit::RefNull => None,
+                            TableInit::Expr(expr) => Some(expr),
+                        };
+                        module.tables.push((table.ty, init));
+                    }
+                }
+                Payload::MemorySection(s) => {
+                    for memory in s {
+                        module.memories.push(memory?);
+                    }
+                }
+                Payload::GlobalSection(s) => {
+                    for global in s {
+                        let global = global?;
+                        module.globals.push((global.ty, Some(global.init_expr)));
+                    }
+                }
+                Payload::ExportSection(s) => {
+                    for export in s {
+                        let export = export?;
+                        module
+                            .exports
+                            .push((export.name, export.kind, export.index));
+                    }
+                }
+                Payload::StartSection { func, .. } => module.start = Some(func),
+                Payload::ElementSection(s) => {
+                    for element in s {
+                        let element = element?;
+                        module.elements.push((element.kind, element.items));
+                    }
+                }
+                Payload::DataSection(s) => {
+                    for data in s {
+                        let data = data?;
+                        module.data.push((data.kind, data.data));
+                    }
+                }
+                Payload::CodeSectionEntry(body) => module.bodies.push(body),
+                Payload::TagSection(_) => bail!("exception handling is not supported"),
+                _ => {}
+            }
+        }
+
+        for memory in module.memories.iter() {
+            if memory.memory64 {
+                bail!("64-bit memories are not supported");
+            }
+            if memory.shared {
+                bail!("shared memories are not supported");
+            }
+            if memory.page_size_log2.is_some_and(|log2| log2 != 16) {
+                bail!("custom page sizes are not supported");
+            }
+        }
+        if module.tables.iter().any(|(ty, _)| ty.table64) {
+            bail!("64-bit tables are not supported");
+        }
+        Ok(module)
+    }
+
+    fn func_type(&self, func: u32) -> &FuncType {
+        &self.types[self.funcs[func as usize] as usize]
+    }
+
+    /// Whether a global is accessed through a `{ value }` object rather than a
+    /// plain JS local
+    fn global_is_object(&self, global: u32) -> bool {
+        global < self.imported_globals
+            || self
+                .exports
+                .iter()
+                .any(|(_, kind, idx)| *kind == ExternalKind::Global && *idx == global)
+    }
+
+    fn global_get(&self, global: u32) -> String {
+        if self.global_is_object(global) {
+            format!("g{global}.value")
+        } else {
+            format!("g{global}")
+        }
+    }
+
+    /// Render the body of the instantiation function
+    fn render_instantiate(&self, out: &mut String) -> Result<()> {
+        // Imports
+        let (mut funcs, mut memories, mut tables, mut globals) = (0, 0, 0, 0);
+        for (module, name, ty) in self.imports.iter() {
+            let (module, name) = (js_string(module), js_string(name));
+            match ty {
+                TypeRef::Func(_) | TypeRef::FuncExact(_) => {
+                    uwriteln!(out, "const f{funcs} = $func(imports, {module}, {name});");
+                    funcs += 1;
+                }
+                TypeRef::Memory(_) => {
+                    uwriteln!(
+                        out,
+                        "const m{memories} = $import(imports, {module}, {name});"
+                    );
+                    memories += 1;
+                }
+                TypeRef::Table(_) => {
+                    uwriteln!(out, "const t{tables} = $import(imports, {module}, {name});");
+                    tables += 1;
+                }
+                TypeRef::Global(_) => {
+                    uwriteln!(
+                        out,
+                        "const g{globals} = $global(imports, {module}, {name});"
+                    );
+                    globals += 1;
+                }
+                TypeRef::Tag(_) => unreachable!(),
+            }
+        }
+
+        // Memories, tables and globals defined by the module
+        for (i, memory) in self.memories.iter().enumerate().skip(memories) {
+            let maximum = memory
+                .maximum
+                .map(|max| max.to_string())
+                .unwrap_or_else(|| "undefined".into());
+            uwriteln!(
+                out,
+                "const m{i} = new $Memory({}, {maximum});",
+                memory.initial
+            );
+        }
+        for (i, (table, init)) in self.tables.iter().enumerate().skip(tables) {
+            let maximum = table
+                .maximum
+                .map(|max| max.to_string())
+                .unwrap_or_else(|| "undefined".into());
+            let init = match init {
+                Some(expr) => self.const_expr(expr)?,
+                None => "null".into(),
+            };
+            uwriteln!(
+                out,
+                "const t{i} = new $Table({}, {maximum}, {init});",
+                table.initial
+            );
+        }
+        for (i, (_, init)) in self.globals.iter().enumerate().skip(globals) {
+            let init = self.const_expr(init.as_ref().unwrap())?;
+            if self.global_is_object(i as u32) {
+                uwriteln!(out, "const g{i} = {{ value: {init} }};");
+            } else {
+                uwriteln!(out, "let g{i} = {init};");
+            }
+        }
+
+        // Element and data segments, dropped segments are replaced with empty ones
+        let mut referenced_funcs = BTreeSet::new();
+        for (i, (_, items)) in self.elements.iter().enumerate() {
+            let items = match items {
+                ElementItems::Functions(funcs) => funcs
+                    .clone()
+                    .into_iter()
+                    .map(|func| {
+                        let func = func?;
+                        referenced_funcs.insert(func);
+                        Ok(format!("f{func}"))
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                ElementItems::Expressions(_, exprs) => exprs
+                    .clone()
+                    .into_iter()
+                    .map(|expr| {
+                        let expr = expr?;
+                        let mut reader = expr.get_operators_reader();
+                        if let Operator::RefFunc { function_index } = reader.read()? {
+                            referenced_funcs.insert(function_index);
+                        }
+                        self.const_expr(&expr)
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            };
+            uwriteln!(out, "let e{i} = [{}];", items.join(", "));
+        }
+        for (i, (_, data)) in self.data.iter().enumerate() {
+            uwriteln!(
+                out,
+                "let d{i} = $data('{}');",
+                general_purpose::STANDARD_NO_PAD.encode(data)
+            );
+        }
+
+        // Functions, tagged with their signature when they may be called indirectly
+        for (i, body) in self.bodies.iter().enumerate() {
+            let func = self.imported_funcs + i as u32;
+            FunctionCompiler::new(self, func)
+                .compile(body, out)
+                .with_context(|| format!("failed to compile function {func}"))?;
+        }
+        for func in referenced_funcs.iter() {
+            if *func >= self.imported_funcs {
+                uwriteln!(out, "f{func}.$t = '{}';", signature(self.func_type(*func)));
+            }
+        }
+
+        // Initialize active segments, then run the start function
+        for (i, (kind, items)) in self.elements.iter().enumerate() {
+            match kind {
+                ElementKind::Active {
+                    table_index,
+                    offset_expr,
+                } => {
+                    let offset = self.const_expr(offset_expr)?;
+                    let len = match items {
+                        ElementItems::Functions(funcs) => funcs.count(),
+                        ElementItems::Expressions(_, exprs) => exprs.count(),
+                    };
+                    uwriteln!(
+                        out,
+                        "$tinit(t{}, e{i}, {offset}, 0, {len});",
+                        table_index.unwrap_or(0)
+                    );
+                    uwriteln!(out, "e{i} = [];");
+                }
+                ElementKind::Declared => uwriteln!(out, "e{i} = [];"),
+                ElementKind::Passive => {}
+            }
+        }
+        for (i, (kind, data)) in self.data.iter().enumerate() {
+            if let DataKind::Active {
+                memory_index,
+                offset_expr,
+            } = kind
+            {
+                let offset = self.const_expr(offset_expr)?;
+                uwriteln!(
+                    out,
+                    "$minit(m{memory_index}, d{i}, {offset}, 0, {});",
+                    data.len()
+                );
+                uwriteln!(out, "d{i} = $empty;");
+            }
+        }
+        if let Some(start) = self.start {
+            uwriteln!(out, "f{start}();");
+        }
+
+        // Exports
+        let mut exports = Vec::new();
+        for (name, kind, idx) in self.exports.iter() {
+            let value = match kind {
+                ExternalKind::Func | ExternalKind::FuncExact => self.export_func(*idx, out),
+                ExternalKind::Memory => format!("m{idx}"),
+                ExternalKind::Table => format!("t{idx}"),
+                ExternalKind::Global => format!("g{idx}"),
+                ExternalKind::Tag => bail!("exception handling is not supported"),
+            };
+            exports.push(format!("{}: {value}", js_string(name)));
+        }
+        uwriteln!(out, "return {{ {} }};", exports.join(", "));
+        Ok(())
+    }
+
+    /// Render the export of a function, legalizing it if it uses `i64`
+    fn export_func(&self, func: u32, out: &mut String) -> String {
+        let ty = self.func_type(func);
+        let i64_params = ty.params().contains(&ValType::I64);
+        let i64_result = ty.results() == [ValType::I64];
+        if !i64_params && !i64_result {
+            return format!("f{func}");
+        }
+
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for (i, ty) in ty.params().iter().enumerate() {
+            if *ty == ValType::I64 {
+                params.push(format!("p{i}"));
+                params.push(format!("p{i}h"));
+                args.push(format!("$i64_join(p{i}, p{i}h)"));
+            } else {
+                params.push(format!("p{i}"));
+                args.push(format!("p{i}"));
+            }
+        }
+        let raw_args = (0..ty.params().len())
+            .map(|i| format!("p{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        uwriteln!(out, "function x{func}({}) {{", params.join(", "));
+        if i64_params {
+            // Calls from the bindings that do not legalize (e.g. post-return functions)
+            uwriteln!(
+                out,
+                "if (arguments.length === {}) return f{func}({raw_args});",
+                ty.params().len()
+            );
+        }
+        if i64_result {
+            uwriteln!(out, "const r = f{func}({});", args.join(", "));
+            uwriteln!(out, "imports.env.setTempRet0(Number(r >> 32n) | 0);");
+            uwriteln!(out, "return Number(r & 0xffffffffn) | 0;");
+        } else {
+            uwriteln!(out, "return f{func}({});", args.join(", "));
+        }
+        uwriteln!(out, "}}");
+        uwriteln!(out, "x{func}.__wasm = f{func};");
+        format!("x{func}")
+    }
+
+    /// Render a constant expression
+    fn const_expr(&self, expr: &ConstExpr<'_>) -> Result<String> {
+        let mut stack = Vec::new();
+        let mut reader = expr.get_operators_reader();
+        loop {
+            let value = match reader.read()? {
+                Operator::End => break,
+                Operator::I32Const { value } => i32_const(value),
+                Operator::I64Const { value } => i64_const(value),
+                Operator::F32Const { value } => f32_const(value.bits()),
+                Operator::F64Const { value } => f64_const(value.bits()),
+                Operator::GlobalGet { global_index } => self.global_get(global_index),
+                Operator::RefNull { .. } => "null".into(),
+                Operator::RefFunc { function_index } => format!("f{function_index}"),
+                op @ (Operator::I32Add
+                | Operator::I32Sub
+                | Operator::I32Mul
+                | Operator::I64Add
+                | Operator::I64Sub
+                | Operator::I64Mul) => {
+                    let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+                    match op {
+                        Operator::I32Add => format!("(({a} + {b}) | 0)"),
+                        Operator::I32Sub => format!("(({a} - {b}) | 0)"),
+                        Operator::I32Mul => format!("Math.imul({a}, {b})"),
+                        Operator::I64Add => format!("BigInt.asIntN(64, {a} + {b})"),
+                        Operator::I64Sub => format!("BigInt.asIntN(64, {a} - {b})"),
+                        _ => format!("BigInt.asIntN(64, {a} * {b})"),
+                    }
+                }
+                op => bail!("unsupported constant expression operator {op:?}"),
+            };
+            stack.push(value);
+        }
+        stack.pop().context("empty constant expression")
+    }
+}
+
+/// Signature of a function type, used to check indirect calls
+fn signature(ty: &FuncType) -> String {
+    let letter = |ty: &ValType| match ty {
+        ValType::I32 => 'i',
+        ValType::I64 => 'j',
+        ValType::F32 => 'f',
+        ValType::F64 => 'd',
+        ValType::V128 => 'v',
+        ValType::Ref(_) => 'r',
+    };
+    let params = ty.params().iter().map(letter).collect::<String>();
+    let results = ty.results().iter().map(letter).collect::<String>();
+    format!("{params}:{results}")
+}
+
+/// Default (zero) value of a local of the given type
+fn default_value(ty: ValType) -> Result<&'static str> {
+    Ok(match ty {
+        ValType::I32 | ValType::F32 | ValType::F64 => "0",
+        ValType::I64 => "0n",
+        ValType::Ref(_) => "null",
+        ValType::V128 => bail!("SIMD is not supported"),
+    })
+}
+
+/// Quote a string as a JS string literal
+fn js_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('\'');
+    for c in s.chars() {
+        match c {
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 || c == '\u{2028}' || c == '\u{2029}' => {
+                uwrite!(out, "\\u{:04x}", c as u32)
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+fn i32_const(value: i32) -> String {
+    if value < 0 {
+        format!("({value})")
+    } else {
+        value.to_string()
+    }
+}
+
+fn i64_const(value: i64) -> String {
+    if value < 0 {
+        format!("({value}n)")
+    } else {
+        format!("{value}n")
+    }
+}
+
+fn f32_const(bits: u32) -> String {
+    let value = f32::from_bits(bits);
+    if value.is_nan() {
+        format!("$f32_from_i32({})", bits as i32)
+    } else {
+        float_literal(value as f64)
+    }
+}
+
+fn f64_const(bits: u64) -> String {
+    let value = f64::from_bits(bits);
+    if value.is_nan() {
+        format!("$f64_from_i64({})", i64_const(bits as i64))
+    } else {
+        float_literal(value)
+    }
+}
+
+fn float_literal(value: f64) -> String {
+    if value.is_infinite() {
+        if value < 0.0 {
+            "(-Infinity)".into()
+        } else {
+            "Infinity".into()
+        }
+    } else if value.is_sign_negative() {
+        format!("({value:?})")
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Kind of a control frame
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Function,
+    Block,
+    Loop,
+    If,
+}
+
+/// A control frame (function body, block, loop or if) being compiled
+struct Frame {
+    kind: FrameKind,
+    label: u32,
+    /// Height of the value stack when the frame was entered, excluding params
+    height: usize,
+    params: usize,
+    results: usize,
+}
+
+impl Frame {
+    /// Number of values carried by a branch to this frame
+    fn arity(&self) -> usize {
+        if self.kind == FrameKind::Loop {
+            self.params
+        } else {
+            self.results
+        }
+    }
+}
+
+/// Compiler for a single function body
+struct FunctionCompiler<'m, 'a> {
+    module: &'m Module<'a>,
+    func: u32,
+    src: String,
+    /// The value stack, where `None` is a value held in the slot for its depth
+    /// and `Some` is a constant that has not been materialized
+    stack: Vec<Option<String>>,
+    max_stack: usize,
+    frames: Vec<Frame>,
+    next_label: u32,
+    /// Whether the current code is unreachable, along with the number of
+    /// nested blocks entered since
+    unreachable: Option<u32>,
+}
+
+impl<'m, 'a> FunctionCompiler<'m, 'a> {
+    fn new(module: &'m Module<'a>, func: u32) -> Self {
+        Self {
+            module,
+            func,
+            src: String::new(),
+            stack: Vec::new(),
+            max_stack: 0,
+            frames: Vec::new(),
+            next_label: 0,
+            unreachable: None,
+        }
+    }
+
+    fn compile(mut self, body: &wasmparser::FunctionBody<'a>, out: &mut String) -> Result<()> {
+        let ty = self.module.func_type(self.func);
+        let params = ty.params().len();
+        let mut locals = Vec::new();
+        for local in body.get_locals_reader()? {
+            let (count, ty) = local?;
+            for _ in 0..count {
+                locals.push(format!(
+                    "l{} = {}",
+                    params + locals.len(),
+                    default_value(ty)?
+                ));
+            }
+        }
+
+        self.frames.push(Frame {
+            kind: FrameKind::Function,
+            label: 0,
+            height: 0,
+            params: 0,
+            results: ty.results().len(),
+        });
+        let mut reader = body.get_operators_reader()?;
+        while !self.frames.is_empty() {
+            let op = reader.read()?;
+            self.operator(op)?;
+        }
+
+        let params = (0..params)
+            .map(|i| format!("l{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        uwriteln!(out, "function f{}({params}) {{", self.func);
+        if !locals.is_empty() {
+            uwriteln!(out, "let {};", locals.join(", "));
+        }
+        if self.max_stack > 0 {
+            let slots = (0..self.max_stack)
+                .map(|i| format!("s{i}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            uwriteln!(out, "let {slots};");
+        }
+        out.push_str(&self.src);
+        uwriteln!(out, "}}");
+        Ok(())
+    }
+
+    /// Value at the given depth of the stack
+    fn value(&self, depth: usize) -> String {
+        self.stack[depth]
+            .clone()
+            .unwrap_or_else(|| format!("s{depth}"))
+    }
+
+    fn pop(&mut self) -> String {
+        let depth = self.stack.len() - 1;
+        let value = self.value(depth);
+        self.stack.pop();
+        value
+    }
+
+    fn pop_n(&mut self, n: usize) -> Vec<String> {
+        let base = self.stack.len() - n;
+        let values = (base..self.stack.len()).map(|d| self.value(d)).collect();
+        self.stack.truncate(base);
+        values
+    }
+
+    /// Push a value computed by the given expression
+    fn push(&mut self, expr: impl AsRef<str>) {
+        let depth = self.stack.len();
+        uwriteln!(self.src, "s{depth} = {};", expr.as_ref());
+        self.push_slots(1);
+    }
+
+    /// Push values that have already been written to their slots
+    fn push_slots(&mut self, n: usize) {
+        for _ in 0..n {
+            self.stack.push(None);
+        }
+        self.max_stack = self.max_stack.max(self.stack.len());
+    }
+
+    /// Push a constant, which is only written to a slot when required
+    fn push_const(&mut self, value: String) {
+        self.stack.push(Some(value));
+    }
+
+    /// Write the values from the given depth onwards to their slots
+    fn materialize(&mut self, from: usize) {
+        for depth in from..self.stack.len() {
+            if let Some(value) = self.stack[depth].take() {
+                uwriteln!(self.src, "s{depth} = {value};");
+            }
+        }
+        self.max_stack = self.max_stack.max(self.stack.len());
+    }
+
+    /// Render a branch to the frame at the given relative depth
+    fn branch(&mut self, relative_depth: u32) -> String {
+        let frame = &self.frames[self.frames.len() - 1 - relative_depth as usize];
+        let arity = frame.arity();
+        let base = self.stack.len() - arity;
+        if frame.kind == FrameKind::Function {
+            return self.return_values(base);
+        }
+
+        let mut out = String::new();
+        for i in 0..arity {
+            let value = self.value(base + i);
+            if value != format!("s{}", frame.height + i) {
+                uwrite!(out, "s{} = {value}; ", frame.height + i);
+            }
+        }
+        if frame.kind == FrameKind::Loop {
+            uwrite!(out, "continue L{};", frame.label);
+        } else {
+            uwrite!(out, "break L{};", frame.label);
+        }
+        out
+    }
+
+    /// Render a return of the values from the given depth onwards
+    fn return_values(&self, base: usize) -> String {
+        let values = (base..self.stack.len())
+            .map(|d| self.value(d))
+            .collect::<Vec<_>>();
+        match values.len() {
+            0 => "return;".into(),
+            1 => format!("return {};", values[0]),
+            _ => format!("return [{}];", values.join(", ")),
+        }
+    }
+
+    /// Params and results of a block type
+    fn block_arity(&self, ty: BlockType) -> (usize, usize) {
+        match ty {
+            BlockType::Empty => (0, 0),
+            BlockType::Type(_) => (0, 1),
+            BlockType::FuncType(idx) => {
+                let ty = &self.module.types[idx as usize];
+                (ty.params().len(), ty.results().len())
+            }
+        }
+    }
+
+    fn enter(&mut self, kind: FrameKind, ty: BlockType) -> u32 {
+        let (params, results) = self.block_arity(ty);
+        let height = self.stack.len() - params;
+        self.materialize(height);
+        let label = self.next_label;
+        self.next_label += 1;
+        self.frames.push(Frame {
+            kind,
+            label,
+            height,
+            params,
+            results,
+        });
+        label
+    }
+
+    /// Write out a call, pushing its results
+    fn call(&mut self, callee: String, ty: &FuncType, tail: bool) {
+        let args = self.pop_n(ty.params().len()).join(", ");
+        if tail {
+            uwriteln!(self.src, "return {callee}({args});");
+            return;
+        }
+        let depth = self.stack.len();
+        match ty.results().len() {
+            0 => uwriteln!(self.src, "{callee}({args});"),
+            1 => self.push(format!("{callee}({args})")),
+            n => {
+                let slots = (depth..depth + n)
+                    .map(|d| format!("s{d}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                uwriteln!(self.src, "[{slots}] = {callee}({args});");
+                self.push_slots(n);
+            }
+        }
+    }
+
+    /// Effective address of a memory access
+    fn address(&mut self, memarg: &MemArg) -> String {
+        let addr = self.pop();
+        if memarg.offset == 0 {
+            format!("{addr} >>> 0")
+        } else {
+            format!("({addr} >>> 0) + {}", memarg.offset)
+        }
+    }
+
+    fn load(&mut self, memarg: &MemArg, method: &str, wrap: Option<&str>) {
+        let addr = self.address(memarg);
+        let little_endian = if method.ends_with("Int8") {
+            ""
+        } else {
+            ", true"
+        };
+        let load = format!("m{}.dv.{method}({addr}{little_endian})", memarg.memory);
+        match wrap {
+            Some(wrap) => self.push(format!("{wrap}({load})")),
+            None => self.push(load),
+        }
+    }
+
+    fn store(&mut self, memarg: &MemArg, method: &str, value: impl Fn(String) -> String) {
+        let v = value(self.pop());
+        let addr = self.address(memarg);
+        let little_endian = if method.ends_with("Int8") {
+            ""
+        } else {
+            ", true"
+        };
+        uwriteln!(
+            self.src,
+            "m{}.dv.{method}({addr}, {v}{little_endian});",
+            memarg.memory
+        );
+    }
+
+    fn unary(&mut self, f: impl Fn(&str) -> String) {
+        let a = self.pop();
+        self.push(f(&a));
+    }
+
+    fn binary(&mut self, f: impl Fn(&str, &str) -> String) {
+        let b = self.pop();
+        let a = self.pop();
+        self.push(f(&a, &b));
+    }
+
+    fn operator(&mut self, op: Operator<'a>) -> Result<()> {
+        // Skip unreachable code until the end of the current frame
+        if let Some(nested) = self.unreachable {
+            match op {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    self.unreachable = Some(nested + 1);
+                    return Ok(());
+                }
+                Operator::Else | Operator::End if nested == 0 => {}
+                Operator::End => {
+                    self.unreachable = Some(nested - 1);
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+        }
+
+        match op {
+            Operator::Unreachable => {
+                uwriteln!(self.src, "$trap('unreachable');");
+                self.unreachable = Some(0);
+            }
+            Operator::Nop => {}
+            Operator::Block { blockty } => {
+                let label = self.enter(FrameKind::Block, blockty);
+                uwriteln!(self.src, "L{label}: {{");
+            }
+            Operator::Loop { blockty } => {
+                let label = self.enter(FrameKind::Loop, blockty);
+                uwriteln!(self.src, "L{label}: while (true) {{");
+            }
+            Operator::If { blockty } => {
+                let cond = self.pop();
+                let label = self.enter(FrameKind::If, blockty);
+                uwriteln!(self.src, "L{label}: if ({cond}) {{");
+            }
+            Operator::Else => {
+                let frame = self.frames.last().unwrap();
+                let (height, params) = (frame.height, frame.params);
+                if self.unreachable.is_none() {
+                    self.materialize(height);
+                }
+                uwriteln!(self.src, "}} else {{");
+                self.stack.truncate(height);
+                self.push_slots(params);
+                self.unreachable = None;
+            }
+            Operator::End => {
+                let frame = self.frames.pop().unwrap();
+                let reachable = self.unreachable.is_none();
+                self.unreachable = None;
+                if frame.kind == FrameKind::Function {
+                    if reachable {
+                        let ret = self.return_values(self.stack.len() - frame.results);
+                        uwriteln!(self.src, "{ret}");
+                    }
+                    return Ok(());
+                }
+                if reachable {
+                    self.materialize(frame.height);
+                    if frame.kind == FrameKind::Loop {
+                        uwriteln!(self.src, "break L{};", frame.label);
+                    }
+                }
+                uwriteln!(self.src, "}}");
+                self.stack.truncate(frame.height);
+                self.push_slots(frame.results);
+            }
+            Operator::Br { relative_depth } => {
+                let branch = self.branch(relative_depth);
+                uwriteln!(self.src, "{branch}");
+                self.unreachable = Some(0);
+            }
+            Operator::BrIf { relative_depth } => {
+                let cond = self.pop();
+                let branch = self.branch(relative_depth);
+                uwriteln!(self.src, "if ({cond}) {{ {branch} }}");
+            }
+            Operator::BrTable { targets } => {
+                let index = self.pop();
+                let mut cases: Vec<(u32, Vec<u32>)> = Vec::new();
+                for (i, target) in targets.targets().enumerate() {
+                    let target = target?;
+                    match cases.iter_mut().find(|(t, _)| *t == target) {
+                        Some((_, indices)) => indices.push(i as u32),
+                        None => cases.push((target, vec![i as u32])),
+                    }
+                }
+                uwriteln!(self.src, "switch ({index}) {{");
+                for (target, indices) in cases {
+                    if target == targets.default() {
+                        continue;
+                    }
+                    for i in indices {
+                        uwrite!(self.src, "case {i}: ");
+                    }
+                    let branch = self.branch(target);
+                    uwriteln!(self.src, "{{ {branch} }}");
+                }
+                let branch = self.branch(targets.default());
+                uwriteln!(self.src, "default: {{ {branch} }}");
+                uwriteln!(self.src, "}}");
+                self.unreachable = Some(0);
+            }
+            Operator::Return => {
+                let results = self.module.func_type(self.func).results().len();
+                let ret = self.return_values(self.stack.len() - results);
+                uwriteln!(self.src, "{ret}");
+                self.unreachable = Some(0);
+            }
+            Operator::Call { function_index } => {
+                let ty = self.module.func_type(function_index);
+                self.call(format!("f{function_index}"), ty, false);
+            }
+            Operator::ReturnCall { function_index } => {
+                let ty = self.module.func_type(function_index);
+                self.call(format!("f{function_index}"), ty, true);
+                self.unreachable = Some(0);
+            }
+            Operator::CallIndirect {
+                type_index,
+                table_index,
+            }
+            | Operator::ReturnCallIndirect {
+                type_index,
+                table_index,
+            } => {
+                let tail = matches!(op, Operator::ReturnCallIndirect { .. });
+                let ty = &self.module.types[type_index as usize];
+                let index = self.pop();
+                let callee = format!("$callee(t{table_index}, {index}, '{}')", signature(ty));
+                self.call(callee, ty, tail);
+                if tail {
+                    self.unreachable = Some(0);
+                }
+            }
+            Operator::Drop => {
+                self.pop();
+            }
+            Operator::Select | Operator::TypedSelect { .. } => {
+                let cond = self.pop();
+                self.binary(|a, b| format!("{cond} ? {a} : {b}"));
+            }
+            Operator::LocalGet { local_index } => self.push(format!("l{local_index}")),
+            Operator::LocalSet { local_index } => {
+                let value = self.pop();
+                uwriteln!(self.src, "l{local_index} = {value};");
+            }
+            Operator::LocalTee { local_index } => {
+                let value = self.value(self.stack.len() - 1);
+                uwriteln!(self.src, "l{local_index} = {value};");
+            }
+            Operator::GlobalGet { global_index } => {
+                let global = self.module.global_get(global_index);
+                self.push(global);
+            }
+            Operator::GlobalSet { global_index } => {
+                let value = self.pop();
+                let global = self.module.global_get(global_index);
+                uwriteln!(self.src, "{global} = {value};");
+            }
+
+            Operator::I32Load { memarg } => self.load(&memarg, "getInt32", None),
+            Operator::I64Load { memarg } => self.load(&memarg, "getBigInt64", None),
+            Operator::F32Load { memarg } => self.load(&memarg, "getFloat32", None),
+            Operator::F64Load { memarg } => self.load(&memarg, "getFloat64", None),
+            Operator::I32Load8S { memarg } => self.load(&memarg, "getInt8", None),
+            Operator::I32Load8U { memarg } => self.load(&memarg, "getUint8", None),
+            Operator::I32Load16S { memarg } => self.load(&memarg, "getInt16", None),
+            Operator::I32Load16U { memarg } => self.load(&memarg, "getUint16", None),
+            Operator::I64Load8S { memarg } => self.load(&memarg, "getInt8", Some("BigInt")),
+            Operator::I64Load8U { memarg } => self.load(&memarg, "getUint8", Some("BigInt")),
+            Operator::I64Load16S { memarg } => self.load(&memarg, "getInt16", Some("BigInt")),
+            Operator::I64Load16U { memarg } => self.load(&memarg, "getUint16", Some("BigInt")),
+            Operator::I64Load32S { memarg } => self.load(&memarg, "getInt32", Some("BigInt")),
+            Operator::I64Load32U { memarg } => self.load(&memarg, "getUint32", Some("BigInt")),
+            Operator::I32Store { memarg } => self.store(&memarg, "setInt32", |v| v),
+            Operator::I64Store { memarg } => self.store(&memarg, "setBigInt64", |v| v),
+            Operator::F32Store { memarg } => self.store(&memarg, "setFloat32", |v| v),
+            Operator::F64Store { memarg } => self.store(&memarg, "setFloat64", |v| v),
+            Operator::I32Store8 { memarg } => self.store(&memarg, "setInt8", |v| v),
+            Operator::I32Store16 { memarg } => self.store(&memarg, "setInt16", |v| v),
+            Operator::I64Store8 { memarg } => {
+                self.store(&memarg, "setUint8", |v| format!("Number({v} & 0xffn)"))
+            }
+            Operator::I64Store16 { memarg } => {
+                self.store(&memarg, "setUint16", |v| format!("Number({v} & 0xffffn)"))
+            }
+            Operator::I64Store32 { memarg } => self.store(&memarg, "setUint32", |v| {
+                format!("Number({v} & 0xffffffffn)")
+            }),
+            Operator::MemorySize { mem } => self.push(format!("m{mem}.size()")),
+            Operator::MemoryGrow { mem } => self.unary(|a| format!("m{mem}.grow({a})")),
+            Operator::MemoryFill { mem } => {
+                let [d, v, n] = self.pop_n(3).try_into().unwrap();
+                uwriteln!(self.src, "$fill(m{mem}, {d}, {v}, {n});");
+            }
+            Operator::MemoryCopy { dst_mem, src_mem } => {
+                let [d, s, n] = self.pop_n(3).try_into().unwrap();
+                uwriteln!(self.src, "$copy(m{dst_mem}, m{src_mem}, {d}, {s}, {n});");
+            }
+            Operator::MemoryInit { data_index, mem } => {
+                let [d, s, n] = self.pop_n(3).try_into().unwrap();
+                uwriteln!(self.src, "$minit(m{mem}, d{data_index}, {d}, {s}, {n});");
+            }
+            Operator::DataDrop { data_index } => {
+                uwriteln!(self.src, "d{data_index} = $empty;");
+            }
+
+            Operator::TableGet { table } => self.unary(|i| format!("t{table}.get({i})")),
+            Operator::TableSet { table } => {
+                let [i, v] = self.pop_n(2).try_into().unwrap();
+                uwriteln!(self.src, "t{table}.set({i}, {v});");
+            }
+            Operator::TableSize { table } => self.push(format!("t{table}.a.length")),
+            Operator::TableGrow { table } => {
+                self.binary(|init, n| format!("t{table}.grow({n}, {init})"))
+            }
+            Operator::TableFill { table } => {
+                let [d, v, n] = self.pop_n(3).try_into().unwrap();
+                uwriteln!(self.src, "$tfill(t{table}, {d}, {v}, {n});");
+            }
+            Operator::TableCopy {
+                dst_table,
+                src_table,
+            } => {
+                let [d, s, n] = self.pop_n(3).try_into().unwrap();
+                uwriteln!(
+                    self.src,
+                    "$tcopy(t{dst_table}, t{src_table}, {d}, {s}, {n});"
+                );
+            }
+            Operator::TableInit { elem_index, table } => {
+                let [d, s, n] = self.pop_n(3).try_into().unwrap();
+                uwriteln!(self.src, "$tinit(t{table}, e{elem_index}, {d}, {s}, {n});");
+            }
+            Operator::ElemDrop { elem_index } => uwriteln!(self.src, "e{elem_index} = [];"),
+            Operator::RefNull { .. } => self.push_const("null".into()),
+            Operator::RefIsNull => self.unary(|a| format!("+({a} === null)")),
+            Operator::RefFunc { function_index } => self.push_const(format!("f{function_index}")),
+
+            Operator::I32Const { value } => self.push_const(i32_const(value)),
+            Operator::I64Const { value } => self.push_const(i64_const(value)),
+            Operator::F32Const { value } => self.push_const(f32_const(value.bits())),
+            Operator::F64Const { value } => self.push_const(f64_const(value.bits())),
+
+            Operator::I32Eqz => self.unary(|a| format!("+({a} === 0)")),
+            Operator::I32Eq => self.binary(|a, b| format!("+({a} === {b})")),
+            Operator::I32Ne => self.binary(|a, b| format!("+({a} !== {b})")),
+            Operator::I32LtS => self.binary(|a, b| format!("+({a} < {b})")),
+            Operator::I32LtU => self.binary(|a, b| format!("+(({a} >>> 0) < ({b} >>> 0))")),
+            Operator::I32GtS => self.binary(|a, b| format!("+({a} > {b})")),
+            Operator::I32GtU => self.binary(|a, b| format!("+(({a} >>> 0) > ({b} >>> 0))")),
+            Operator::I32LeS => self.binary(|a, b| format!("+({a} <= {b})")),
+            Operator::I32LeU => self.binary(|a, b| format!("+(({a} >>> 0) <= ({b} >>> 0))")),
+            Operator::I32GeS => self.binary(|a, b| format!("+({a} >= {b})")),
+            Operator::I32GeU => self.binary(|a, b| format!("+(({a} >>> 0) >= ({b} >>> 0))")),
+            Operator::I64Eqz => self.unary(|a| format!("+({a} === 0n)")),
+            Operator::I64Eq => self.binary(|a, b| format!("+({a} === {b})")),
+            Operator::I64Ne => self.binary(|a, b| format!("+({a} !== {b})")),
+            Operator::I64LtS => self.binary(|a, b| format!("+({a} < {b})")),
+            Operator::I64LtU => {
+                self.binary(|a, b| format!("+(BigInt.asUintN(64, {a}) < BigInt.asUintN(64, {b}))"))
+            }
+            Operator::I64GtS => self.binary(|a, b| format!("+({a} > {b})")),
+            Operator::I64GtU => {
+                self.binary(|a, b| format!("+(BigInt.asUintN(64, {a}) > BigInt.asUintN(64, {b}))"))
+            }
+            Operator::I64LeS => self.binary(|a, b| format!("+({a} <= {b})")),
+            Operator::I64LeU => {
+                self.binary(|a, b| format!("+(BigInt.asUintN(64, {a}) <= BigInt.asUintN(64, {b}))"))
+            }
+            Operator::I64GeS => self.binary(|a, b| format!("+({a} >= {b})")),
+            Operator::I64GeU => {
+                self.binary(|a, b| format!("+(BigInt.asUintN(64, {a}) >= BigInt.asUintN(64, {b}))"))
+            }
+            Operator::F32Eq | Operator::F64Eq => self.binary(|a, b| format!("+({a} === {b})")),
+            Operator::F32Ne | Operator::F64Ne => self.binary(|a, b| format!("+({a} !== {b})")),
+            Operator::F32Lt | Operator::F64Lt => self.binary(|a, b| format!("+({a} < {b})")),
+            Operator::F32Gt | Operator::F64Gt => self.binary(|a, b| format!("+({a} > {b})")),
+            Operator::F32Le | Operator::F64Le => self.binary(|a, b| format!("+({a} <= {b})")),
+            Operator::F32Ge | Operator::F64Ge => self.binary(|a, b| format!("+({a} >= {b})")),
+
+            Operator::I32Clz => self.unary(|a| format!("Math.clz32({a})")),
+            Operator::I32Ctz => self.unary(|a| format!("$i32_ctz({a})")),
+            Operator::I32Popcnt => self.unary(|a| format!("$i32_popcnt({a})")),
+            Operator::I32Add => self.binary(|a, b| format!("({a} + {b}) | 0")),
+            Operator::I32Sub => self.binary(|a, b| format!("({a} - {b}) | 0")),
+            Operator::I32Mul => self.binary(|a, b| format!("Math.imul({a}, {b})")),
+            Operator::I32DivS => self.binary(|a, b| format!("$i32_div_s({a}, {b})")),
+            Operator::I32DivU => self.binary(|a, b| format!("$i32_div_u({a}, {b})")),
+            Operator::I32RemS => self.binary(|a, b| format!("$i32_rem_s({a}, {b})")),
+            Operator::I32RemU => self.binary(|a, b| format!("$i32_rem_u({a}, {b})")),
+            Operator::I32And => self.binary(|a, b| format!("{a} & {b}")),
+            Operator::I32Or => self.binary(|a, b| format!("{a} | {b}")),
+            Operator::I32Xor => self.binary(|a, b| format!("{a} ^ {b}")),
+            Operator::I32Shl => self.binary(|a, b| format!("{a} << {b}")),
+            Operator::I32ShrS => self.binary(|a, b| format!("{a} >> {b}")),
+            Operator::I32ShrU => self.binary(|a, b| format!("({a} >>> {b}) | 0")),
+            Operator::I32Rotl => self.binary(|a, b| format!("$i32_rotl({a}, {b})")),
+            Operator::I32Rotr => self.binary(|a, b| format!("$i32_rotr({a}, {b})")),
+            Operator::I64Clz => self.unary(|a| format!("$i64_clz({a})")),
+            Operator::I64Ctz => self.unary(|a| format!("$i64_ctz({a})")),
+            Operator::I64Popcnt => self.unary(|a| format!("$i64_popcnt({a})")),
+            Operator::I64Add => self.binary(|a, b| format!("BigInt.asIntN(64, {a} + {b})")),
+            Operator::I64Sub => self.binary(|a, b| format!("BigInt.asIntN(64, {a} - {b})")),
+            Operator::I64Mul => self.binary(|a, b| format!("BigInt.asIntN(64, {a} * {b})")),
+            Operator::I64DivS => self.binary(|a, b| format!("$i64_div_s({a}, {b})")),
+            Operator::I64DivU => self.binary(|a, b| format!("$i64_div_u({a}, {b})")),
+            Operator::I64RemS => self.binary(|a, b| format!("$i64_rem_s({a}, {b})")),
+            Operator::I64RemU => self.binary(|a, b| format!("$i64_rem_u({a}, {b})")),
+            Operator::I64And => self.binary(|a, b| format!("{a} & {b}")),
+            Operator::I64Or => self.binary(|a, b| format!("{a} | {b}")),
+            Operator::I64Xor => self.binary(|a, b| format!("{a} ^ {b}")),
+            Operator::I64Shl => {
+                self.binary(|a, b| format!("BigInt.asIntN(64, {a} << ({b} & 63n))"))
+            }
+            Operator::I64ShrS => self.binary(|a, b| format!("{a} >> ({b} & 63n)")),
+            Operator::I64ShrU => self.binary(|a, b| {
+                format!("BigInt.asIntN(64, BigInt.asUintN(64, {a}) >> ({b} & 63n))")
+            }),
+            Operator::I64Rotl => self.binary(|a, b| format!("$i64_rotl({a}, {b})")),
+            Operator::I64Rotr => self.binary(|a, b| format!("$i64_rotr({a}, {b})")),
+
+            Operator::F32Abs | Operator::F64Abs => self.unary(|a| format!("Math.abs({a})")),
+            Operator::F32Neg | Operator::F64Neg => self.unary(|a| format!("-{a}")),
+            Operator::F32Ceil | Operator::F64Ceil => self.unary(|a| format!("Math.ceil({a})")),
+            Operator::F32Floor | Operator::F64Floor => self.unary(|a| format!("Math.floor({a})")),
+            Operator::F32Trunc | Operator::F64Trunc => self.unary(|a| format!("Math.trunc({a})")),
+            Operator::F32Nearest | Operator::F64Nearest => self.unary(|a| format!("$nearest({a})")),
+            Operator::F32Sqrt => self.unary(|a| format!("Math.fround(Math.sqrt({a}))")),
+            Operator::F32Add => self.binary(|a, b| format!("Math.fround({a} + {b})")),
+            Operator::F32Sub => self.binary(|a, b| format!("Math.fround({a} - {b})")),
+            Operator::F32Mul => self.binary(|a, b| format!("Math.fround({a} * {b})")),
+            Operator::F32Div => self.binary(|a, b| format!("Math.fround({a} / {b})")),
+            Operator::F64Sqrt => self.unary(|a| format!("Math.sqrt({a})")),
+            Operator::F64Add => self.binary(|a, b| format!("{a} + {b}")),
+            Operator::F64Sub => self.binary(|a, b| format!("{a} - {b}")),
+            Operator::F64Mul => self.binary(|a, b| format!("{a} * {b}")),
+            Operator::F64Div => self.binary(|a, b| format!("{a} / {b}")),
+            Operator::F32Min | Operator::F64Min => {
+                self.binary(|a, b| format!("Math.min({a}, {b})"))
+            }
+            Operator::F32Max | Operator::F64Max => {
+                self.binary(|a, b| format!("Math.max({a}, {b})"))
+            }
+            Operator::F32Copysign | Operator::F64Copysign => {
+                self.binary(|a, b| format!("$copysign({a}, {b})"))
+            }
+
+            Operator::I32WrapI64 => self.unary(|a| format!("Number(BigInt.asIntN(32, {a}))")),
+            Operator::I32TruncF32S | Operator::I32TruncF64S => {
+                self.unary(|a| format!("$trunc_i32_s({a})"))
+            }
+            Operator::I32TruncF32U | Operator::I32TruncF64U => {
+                self.unary(|a| format!("$trunc_i32_u({a})"))
+            }
+            Operator::I64TruncF32S | Operator::I64TruncF64S => {
+                self.unary(|a| format!("$trunc_i64_s({a})"))
+            }
+            Operator::I64TruncF32U | Operator::I64TruncF64U => {
+                self.unary(|a| format!("$trunc_i64_u({a})"))
+            }
+            Operator::I32TruncSatF32S | Operator::I32TruncSatF64S => {
+                self.unary(|a| format!("$trunc_sat_i32_s({a})"))
+            }
+            Operator::I32TruncSatF32U | Operator::I32TruncSatF64U => {
+                self.unary(|a| format!("$trunc_sat_i32_u({a})"))
+            }
+            Operator::I64TruncSatF32S | Operator::I64TruncSatF64S => {
+                self.unary(|a| format!("$trunc_sat_i64_s({a})"))
+            }
+            Operator::I64TruncSatF32U | Operator::I64TruncSatF64U => {
+                self.unary(|a| format!("$trunc_sat_i64_u({a})"))
+            }
+            Operator::I64ExtendI32S => self.unary(|a| format!("BigInt({a})")),
+            Operator::I64ExtendI32U => self.unary(|a| format!("BigInt({a} >>> 0)")),
+            Operator::F32ConvertI32S => self.unary(|a| format!("Math.fround({a})")),
+            Operator::F32ConvertI32U => self.unary(|a| format!("Math.fround({a} >>> 0)")),
+            Operator::F32ConvertI64S => self.unary(|a| format!("$f32_from_i64({a})")),
+            Operator::F32ConvertI64U => {
+                self.unary(|a| format!("$f32_from_u64(BigInt.asUintN(64, {a}))"))
+            }
+            Operator::F32DemoteF64 => self.unary(|a| format!("Math.fround({a})")),
+            Operator::F64ConvertI32S | Operator::F64PromoteF32 => self.unary(|a| a.to_string()),
+            Operator::F64ConvertI32U => self.unary(|a| format!("{a} >>> 0")),
+            Operator::F64ConvertI64S => self.unary(|a| format!("Number({a})")),
+            Operator::F64ConvertI64U => self.unary(|a| format!("Number(BigInt.asUintN(64, {a}))")),
+            Operator::I32ReinterpretF32 => self.unary(|a| format!("$i32_from_f32({a})")),
+            Operator::I64ReinterpretF64 => self.unary(|a| format!("$i64_from_f64({a})")),
+            Operator::F32ReinterpretI32 => self.unary(|a| format!("$f32_from_i32({a})")),
+            Operator::F64ReinterpretI64 => self.unary(|a| format!("$f64_from_i64({a})")),
+            Operator::I32Extend8S => self.unary(|a| format!("({a} << 24) >> 24")),
+            Operator::I32Extend16S => self.unary(|a| format!("({a} << 16) >> 16")),
+            Operator::I64Extend8S => self.unary(|a| format!("BigInt.asIntN(8, {a})")),
+            Operator::I64Extend16S => self.unary(|a| format!("BigInt.asIntN(16, {a})")),
+            Operator::I64Extend32S => self.unary(|a| format!("BigInt.asIntN(32, {a})")),
+
+            op => bail!("unsupported operator {op:?}"),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wasm_encoder::{
+        CodeSection, ExportKind, ExportSection, Function, FunctionSection, Instruction, TypeSection,
+    };
+
+    fn module(
+        params: &[wasm_encoder::ValType],
+        results: &[wasm_encoder::ValType],
+        body: &[Instruction<'_>],
+    ) -> Vec<u8> {
+        let mut types = TypeSection::new();
+        types
+            .ty()
+            .function(params.iter().copied(), results.iter().copied());
+        let mut funcs = FunctionSection::new();
+        funcs.function(0);
+        let mut exports = ExportSection::new();
+        exports.export("f", ExportKind::Func, 0);
+        let mut func = Function::new([]);
+        for instr in body {
+            func.instruction(instr);
+        }
+        func.instruction(&Instruction::End);
+        let mut code = CodeSection::new();
+        code.function(&func);
+
+        let mut module = wasm_encoder::Module::new();
+        module
+            .section(&types)
+            .section(&funcs)
+            .section(&exports)
+            .section(&code);
+        module.finish()
+    }
+
+    #[test]
+    fn legalizes_i64_exports() {
+        use wasm_encoder::ValType::I64;
+        let wasm = module(
+            &[I64, I64],
+            &[I64],
+            &[
+                Instruction::LocalGet(0),
+                Instruction::LocalGet(1),
+                Instruction::I64Add,
+            ],
+        );
+        let js = compile(&wasm, ModuleFormat::Esm).unwrap();
+        assert!(js.contains("export default function instantiate(imports) {"));
+        assert!(js.contains("function x0(p0, p0h, p1, p1h) {"));
+        assert!(js.contains("imports.env.setTempRet0("));
+        assert!(js.contains("x0.__wasm = f0;"));
+        assert!(js.contains("return { 'f': x0 };"));
+    }
+
+    #[test]
+    fn commonjs_module() {
+        let wasm = module(&[], &[], &[Instruction::Nop]);
+        let js = compile(&wasm, ModuleFormat::CommonJs).unwrap();
+        assert!(js.contains("module.exports = function instantiate(imports) {"));
+        assert!(js.contains("return { 'f': f0 };"));
+    }
+
+    #[test]
+    fn rejects_simd() {
+        let wasm = module(&[], &[], &[Instruction::V128Const(0), Instruction::Drop]);
+        let err = compile(&wasm, ModuleFormat::Esm).unwrap_err();
+        assert!(format!("{err:#}").contains("unsupported operator"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Errors produced while transpiling components or generating types
+
+use std::fmt;
+
+/// Error produced by [`transpile`](crate::transpile) and
+/// [`generate_types`](crate::generate_types)
+///
+/// Where possible, errors carry the offending item (a WIT function/type/interface,
+/// a component export, or a trampoline) so that callers can point users at the
+/// part of the component that could not be handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TranspileError {
+    /// The component uses a feature that is not (yet) supported by the transpiler
+    UnsupportedFeature {
+        /// Description of the unsupported feature
+        feature: String,
+        /// The item (e.g. trampoline or WIT item) that uses the feature
+        item: Option<String>,
+    },
+    /// The component could not be decoded, validated or is otherwise malformed
+    InvalidComponent {
+        /// Description of the problem
+        message: String,
+        /// The offending item, if known
+        item: Option<String>,
+    },
+    /// An import mapping (see [`TranspileOpts::map`](crate::TranspileOpts::map))
+    /// could not be applied
+    Mapping {
+        /// The mapping key (import specifier) that was invalid
+        specifier: String,
+        /// Description of the problem
+        message: String,
+    },
+    /// Generation of TypeScript declarations failed
+    TypeGeneration {
+        /// Description of the problem
+        message: String,
+        /// The offending WIT item, if known
+        item: Option<String>,
+    },
+}
+
+impl TranspileError {
+    /// Create an [`TranspileError::UnsupportedFeature`] for a given item
+    pub(crate) fn unsupported(feature: impl Into<String>, item: impl Into<String>) -> Self {
+        Self::UnsupportedFeature {
+            feature: feature.into(),
+            item: Some(item.into()),
+        }
+    }
+
+    /// Create an [`TranspileError::InvalidComponent`] for a given item
+    pub(crate) fn invalid(message: impl Into<String>, item: impl Into<String>) -> Self {
+        Self::InvalidComponent {
+            message: message.into(),
+            item: Some(item.into()),
+        }
+    }
+
+    /// The item the error relates to, if known
+    pub fn item(&self) -> Option<&str> {
+        match self {
+            Self::UnsupportedFeature { item, .. }
+            | Self::InvalidComponent { item, .. }
+            | Self::TypeGeneration { item, .. } => item.as_deref(),
+            Self::Mapping { specifier, .. } => Some(specifier),
+        }
+    }
+
+    /// A description of the error without the related item
+    pub fn message(&self) -> &str {
+        match self {
+            Self::UnsupportedFeature { feature, .. } => feature,
+            Self::InvalidComponent { message, .. }
+            | Self::Mapping { message, .. }
+            | Self::TypeGeneration { message, .. } => message,
+        }
+    }
+}
+
+impl fmt::Display for TranspileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFeature { feature, .. } => {
+                write!(f, "unsupported feature: {feature}")?
+            }
+            Self::InvalidComponent { message, .. } => write!(f, "invalid component: {message}")?,
+            Self::Mapping { specifier, message } => {
+                return write!(f, "invalid mapping for [{specifier}]: {message}");
+            }
+            Self::TypeGeneration { message, .. } => {
+                write!(f, "failed to generate types: {message}")?
+            }
+        }
+        if let Some(item) = self.item() {
+            write!(f, " (at [{item}])")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TranspileError {}
+
+/// Result type used by the transpilation entrypoints
+pub type TranspileResult<T> = Result<T, TranspileError>;
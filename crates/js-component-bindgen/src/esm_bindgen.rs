@@ -6,7 +6,7 @@ use heck::ToLowerCamelCase;
 use crate::intrinsics::webidl::WebIdlIntrinsic;
 use crate::names::{LocalNames, maybe_quote_id, maybe_quote_member};
 use crate::source::Source;
-use crate::{TranspileOpts, uwrite, uwriteln};
+use crate::{ModuleFormat, TranspileError, TranspileOpts, TranspileResult, uwrite, uwriteln};
 
 /// JS local name
 type LocalName = String;
@@ -26,6 +26,24 @@ enum ExportBinding {
     Local(LocalName, WasmFuncName),
 }
 
+/// The name an interface is exported under, given its ID (ex. `wasi:cli/run@0.2.0`),
+/// which is left as-is, or its kebab-case name in the world, which is converted to camel case
+pub(crate) fn interface_export_name(iface_id_or_kebab: &str) -> String {
+    if iface_id_or_kebab.contains(':') {
+        iface_id_or_kebab.to_string()
+    } else {
+        iface_id_or_kebab.to_lower_camel_case()
+    }
+}
+
+/// The short name an interface exported by ID is additionally exported under
+/// (ex. `run` for `wasi:cli/run@0.2.0`), if the export name has a path
+pub(crate) fn export_alias(export_name: &str) -> Option<String> {
+    let path_idx = export_name.rfind('/')?;
+    let end = export_name.rfind('@').unwrap_or(export_name.len());
+    Some(export_name[path_idx + 1..end].to_lower_camel_case())
+}
+
 #[derive(Default)]
 pub struct EsmBindgen {
     imports: BTreeMap<String, ImportBinding>,
@@ -38,7 +56,11 @@ impl EsmBindgen {
     /// first segment
     /// arbitrary nesting of interfaces is supported in order to support virtual WASI interfaces
     /// only two-level nesting supports serialization into imports currently
-    pub fn add_import_binding(&mut self, path: &[String], binding_name: String) {
+    pub fn add_import_binding(
+        &mut self,
+        path: &[String],
+        binding_name: String,
+    ) -> TranspileResult<()> {
         let mut iface = &mut self.imports;
 
         // Process elements of the path in order
@@ -55,12 +77,14 @@ impl EsmBindgen {
             iface = match iface.get_mut(&path[i]).unwrap() {
                 ImportBinding::Interface(iface) => iface,
                 ImportBinding::Local(local) => {
-                    panic!(
-                        "Internal bindgen error: Import '{}' cannot be both an interface '{}' and a function '{}'",
+                    return Err(TranspileError::unsupported(
+                        format!(
+                            "import cannot be both an interface '{}' and a function '{}'",
+                            path[i + 1..].join("."),
+                            local[0],
+                        ),
                         path[0..i + 1].join("."),
-                        path[i + 1..].join("."),
-                        local[0],
-                    );
+                    ));
                 }
             };
         }
@@ -68,7 +92,10 @@ impl EsmBindgen {
         if let Some(ref mut existing) = iface.get_mut(&path[path.len() - 1]) {
             match existing {
                 ImportBinding::Interface(_) => {
-                    unreachable!("Multi-version interfaces must have the same shape")
+                    return Err(TranspileError::unsupported(
+                        "multi-version interfaces must have the same shape",
+                        path.join("."),
+                    ));
                 }
                 ImportBinding::Local(binding_local_names) => {
                     if !binding_local_names.contains(&binding_name) {
@@ -82,32 +109,31 @@ impl EsmBindgen {
                 ImportBinding::Local(vec![binding_name]),
             );
         }
+        Ok(())
     }
 
-    /// Add an exported function binding, optionally on an interface id or kebab name
+    /// Add an exported binding, optionally on an interface id or kebab name
+    ///
+    /// The `item_name` is the name of the exported item in the component, such
+    /// as the name of a WIT function.
     pub fn add_export_binding(
         &mut self,
         iface_id_or_kebab: Option<&str>,
         local_name: String,
         func_name: String,
-        func: &wit_parser::Function,
-    ) {
+        item_name: &str,
+    ) -> TranspileResult<()> {
         let mut iface = &mut self.exports;
         // If we weren't provided an interface ID, it's a local name
         let Some(iface_id_or_kebab) = iface_id_or_kebab else {
             iface.insert(
                 func_name,
-                ExportBinding::Local(local_name, func.name.to_string()),
+                ExportBinding::Local(local_name, item_name.to_string()),
             );
-            return;
+            return Ok(());
         };
 
-        // convert kebab names to camel case, leave ids as-is
-        let iface_id_or_kebab = if iface_id_or_kebab.contains(':') {
-            iface_id_or_kebab.to_string()
-        } else {
-            iface_id_or_kebab.to_lower_camel_case()
-        };
+        let iface_id_or_kebab = interface_export_name(iface_id_or_kebab);
 
         if !iface.contains_key(&iface_id_or_kebab) {
             iface.insert(
@@ -118,32 +144,30 @@ impl EsmBindgen {
 
         iface = match iface.get_mut(&iface_id_or_kebab).unwrap() {
             ExportBinding::Interface(iface) => iface,
-            ExportBinding::Local(_, _) => panic!(
-                "Exported interface {iface_id_or_kebab} cannot be both a function and an interface"
-            ),
+            ExportBinding::Local(_, _) => {
+                return Err(TranspileError::unsupported(
+                    "exported interface cannot be both a function and an interface",
+                    iface_id_or_kebab,
+                ));
+            }
         };
 
         iface.insert(
             func_name,
-            ExportBinding::Local(local_name, func.name.to_string()),
+            ExportBinding::Local(local_name, item_name.to_string()),
         );
+        Ok(())
     }
 
     /// once all exports have been created, aliases can be populated for interface
     /// names that do not collide with kebab names or other interface names
     pub fn populate_export_aliases(&mut self) {
         for expt_name in self.exports.keys() {
-            if let Some(path_idx) = expt_name.rfind('/') {
-                let end = if let Some(version_idx) = expt_name.rfind('@') {
-                    version_idx
-                } else {
-                    expt_name.len()
-                };
-                let alias = &expt_name[path_idx + 1..end].to_lower_camel_case();
-                if !self.exports.contains_key(alias) && !self.export_aliases.contains_key(alias) {
-                    self.export_aliases
-                        .insert(alias.to_string(), expt_name.to_string());
-                }
+            if let Some(alias) = export_alias(expt_name)
+                && !self.exports.contains_key(&alias)
+                && !self.export_aliases.contains_key(&alias)
+            {
+                self.export_aliases.insert(alias, expt_name.to_string());
             }
         }
     }
@@ -176,12 +200,12 @@ impl EsmBindgen {
         instantiation: bool,
         local_names: &mut LocalNames,
         opts: &TranspileOpts,
-    ) {
+    ) -> TranspileResult<()> {
         if self.exports.is_empty() {
             if instantiation {
                 output.push_str("return {}");
             }
-            return;
+            return Ok(());
         }
         // first create all the interfaces
         // we currently only support first-level nesting so there is no ordering to figure out
@@ -195,16 +219,26 @@ impl EsmBindgen {
             uwriteln!(output, "const {local_name} = {{");
             for (func_name, export) in iface {
                 let ExportBinding::Local(local_name, _) = export else {
-                    panic!("Unsupported nested export interface");
+                    return Err(TranspileError::unsupported(
+                        "nested export interfaces",
+                        format!("{export_name}.{func_name}"),
+                    ));
                 };
                 uwriteln!(output, "{}: {local_name},", maybe_quote_id(func_name));
             }
             uwriteln!(output, "\n}};");
         }
+        let commonjs = opts.module_format == ModuleFormat::CommonJs;
         uwrite!(
             output,
             "\n{} {{ ",
-            if instantiation { "return" } else { "export" }
+            if instantiation {
+                "return"
+            } else if commonjs {
+                "module.exports ="
+            } else {
+                "export"
+            }
         );
         let mut first = true;
         for (alias, export_name) in &self.export_aliases {
@@ -219,7 +253,7 @@ impl EsmBindgen {
             if local_name == alias_maybe_quoted {
                 output.push_str(local_name);
                 uwrite!(output, ", ");
-            } else if instantiation {
+            } else if instantiation || commonjs {
                 uwrite!(output, "{alias_maybe_quoted}: {local_name}");
                 uwrite!(output, ", ");
             } else if !self.contains_js_quote(&alias_maybe_quoted) || !opts.no_namespaced_exports {
@@ -239,7 +273,7 @@ impl EsmBindgen {
             if local_name == export_name_maybe_quoted {
                 output.push_str(local_name);
                 uwrite!(output, ", ");
-            } else if instantiation {
+            } else if instantiation || commonjs {
                 uwrite!(output, "{export_name_maybe_quoted}: {local_name}");
                 uwrite!(output, ", ");
             } else if !self.contains_js_quote(&export_name_maybe_quoted)
@@ -250,6 +284,10 @@ impl EsmBindgen {
             }
         }
         uwrite!(output, " }}");
+        if commonjs && !instantiation {
+            uwrite!(output, ";");
+        }
+        Ok(())
     }
 
     fn contains_js_quote(&self, js_string: &str) -> bool {
@@ -271,13 +309,25 @@ impl EsmBindgen {
     /// let gen = (function* _initGenerator () {
     /// ```
     ///
+    /// Without an imports object, imports are rendered as ES module imports, or as
+    /// `require()` calls when generating a CommonJS module.
     pub fn render_imports(
         &mut self,
         output: &mut Source,
         imports_object: Option<&str>,
         local_names: &mut LocalNames,
+        opts: &TranspileOpts,
     ) {
         let mut iface_imports = Vec::new();
+        let commonjs = imports_object.is_none() && opts.module_format == ModuleFormat::CommonJs;
+        // Source of an import statement, or of the destructured `require()` call
+        let from = |specifier: &str| {
+            if commonjs {
+                format!("= require('{specifier}')")
+            } else {
+                format!("from '{specifier}'")
+            }
+        };
 
         for (specifier, binding) in &self.imports {
             // Build IDL binding if the specifier uses specifal WebIDL support
@@ -293,7 +343,9 @@ impl EsmBindgen {
                 None
             };
 
-            if imports_object.is_some() || idl_binding.is_some() {
+            // Whether the import is bound by destructuring an object, rather than an import statement
+            let destructured = imports_object.is_some() || idl_binding.is_some() || commonjs;
+            if destructured {
                 uwrite!(output, "const ");
             } else {
                 uwrite!(output, "import ");
@@ -312,11 +364,11 @@ impl EsmBindgen {
                                 ImportBinding::Interface(iface) => {
                                     let iface_local_name = local_names.create_once(specifier);
                                     iface_imports.push((iface_local_name.to_string(), iface));
-                                    uwriteln!(output, "{iface_local_name} from '{specifier}';");
+                                    uwriteln!(output, "{iface_local_name} {};", from(specifier));
                                 }
                                 ImportBinding::Local(local_names) => {
                                     let local_name = &local_names[0];
-                                    uwriteln!(output, "{local_name} from '{specifier}';");
+                                    uwriteln!(output, "{local_name} {};", from(specifier));
                                     for other_local_name in &local_names[1..] {
                                         uwriteln!(
                                             output,
@@ -351,7 +403,7 @@ impl EsmBindgen {
                                 iface_imports.push((iface_local_name.to_string(), iface));
                                 if external_name == iface_local_name {
                                     uwrite!(output, "{external_name}");
-                                } else if imports_object.is_some() || idl_binding.is_some() {
+                                } else if destructured {
                                     uwrite!(output, "{external_name}: {iface_local_name}");
                                 } else {
                                     uwrite!(output, "{external_name} as {iface_local_name}");
@@ -372,7 +424,7 @@ impl EsmBindgen {
                                     }
                                     if external_name == local_name {
                                         uwrite!(output, "{external_name}");
-                                    } else if imports_object.is_some() || idl_binding.is_some() {
+                                    } else if destructured {
                                         uwrite!(output, "{external_name}: {local_name}");
                                     } else {
                                         uwrite!(output, "{external_name} as {local_name}");
@@ -420,7 +472,7 @@ impl EsmBindgen {
                         }
                         uwrite!(output, ";\n");
                     } else {
-                        uwriteln!(output, "}} from '{specifier}';");
+                        uwriteln!(output, "}} {};", from(specifier));
                     }
                 }
 
@@ -434,7 +486,7 @@ impl EsmBindgen {
                             maybe_quote_member(specifier)
                         );
                     } else {
-                        uwriteln!(output, "{local_name} from '{specifier}';");
+                        uwriteln!(output, "{local_name} {};", from(specifier));
                     }
 
                     for other_local_name in &binding_local_names[1..] {
@@ -492,3 +544,37 @@ impl EsmBindgen {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_both_interface_and_function() {
+        let mut bindgen = EsmBindgen::default();
+        let path = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        bindgen
+            .add_import_binding(&path(&["pkg", "run"]), "run".into())
+            .unwrap();
+
+        let err = bindgen
+            .add_import_binding(&path(&["pkg", "run", "inner"]), "inner".into())
+            .unwrap_err();
+        assert!(matches!(err, TranspileError::UnsupportedFeature { .. }));
+        assert_eq!(err.item(), Some("pkg.run"));
+    }
+
+    #[test]
+    fn export_both_interface_and_function() {
+        let mut bindgen = EsmBindgen::default();
+        bindgen
+            .add_export_binding(None, "run".into(), "run".into(), "run")
+            .unwrap();
+
+        let err = bindgen
+            .add_export_binding(Some("run"), "inner".into(), "inner".into(), "inner")
+            .unwrap_err();
+        assert!(matches!(err, TranspileError::UnsupportedFeature { .. }));
+        assert_eq!(err.item(), Some("run"));
+    }
+}
@@ -15,16 +15,17 @@ use wit_parser::{
     Alignment, ArchitectureSize, Handle, Resolve, SizeAlign, Type, TypeDef, TypeDefKind, TypeId,
 };
 
-use crate::intrinsics::Intrinsic;
 use crate::intrinsics::component::ComponentIntrinsic;
 use crate::intrinsics::conversion::ConversionIntrinsic;
 use crate::intrinsics::js_helper::JsHelperIntrinsic;
 use crate::intrinsics::p3::async_future::AsyncFutureIntrinsic;
 use crate::intrinsics::p3::async_stream::AsyncStreamIntrinsic;
 use crate::intrinsics::p3::async_task::AsyncTaskIntrinsic;
+use crate::intrinsics::p3::error_context::ErrCtxIntrinsic;
 use crate::intrinsics::resource::ResourceIntrinsic;
 use crate::intrinsics::string::StringIntrinsic;
-use crate::{ManagesIntrinsics, get_thrown_type, source};
+use crate::intrinsics::{Intrinsic, index_expr};
+use crate::{ImportJournalMode, ManagesIntrinsics, TranspileError, get_thrown_type, source};
 use crate::{uwrite, uwriteln};
 
 /// Method of error handling
@@ -133,12 +134,33 @@ pub struct ResourceTable {
 /// A mapping of type IDs to the resources that they represent
 pub type ResourceMap = BTreeMap<TypeId, ResourceTable>;
 
+/// Alternative JS representation of a WIT type
+///
+/// See [`TypeRepresentations`](crate::TypeRepresentations) for the types that
+/// are represented this way.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeRepr {
+    /// Enum represented by the index of its case
+    NumericEnum,
+    /// Flags represented as a `number` bitmask
+    BitmaskFlags,
+    /// Variant represented as instances of a class per case, which are static
+    /// members of the given JS class (e.g. `Shape.Circle`)
+    ClassVariant { class_name: String },
+}
+
+/// A mapping of type IDs to the alternative representations used for them
+pub type TypeReprMap = BTreeMap<TypeId, TypeRepr>;
+
 #[derive(bon::Builder)]
 #[non_exhaustive]
 pub struct FunctionBindgen<'a> {
     /// Mapping of resources for types that have corresponding definitions locally
     pub resource_map: &'a ResourceMap,
 
+    /// Types that use an alternative JS representation
+    pub type_reprs: &'a TypeReprMap,
+
     /// Whether current resource borrows need to be deactivated
     pub clear_resource_borrows: bool,
 
@@ -184,6 +206,12 @@ pub struct FunctionBindgen<'a> {
     /// Whether tracing is enabled
     pub tracing_enabled: bool,
 
+    /// JS object literal identifying the function in tracing events
+    pub tracing_fn_id: &'a String,
+
+    /// Whether calls to host imports are recorded into or replayed from the import journal
+    pub import_journal: Option<ImportJournalMode>,
+
     /// Method if string encoding
     pub encoding: StringEncoding,
 
@@ -215,6 +243,10 @@ pub struct FunctionBindgen<'a> {
     /// Whether the callee was transpiled from Wasm to JS (asm.js) and thus needs shimming for i64
     pub asmjs: bool,
 
+    /// Whether intrinsics are provided by a shared runtime, in which case component
+    /// instance and table indices are offset by a base allocated at load time
+    pub shared_runtime: bool,
+
     /// Component state generated from processing a component.
     ///
     /// This information is normally accessible via producing/having
@@ -230,6 +262,10 @@ pub struct FunctionBindgen<'a> {
     /// Whether the bindgen is being performed for an import
     /// (false implies generation is being performed for an export)
     pub(crate) for_import: Option<bool>,
+
+    /// First error encountered while generating the function, which is returned
+    /// once bindgen has completed (instructions themselves cannot fail)
+    pub(crate) error: Option<TranspileError>,
 }
 
 /// Metadata that is derived from processing a component.
@@ -248,6 +284,7 @@ pub struct FunctionBindgenComponentState {
     pub(crate) realloc_fn_idx: Option<RuntimeReallocIndex>,
     pub(crate) memory_idx: Option<RuntimeMemoryIndex>,
     pub(crate) callback_fn_idx: Option<RuntimeCallbackIndex>,
+    pub(crate) err_ctx_table_idx: Option<TypeComponentLocalErrorContextTableIndex>,
 }
 
 /// JS expressions that resolve to or return component state
@@ -273,9 +310,9 @@ impl FunctionBindgenComponentState {
     ///
     /// When certain values are missing either `null` or `() => null` JS expressions are returned
     ///
-    fn get_js_exprs(&self) -> ComponentStateJsExprs {
+    fn get_js_exprs(&self, shared_runtime: bool) -> ComponentStateJsExprs {
         ComponentStateJsExprs {
-            component_idx: self.component_idx.as_u32().to_string(),
+            component_idx: index_expr(self.component_idx.as_u32(), shared_runtime),
             callback_fn_name: match self.callback_fn_idx {
                 Some(idx) => format!("callback_{}", idx.as_u32()),
                 None => "null".into(),
@@ -301,6 +338,11 @@ impl FunctionBindgenComponentState {
 }
 
 impl FunctionBindgen<'_> {
+    /// Record an error that prevents the function from being generated, keeping the first one
+    fn fail(&mut self, err: TranspileError) {
+        self.error.get_or_insert(err);
+    }
+
     fn tmp(&mut self) -> usize {
         let ret = self.tmp;
         self.tmp += 1;
@@ -463,6 +505,41 @@ impl FunctionBindgen<'_> {
         }
     }
 
+    /// Write the tracing event for entering the function (with the given lifted
+    /// arguments), starting a span that is ended by [`Self::trace_exit`] or
+    /// [`Self::trace_error`]
+    fn trace_enter(&mut self, args: &str) {
+        if !self.tracing_enabled {
+            return;
+        }
+        let tracer = self.intrinsic(Intrinsic::Tracer);
+        uwriteln!(
+            self.src,
+            "const traceSpan = {tracer}.enter({fn_id}, task.id(), task.componentIdx(), [{args}]);",
+            fn_id = self.tracing_fn_id,
+        );
+    }
+
+    /// Build the statement ending the tracing span of the function with the given
+    /// lifted result (empty if tracing is disabled)
+    fn trace_exit(&mut self, result: &str) -> String {
+        if !self.tracing_enabled {
+            return String::new();
+        }
+        let tracer = self.intrinsic(Intrinsic::Tracer);
+        format!("{tracer}.exit(traceSpan, {result});")
+    }
+
+    /// Build the statement ending the tracing span of the function with the given
+    /// thrown error (empty if tracing is disabled)
+    fn trace_error(&mut self, err: &str) -> String {
+        if !self.tracing_enabled {
+            return String::new();
+        }
+        let tracer = self.intrinsic(Intrinsic::Tracer);
+        format!("{tracer}.error(traceSpan, {err});")
+    }
+
     /// Start the current task
     ///
     /// The code generated by this function *may* also start a subtask
@@ -492,7 +569,7 @@ impl FunctionBindgen<'_> {
                     callback_fn_name,
                     get_callback_fn,
                     ..
-                } = state.get_js_exprs();
+                } = state.get_js_exprs(self.shared_runtime);
                 (component_idx, get_callback_fn, callback_fn_name)
             } else {
                 ("1".into(), "() => null".into(), "null".into())
@@ -739,6 +816,32 @@ impl Bindgen for FunctionBindgen<'_> {
                 results.push(format!("[{}]", operands.join(", ")));
             }
 
+            Instruction::FlagsLower { flags, ty, .. }
+                if self.type_reprs.get(ty) == Some(&TypeRepr::BitmaskFlags) =>
+            {
+                let op0 = &operands[0];
+                if flags.repr().count() == 0 {
+                    return;
+                }
+
+                let tmp = self.tmp();
+                uwriteln!(self.src, "var flags{tmp} = {op0} ?? 0;");
+                // Bits beyond the last flag must not be set
+                let extraneous_bits_check = if flags.flags.len() < 32 {
+                    let mask: u32 = 0xffffffff << flags.flags.len();
+                    format!(" || (flags{tmp} & {mask}) !== 0")
+                } else {
+                    String::new()
+                };
+                uwriteln!(
+                    self.src,
+                    "if (!Number.isInteger(flags{tmp}){extraneous_bits_check}) {{
+                        throw new TypeError('only a bitmask of flags, undefined or null can be converted to flags');
+                    }}"
+                );
+                results.push(format!("flags{tmp}"));
+            }
+
             Instruction::FlagsLower { flags, .. } => {
                 let op0 = &operands[0];
 
@@ -785,7 +888,7 @@ impl Bindgen for FunctionBindgen<'_> {
                 // already defaulted everyting to 0.
             }
 
-            Instruction::FlagsLift { flags, .. } => {
+            Instruction::FlagsLift { flags, ty, .. } => {
                 let tmp = self.tmp();
                 results.push(format!("flags{tmp}"));
 
@@ -804,6 +907,15 @@ impl Bindgen for FunctionBindgen<'_> {
                     }
                 }
 
+                if self.type_reprs.get(ty) == Some(&TypeRepr::BitmaskFlags) {
+                    let bitmask = operands
+                        .last()
+                        .map(|op| format!("{op} >>> 0"))
+                        .unwrap_or_else(|| "0".into());
+                    uwriteln!(self.src, "var flags{tmp} = {bitmask};");
+                    return;
+                }
+
                 uwriteln!(self.src, "var flags{tmp} = {{");
 
                 for (i, flag) in flags.flags.iter().enumerate() {
@@ -818,6 +930,59 @@ impl Bindgen for FunctionBindgen<'_> {
 
             Instruction::VariantPayloadName => results.push("e".to_string()),
 
+            Instruction::VariantLower {
+                variant,
+                results: result_types,
+                name,
+                ty,
+            } if matches!(self.type_reprs.get(ty), Some(TypeRepr::ClassVariant { .. })) => {
+                let Some(TypeRepr::ClassVariant { class_name }) = self.type_reprs.get(ty) else {
+                    unreachable!("unexpected variant representation");
+                };
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let tmp = self.tmp();
+                let op = &operands[0];
+                uwriteln!(self.src, "var variant{tmp} = {op};");
+
+                for i in 0..result_types.len() {
+                    uwriteln!(self.src, "let variant{tmp}_{i};");
+                    results.push(format!("variant{tmp}_{i}"));
+                }
+
+                // Cases are discriminated by the class they are an instance of
+                for (i, (case, (block, block_results))) in
+                    variant.cases.iter().zip(blocks).enumerate()
+                {
+                    let case_class = case.name.to_upper_camel_case();
+                    let else_ = if i == 0 { "" } else { "} else " };
+                    uwriteln!(
+                        self.src,
+                        "{else_}if (variant{tmp} instanceof {class_name}.{case_class}) {{"
+                    );
+                    if case.ty.is_some() {
+                        uwriteln!(self.src, "const e = variant{tmp}.val;");
+                    }
+                    self.src.push_str(&block);
+
+                    for (i, result) in block_results.iter().enumerate() {
+                        uwriteln!(self.src, "variant{tmp}_{i} = {result};");
+                    }
+                }
+                let variant_name = name.to_upper_camel_case();
+                if !variant.cases.is_empty() {
+                    uwrite!(self.src, "}} else ");
+                }
+                uwriteln!(
+                    self.src,
+                    r#"{{
+                        throw new TypeError(`invalid variant value (received \`${{variant{tmp}}}\`) specified for \`{variant_name}\``);
+                    }}"#,
+                );
+            }
+
             Instruction::VariantLower {
                 variant,
                 results: result_types,
@@ -866,6 +1031,59 @@ impl Bindgen for FunctionBindgen<'_> {
                 uwriteln!(self.src, "}}");
             }
 
+            Instruction::VariantLift { variant, name, ty }
+                if matches!(self.type_reprs.get(ty), Some(TypeRepr::ClassVariant { .. })) =>
+            {
+                let Some(TypeRepr::ClassVariant { class_name }) = self.type_reprs.get(ty) else {
+                    unreachable!("unexpected variant representation");
+                };
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+
+                let tmp = self.tmp();
+                let op = &operands[0];
+
+                uwriteln!(
+                    self.src,
+                    "let variant{tmp};
+                    switch ({op}) {{"
+                );
+
+                for (i, (case, (block, block_results))) in
+                    variant.cases.iter().zip(blocks).enumerate()
+                {
+                    let case_class = case.name.to_upper_camel_case();
+                    let val = if case.ty.is_some() {
+                        assert!(block_results.len() == 1);
+                        block_results[0].as_str()
+                    } else {
+                        assert!(block_results.is_empty());
+                        ""
+                    };
+                    uwriteln!(
+                        self.src,
+                        "case {i}: {{
+                            {block}\
+                            variant{tmp} = new {class_name}.{case_class}({val});
+                            break;
+                        }}"
+                    );
+                }
+                let variant_name = name.to_upper_camel_case();
+                if !self.valid_lifting_optimization {
+                    uwriteln!(
+                        self.src,
+                        "default: {{
+                            throw new TypeError('invalid variant discriminant for {variant_name}');
+                        }}",
+                    );
+                }
+                uwriteln!(self.src, "}}");
+                results.push(format!("variant{tmp}"));
+            }
+
             Instruction::VariantLift { variant, name, .. } => {
                 let blocks = self
                     .blocks
@@ -1145,6 +1363,22 @@ impl Bindgen for FunctionBindgen<'_> {
                 results.push(format!("variant{tmp}"));
             }
 
+            Instruction::EnumLower { name, enum_, ty }
+                if self.type_reprs.get(ty) == Some(&TypeRepr::NumericEnum) =>
+            {
+                let tmp = self.tmp();
+                let op = &operands[0];
+                let num_cases = enum_.cases.len();
+                uwriteln!(
+                    self.src,
+                    "var enum{tmp} = {op};
+                    if (!Number.isInteger(enum{tmp}) || enum{tmp} < 0 || enum{tmp} >= {num_cases}) {{
+                        throw new TypeError(`\"${{enum{tmp}}}\" is not one of the cases of {name}`);
+                    }}"
+                );
+                results.push(format!("enum{tmp}"));
+            }
+
             Instruction::EnumLower { name, enum_, .. } => {
                 let tmp = self.tmp();
 
@@ -1187,6 +1421,24 @@ impl Bindgen for FunctionBindgen<'_> {
                 results.push(format!("enum{tmp}"));
             }
 
+            Instruction::EnumLift { name, enum_, ty }
+                if self.type_reprs.get(ty) == Some(&TypeRepr::NumericEnum) =>
+            {
+                let tmp = self.tmp();
+                uwriteln!(self.src, "var enum{tmp} = {};", operands[0]);
+                if !self.valid_lifting_optimization {
+                    let name = name.to_upper_camel_case();
+                    let num_cases = enum_.cases.len();
+                    uwriteln!(
+                        self.src,
+                        "if (enum{tmp} < 0 || enum{tmp} >= {num_cases}) {{
+                            throw new TypeError('invalid discriminant specified for {name}');
+                        }}",
+                    );
+                }
+                results.push(format!("enum{tmp}"));
+            }
+
             Instruction::EnumLift { name, enum_, .. } => {
                 let tmp = self.tmp();
 
@@ -1650,6 +1902,10 @@ impl Bindgen for FunctionBindgen<'_> {
                     uwriteln!(self.src, "{scope_id}++;");
                 }
 
+                // The caller's arguments are the lifted arguments of an export
+                let trace_args = self.params.join(", ");
+                self.trace_enter(&trace_args);
+
                 // Set task memory index and memory object
                 let (memory_idx_expr, get_memory_fn_expr) =
                     if let Some(state) = &self.component_state {
@@ -1657,7 +1913,7 @@ impl Bindgen for FunctionBindgen<'_> {
                             memory_idx,
                             get_memory_fn,
                             ..
-                        } = state.get_js_exprs();
+                        } = state.get_js_exprs(self.shared_runtime);
                         (memory_idx, get_memory_fn)
                     } else {
                         ("null".into(), "() => null".into())
@@ -1678,6 +1934,7 @@ impl Bindgen for FunctionBindgen<'_> {
                 let (vars_init, assignment_lhs) =
                     self.generate_result_assignment_lhs(sig_results_length, results, is_async);
 
+                let trace_error = self.trace_error("err");
                 let (call_prefix, call_wrapper, call_err_cleanup) =
                     if self.requires_async_porcelain | self.is_async {
                         (
@@ -1689,6 +1946,7 @@ impl Bindgen for FunctionBindgen<'_> {
                                   taskID: task.id(),
                                   err,
                               }});
+                              {trace_error}
                               task.setErrored(err);
                               task.reject(err);
                               task.exit();
@@ -1706,6 +1964,7 @@ impl Bindgen for FunctionBindgen<'_> {
                                   taskID: task.id(),
                                   err,
                               }});
+                              {trace_error}
                               task.setErrored(err);
                               task.reject(err);
                               task.exit();
@@ -1741,18 +2000,25 @@ impl Bindgen for FunctionBindgen<'_> {
                     args = args.join(", ")
                 );
 
+                let mut callee_async = "";
                 if self.asmjs {
                     // wasm2js does not support multivalue return
                     // if/when it does, this will need changing.
                     assert!(sig.results.len() <= 1);
-                    // same with async(?)
-                    assert!(!self.requires_async_porcelain && !self.is_async);
+                    // Only asyncify-instrumented core modules can suspend here, and they
+                    // are never component model async
+                    assert!(!self.is_async);
 
                     if sig.results.len() == 1 && matches!(sig.results[0], WasmType::I64) {
                         let merge_i64 = self
                             .intrinsic(Intrinsic::Conversion(ConversionIntrinsic::MergeBigInt64));
-                        callee_invoke =
-                            format!("{merge_i64}({callee_invoke}, task.tmpRetI64HighBits)");
+                        callee_invoke = if self.requires_async_porcelain {
+                            // The high bits are only set once the suspended call completes
+                            callee_async = "async ";
+                            format!("{merge_i64}(await {callee_invoke}, task.tmpRetI64HighBits)")
+                        } else {
+                            format!("{merge_i64}({callee_invoke}, task.tmpRetI64HighBits)")
+                        };
                     }
                 }
 
@@ -1764,28 +2030,13 @@ impl Bindgen for FunctionBindgen<'_> {
                            {assignment_lhs} {call_prefix} {call_wrapper}({{
                                taskID: task.id(),
                                componentIdx: task.componentIdx(),
-                               fn: () => {callee_invoke},
+                               fn: {callee_async}() => {callee_invoke},
                             }});
                       }} catch (err) {{
                           {call_err_cleanup}
                       }}
                     "#,
                 );
-
-                if self.tracing_enabled {
-                    let prefix = self.tracing_prefix;
-                    let to_result_string =
-                        self.intrinsic(Intrinsic::Conversion(ConversionIntrinsic::ToResultString));
-                    uwriteln!(
-                        self.src,
-                        "console.error(`{prefix} return {}`);",
-                        if sig_results_length > 0 || !results.is_empty() {
-                            format!("result=${{{to_result_string}(ret)}}")
-                        } else {
-                            "".to_string()
-                        }
-                    );
-                }
             }
 
             // Call to an imported interface (normally provided by the host)
@@ -1829,7 +2080,7 @@ impl Bindgen for FunctionBindgen<'_> {
                             callback_fn_name,
                             get_callback_fn,
                             ..
-                        } = state.get_js_exprs();
+                        } = state.get_js_exprs(self.shared_runtime);
                         (component_idx, callback_fn_name, get_callback_fn)
                     } else {
                         ("-1".into(), "null".into(), "() => null".into())
@@ -1921,7 +2172,12 @@ impl Bindgen for FunctionBindgen<'_> {
                     uwriteln!(self.src, "const started = task.enterSync();",);
                 }
 
+                // Operands are the lifted arguments (including the receiver of methods)
+                let trace_args = operands.join(", ");
+                self.trace_enter(&trace_args);
+
                 // Build the JS expression that calls the callee
+                let trace_error = self.trace_error("err");
                 let (call_prefix, call_wrapper, call_err_cleanup) = if is_async
                     || self.requires_async_porcelain
                 {
@@ -1935,6 +2191,7 @@ impl Bindgen for FunctionBindgen<'_> {
                                   subtaskID: task.getParentSubtask()?.id(),
                                   err,
                               }});
+                              {trace_error}
                               task.setErrored(err);
                               task.reject(err);
                               task.exit();
@@ -1953,6 +2210,7 @@ impl Bindgen for FunctionBindgen<'_> {
                                   subtaskID: task.getParentSubtask()?.id(),
                                   err,
                               }});
+                              {trace_error}
                               task.setErrored(err);
                               task.reject(err);
                               task.exit();
@@ -1962,11 +2220,45 @@ impl Bindgen for FunctionBindgen<'_> {
                     )
                 };
 
+                // When journaling, the host call goes through the import journal, which
+                // records the call or replays its recorded result instead
+                let host_call = match self.import_journal {
+                    None => format!("{callee_fn_js}({callee_args_js})"),
+                    Some(mode) => {
+                        let import_journal = self.intrinsic(Intrinsic::ImportJournal);
+                        let fn_name = match self.iface_name {
+                            Some(iface_name) => format!("{iface_name}#{}", func.name),
+                            None => func.name.clone(),
+                        };
+                        // Imported resource classes allow host objects to be replayed
+                        let classes = self
+                            .resource_map
+                            .values()
+                            .filter_map(|table| match &table.data {
+                                ResourceData::Host { local_name, .. } => Some(local_name.as_str()),
+                                ResourceData::Guest { .. } => None,
+                            })
+                            .collect::<BTreeSet<_>>()
+                            .into_iter()
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        let args = operands.join(", ");
+                        match mode {
+                            ImportJournalMode::Record => format!(
+                                "{import_journal}.record('{fn_name}', [{args}], {{ {classes} }}, () => {callee_fn_js}({callee_args_js}))"
+                            ),
+                            ImportJournalMode::Replay => format!(
+                                "{import_journal}.replay('{fn_name}', [{args}], {{ {classes} }})"
+                            ),
+                        }
+                    }
+                };
+
                 let call = format!(
                     r#"{call_prefix} {call_wrapper}({{
                               componentIdx: task.componentIdx(),
                               taskID: task.id(),
-                              fn: () => {callee_fn_js}({callee_args_js}),
+                              fn: () => {host_call},
                           }})
                         "#,
                 );
@@ -2024,20 +2316,13 @@ impl Bindgen for FunctionBindgen<'_> {
                     }
                 }
 
-                if self.tracing_enabled {
-                    let prefix = self.tracing_prefix;
-                    let to_result_string =
-                        self.intrinsic(Intrinsic::Conversion(ConversionIntrinsic::ToResultString));
-                    uwriteln!(
-                        self.src,
-                        "console.error(`{prefix} return {}`);",
-                        if fn_wasm_result_count > 0 || !results.is_empty() {
-                            format!("result=${{{to_result_string}(ret)}}")
-                        } else {
-                            "".to_string()
-                        }
-                    );
-                }
+                // The host result is the lifted result of an import
+                let trace_result = results
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| "undefined".into());
+                let trace_exit = self.trace_exit(&trace_result);
+                uwriteln!(self.src, "{trace_exit}");
 
                 // TODO: if it was an async call, we may not be able to clear the borrows yet.
                 // save them to the task/ensure they are added to the task's list of borrows?
@@ -2061,7 +2346,7 @@ impl Bindgen for FunctionBindgen<'_> {
                             }}
                             rsc[{symbol_resource_handle}] = undefined;
                         }}
-                        {cur_resource_borrows} = [];"
+                        {cur_resource_borrows}.length = 0;"
                     );
                     self.clear_resource_borrows = false;
                 }
@@ -2088,7 +2373,8 @@ impl Bindgen for FunctionBindgen<'_> {
 
                 // Get the component idx expr
                 let component_idx_expr = if let Some(state) = &self.component_state {
-                    let ComponentStateJsExprs { component_idx, .. } = state.get_js_exprs();
+                    let ComponentStateJsExprs { component_idx, .. } =
+                        state.get_js_exprs(self.shared_runtime);
                     component_idx
                 } else {
                     "-1".into()
@@ -2116,6 +2402,10 @@ impl Bindgen for FunctionBindgen<'_> {
 
                 assert!(!self.is_async, "async functions should use AsyncTaskReturn");
 
+                // Returns of imports hand lowered results back to the component, while the
+                // returns of exports hand the lifted results to the caller
+                let trace_return = self.for_import != Some(true);
+
                 // Depending how many values are on the stack after returning, we must execute differently.
                 //
                 // In particular, if this function is async (distinct from whether async porcelain was necessary or not),
@@ -2124,6 +2414,10 @@ impl Bindgen for FunctionBindgen<'_> {
                 match stack_value_count {
                     // (sync) Handle no result case
                     0 => {
+                        if trace_return {
+                            let trace_exit = self.trace_exit("undefined");
+                            uwriteln!(self.src, "{trace_exit}");
+                        }
                         uwriteln!(self.src, "task.resolve([ret]);");
                         if let Some(f) = &self.post_return {
                             uwriteln!(
@@ -2142,6 +2436,10 @@ impl Bindgen for FunctionBindgen<'_> {
                         let op = &operands[0];
 
                         uwriteln!(self.src, "const retCopy = {op};");
+                        if trace_return {
+                            let trace_exit = self.trace_exit("retCopy");
+                            uwriteln!(self.src, "{trace_exit}");
+                        }
                         uwriteln!(self.src, "task.resolve([retCopy.val]);");
 
                         if let Some(f) = &self.post_return {
@@ -2183,6 +2481,10 @@ impl Bindgen for FunctionBindgen<'_> {
                             ret_val.clone()
                         };
 
+                        if trace_return {
+                            let trace_exit = self.trace_exit(&ret_val);
+                            uwriteln!(self.src, "{trace_exit}");
+                        }
                         uwriteln!(self.src, "task.resolve([{ret_val}]);");
 
                         // Handle the post return if necessary
@@ -2663,18 +2965,79 @@ impl Bindgen for FunctionBindgen<'_> {
                 }
             }
 
+            // Error contexts stay owned by the component they are lifted from (they are reference
+            // counted), the host receives an `ErrorContext` that holds its own reference
             Instruction::ErrorContextLift => {
-                let item = operands
+                let handle = operands
                     .first()
                     .expect("unexpectedly missing ErrorContextLift arg");
-                results.push(item.clone());
+                let Some(state) = self.component_state.as_ref() else {
+                    self.fail(TranspileError::unsupported(
+                        "error-context lifting without component state",
+                        self.callee,
+                    ));
+                    results.push("undefined".into());
+                    return;
+                };
+                let component_idx = index_expr(state.component_idx.as_u32(), self.shared_runtime);
+                let get_or_create_async_state_fn = self.intrinsic(Intrinsic::Component(
+                    ComponentIntrinsic::GetOrCreateAsyncState,
+                ));
+                let host_value_fn = self.intrinsic(Intrinsic::ErrCtx(ErrCtxIntrinsic::HostValue));
+
+                let tmp = self.tmp();
+                let err_ctx = format!("errCtx{tmp}");
+                uwriteln!(
+                    self.src,
+                    "const {err_ctx} = {get_or_create_async_state_fn}({component_idx}).handles.get({handle});
+                    if ({err_ctx}?.globalRep === undefined) {{
+                        throw new Error(`missing error context (handle [${{{handle}}}]) in component [{component_idx}]`);
+                    }}"
+                );
+                results.push(format!("{host_value_fn}({err_ctx}.globalRep)"));
             }
 
+            // Lowering creates a new handle in the component for the error context the host value
+            // stands for (reserving one for host created `Error`s)
             Instruction::ErrorContextLower => {
                 let item = operands
                     .first()
                     .expect("unexpectedly missing ErrorContextLower arg");
-                results.push(item.clone());
+                let Some(state) = self.component_state.as_ref() else {
+                    self.fail(TranspileError::unsupported(
+                        "error-context lowering without component state",
+                        self.callee,
+                    ));
+                    results.push("undefined".into());
+                    return;
+                };
+                let Some(err_ctx_table_idx) = state.err_ctx_table_idx else {
+                    self.fail(TranspileError::invalid(
+                        format!(
+                            "missing error-context table for component instance [{}]",
+                            state.component_idx.as_u32()
+                        ),
+                        self.callee,
+                    ));
+                    results.push("undefined".into());
+                    return;
+                };
+                let component_idx = index_expr(state.component_idx.as_u32(), self.shared_runtime);
+                let table_idx = index_expr(err_ctx_table_idx.as_u32(), self.shared_runtime);
+                let get_local_tbl_fn =
+                    self.intrinsic(Intrinsic::ErrCtx(ErrCtxIntrinsic::GetLocalTable));
+                let create_local_handle_fn =
+                    self.intrinsic(Intrinsic::ErrCtx(ErrCtxIntrinsic::CreateLocalHandle));
+                let host_rep_fn = self.intrinsic(Intrinsic::ErrCtx(ErrCtxIntrinsic::HostRep));
+
+                let tmp = self.tmp();
+                let (table, handle) = (format!("errCtxTable{tmp}"), format!("errCtxHandle{tmp}"));
+                uwriteln!(
+                    self.src,
+                    "const {table} = {get_local_tbl_fn}({component_idx}, {table_idx}, {{ upsert: true }});
+                    const {{ waitableIdx: {handle} }} = {create_local_handle_fn}({component_idx}, {table}, {host_rep_fn}({item}));"
+                );
+                results.push(handle);
             }
 
             Instruction::FutureLower { ty, .. } => {
@@ -2732,7 +3095,8 @@ impl Bindgen for FunctionBindgen<'_> {
                 else {
                     unreachable!("invalid resource table observed during future lower");
                 };
-                let future_table_idx = future_table_idx_ty.as_u32();
+                let future_table_idx =
+                    index_expr(future_table_idx_ty.as_u32(), self.shared_runtime);
 
                 // Generate payload metadata ('elemMeta')
                 let (
@@ -2811,7 +3175,7 @@ impl Bindgen for FunctionBindgen<'_> {
                             component_idx,
                             get_realloc_fn,
                             ..
-                        } = state.get_js_exprs();
+                        } = state.get_js_exprs(self.shared_runtime);
                         (component_idx, get_realloc_fn)
                     } else {
                         ("-1".into(), "undefined".into())
@@ -2999,11 +3363,13 @@ impl Bindgen for FunctionBindgen<'_> {
                                 ("null".into(), "null".into())
                             };
 
-                        let future_table_idx = future_table_idx_ty.as_u32();
+                        let future_table_idx =
+                            index_expr(future_table_idx_ty.as_u32(), self.shared_runtime);
 
                         // Set task memory index and memory object
                         let component_idx_expr = if let Some(state) = &self.component_state {
-                            let ComponentStateJsExprs { component_idx, .. } = state.get_js_exprs();
+                            let ComponentStateJsExprs { component_idx, .. } =
+                                state.get_js_exprs(self.shared_runtime);
                             component_idx
                         } else {
                             "-1".into()
@@ -3096,7 +3462,8 @@ impl Bindgen for FunctionBindgen<'_> {
                     unreachable!("invalid resource table observed during stream lower");
                 };
 
-                let stream_table_idx = stream_table_idx_ty.as_u32();
+                let stream_table_idx =
+                    index_expr(stream_table_idx_ty.as_u32(), self.shared_runtime);
 
                 let (
                     payload_type_name_js,
@@ -3172,7 +3539,7 @@ impl Bindgen for FunctionBindgen<'_> {
                             component_idx,
                             get_realloc_fn,
                             ..
-                        } = state.get_js_exprs();
+                        } = state.get_js_exprs(self.shared_runtime);
                         (component_idx, get_realloc_fn)
                     } else {
                         ("-1".into(), "undefined".into())
@@ -3323,11 +3690,13 @@ impl Bindgen for FunctionBindgen<'_> {
                                 ("null".into(), "null".into())
                             };
 
-                        let stream_table_idx = stream_table_idx_ty.as_u32();
+                        let stream_table_idx =
+                            index_expr(stream_table_idx_ty.as_u32(), self.shared_runtime);
 
                         // Set task memory index and memory object
                         let component_idx_expr = if let Some(state) = &self.component_state {
-                            let ComponentStateJsExprs { component_idx, .. } = state.get_js_exprs();
+                            let ComponentStateJsExprs { component_idx, .. } =
+                                state.get_js_exprs(self.shared_runtime);
                             component_idx
                         } else {
                             "-1".into()
@@ -3388,7 +3757,8 @@ impl Bindgen for FunctionBindgen<'_> {
 
                 // Set task memory index and memory object
                 let component_idx_expr = if let Some(state) = &self.component_state {
-                    let ComponentStateJsExprs { component_idx, .. } = state.get_js_exprs();
+                    let ComponentStateJsExprs { component_idx, .. } =
+                        state.get_js_exprs(self.shared_runtime);
                     component_idx
                 } else {
                     "-1".into()
@@ -3444,6 +3814,13 @@ impl Bindgen for FunctionBindgen<'_> {
                 // then we should start it, given that the task we have recently created (however we got to
                 // the async return) is going to continue to be polled soon (via the driver loop).
                 //
+                // Host-provided (imported) functions are traced around the host call,
+                // while async exports complete once their task does
+                let trace_exit = if self.for_import != Some(true) {
+                    self.trace_exit("taskRes")
+                } else {
+                    String::new()
+                };
                 uwriteln!(
                     self.src,
                     r#"
@@ -3482,6 +3859,7 @@ impl Bindgen for FunctionBindgen<'_> {
                       }});
 
                       let taskRes = await task.completionPromise();
+                      {trace_exit}
                       if (task.getErrHandling() === 'throw-result-err') {{
                           if (typeof taskRes !== 'object') {{
                               return {return_task_res};
@@ -3671,4 +4049,81 @@ mod tests {
 
         assert_eq!(check_fn_intrinsic, format!("{check_fn}.bind(null, 'u64')",));
     }
+
+    #[test]
+    fn test_error_context_without_component_state_is_an_error() {
+        let mut resolve = Resolve::new();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                  package test:errors;
+                  world test {
+                      export echo: func(e: error-context) -> error-context;
+                  }
+                "#,
+            )
+            .unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+        let wit_parser::WorldItem::Function(func) =
+            resolve.worlds[world].exports.values().next().unwrap()
+        else {
+            unreachable!("unexpected non-function export");
+        };
+        let mut sizes = SizeAlign::default();
+        sizes.fill(&resolve);
+
+        let resource_map = ResourceMap::new();
+        let mut intrinsics = BTreeSet::new();
+        let tracing_prefix = String::new();
+        let mut f = FunctionBindgen {
+            resource_map: &resource_map,
+            type_reprs: &TypeReprMap::new(),
+            clear_resource_borrows: false,
+            intrinsics: &mut intrinsics,
+            valid_lifting_optimization: false,
+            sizes: &sizes,
+            err: ErrHandling::None,
+            tmp: 0,
+            src: source::Source::default(),
+            block_storage: Vec::new(),
+            blocks: Vec::new(),
+            params: vec!["arg0".into()],
+            memory: None,
+            realloc: None,
+            post_return: None,
+            tracing_prefix: &tracing_prefix,
+            tracing_enabled: false,
+            tracing_fn_id: &tracing_prefix,
+            import_journal: None,
+            encoding: StringEncoding::UTF8,
+            callee: "callee",
+            callee_resource_dynamic: false,
+            resolve: &resolve,
+            requires_async_porcelain: false,
+            is_async: false,
+            wrap_async_future_result: false,
+            iface_name: None,
+            asmjs: false,
+            shared_runtime: false,
+            component_state: None,
+            for_import: Some(false),
+            error: None,
+        };
+        wit_bindgen_core::abi::call(
+            &resolve,
+            wit_parser::abi::AbiVariant::GuestExport,
+            wit_bindgen_core::abi::LiftLower::LowerArgsLiftResults,
+            func,
+            &mut f,
+            false,
+        );
+
+        // Error contexts can only be lowered and lifted with the state of the component
+        assert!(
+            matches!(f.error, Some(TranspileError::UnsupportedFeature { .. })),
+            "{:?}",
+            f.error
+        );
+    }
 }
@@ -0,0 +1,500 @@
+//! Inspection of components without performing a full transpilation
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use heck::ToLowerCamelCase;
+use wit_parser::{
+    Function, Handle, InterfaceId, Resolve, Type, TypeDefKind, TypeId, WorldId, WorldItem,
+};
+
+use crate::esm_bindgen::{export_alias, interface_export_name};
+use crate::transpile_bindgen::{function_export_name, map_import_with_implements, validate_map};
+use crate::{
+    AsyncMode, ComponentInfo, CoreModuleItems, ExportKind, TranspileOpts, TranspileResult,
+    decode_component, matches_any_pattern,
+};
+
+/// Whether a function is imported into or exported from a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionDirection {
+    Import,
+    Export,
+}
+
+/// Signature and binding information for a single component function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionInfo {
+    /// Whether the function is imported or exported
+    pub direction: FunctionDirection,
+    /// The interface the function belongs to (e.g. `wasi:cli/run@0.2.0`),
+    /// or `None` for functions that are imported/exported by the world directly
+    pub interface: Option<String>,
+    /// The WIT name of the function (e.g. `[method]input.read`)
+    pub name: String,
+    /// Parameter names along with their WIT types
+    pub params: Vec<(String, String)>,
+    /// The WIT result type of the function, if any
+    pub result: Option<String>,
+    /// Whether the function is a component model (P3) `async` function
+    pub is_async: bool,
+    /// Whether the function will be bound with async (i.e. JSPI) porcelain,
+    /// as configured by [`TranspileOpts::async_mode`]
+    pub async_porcelain: bool,
+    /// Names of the resources the function is a member of or takes/returns handles to
+    pub resources: Vec<String>,
+}
+
+/// Inspect a Wasm component, returning the import specifiers and exports that
+/// transpiling it with the given options would produce, along with the
+/// signatures of every function it imports and exports.
+///
+/// Unlike [`transpile`](crate::transpile), no core Wasm modules are translated
+/// and no code is generated.
+pub fn inspect(component: &[u8], opts: TranspileOpts) -> TranspileResult<ComponentInfo> {
+    let (resolve, world_id, core_modules) = decode_component(component)?;
+
+    validate_map(&opts.map)?;
+
+    Ok(Inspector::new(&resolve, world_id, &opts).finish(&core_modules))
+}
+
+struct Inspector<'a> {
+    resolve: &'a Resolve,
+    world: WorldId,
+    opts: &'a TranspileOpts,
+    async_imports: HashSet<String>,
+    async_exports: HashSet<String>,
+}
+
+impl<'a> Inspector<'a> {
+    fn new(resolve: &'a Resolve, world: WorldId, opts: &'a TranspileOpts) -> Self {
+        let (async_imports, async_exports) = match opts.async_mode.clone() {
+            None | Some(AsyncMode::Sync) => (Default::default(), Default::default()),
+            Some(
+                AsyncMode::JavaScriptPromiseIntegration { imports, exports }
+                | AsyncMode::Asyncify { imports, exports },
+            ) => (imports.into_iter().collect(), exports.into_iter().collect()),
+        };
+        Self {
+            resolve,
+            world,
+            opts,
+            async_imports,
+            async_exports,
+        }
+    }
+
+    fn finish(self, core_modules: &CoreModuleItems) -> ComponentInfo {
+        let world = &self.resolve.worlds[self.world];
+        let mut imports = BTreeSet::new();
+        let mut functions = Vec::new();
+
+        for (key, item) in world.imports.iter() {
+            let import_name = self.resolve.name_world_key(key);
+            let implements = self.resolve.implements_value(key, item);
+            match item {
+                WorldItem::Interface { id, .. } => {
+                    let (specifier, _) = map_import_with_implements(
+                        &self.opts.map,
+                        &import_name,
+                        implements.as_deref(),
+                    );
+                    imports.insert(specifier);
+                    self.interface_functions(
+                        FunctionDirection::Import,
+                        &import_name,
+                        *id,
+                        &mut functions,
+                    );
+                }
+                WorldItem::Function(func) => {
+                    let (specifier, _) = map_import_with_implements(
+                        &self.opts.map,
+                        self.root_import_name(func),
+                        None,
+                    );
+                    imports.insert(specifier);
+                    functions.push(self.function(FunctionDirection::Import, None, func));
+                }
+                WorldItem::Type { .. } => {}
+            }
+        }
+        for import_name in core_modules.imports.iter() {
+            let (specifier, _) = map_import_with_implements(&self.opts.map, import_name, None);
+            imports.insert(specifier);
+        }
+
+        let mut exports = BTreeMap::new();
+        for (key, item) in world.exports.iter() {
+            match item {
+                WorldItem::Interface { id, .. } => {
+                    let export_name = interface_export_name(&self.resolve.name_world_key(key));
+                    exports.insert(export_name, ExportKind::Instance);
+                    self.interface_functions(
+                        FunctionDirection::Export,
+                        &self.resolve.name_world_key(key),
+                        *id,
+                        &mut functions,
+                    );
+                }
+                WorldItem::Function(func) => {
+                    let export_name =
+                        function_export_name(self.resolve, func, &self.resolve.name_world_key(key));
+                    exports.insert(export_name, ExportKind::LiftedFunction);
+                    functions.push(self.function(FunctionDirection::Export, None, func));
+                }
+                WorldItem::Type { .. } => {}
+            }
+        }
+        for export_name in core_modules.exports.iter() {
+            exports.insert(export_name.to_lower_camel_case(), ExportKind::Module);
+        }
+
+        // Interfaces exported by id are additionally exported under their
+        // short name, when that name does not collide with another export
+        let mut aliases = BTreeMap::new();
+        for (export_name, kind) in exports.iter() {
+            if let Some(alias) = export_alias(export_name)
+                && !exports.contains_key(&alias)
+                && !aliases.contains_key(&alias)
+            {
+                aliases.insert(alias, kind.clone());
+            }
+        }
+
+        ComponentInfo {
+            imports: imports.into_iter().collect(),
+            exports: aliases.into_iter().chain(exports).collect(),
+            functions,
+        }
+    }
+
+    /// The import name used for a function imported directly by the world,
+    /// which for resource members is the name of the resource
+    fn root_import_name<'b>(&'b self, func: &'b Function) -> &'b str {
+        match func.kind.resource() {
+            Some(ty) => self.resource_name(ty),
+            None => &func.name,
+        }
+    }
+
+    fn interface_functions(
+        &self,
+        direction: FunctionDirection,
+        iface_name: &str,
+        id: InterfaceId,
+        functions: &mut Vec<FunctionInfo>,
+    ) {
+        for func in self.resolve.interfaces[id].functions.values() {
+            functions.push(self.function(direction, Some(iface_name), func));
+        }
+    }
+
+    fn function(
+        &self,
+        direction: FunctionDirection,
+        iface_name: Option<&str>,
+        func: &Function,
+    ) -> FunctionInfo {
+        let async_funcs = match direction {
+            FunctionDirection::Import => &self.async_imports,
+            FunctionDirection::Export => &self.async_exports,
+        };
+
+        let mut resources = BTreeSet::new();
+        if let Some(ty) = func.kind.resource() {
+            resources.insert(self.resource_name(ty).to_string());
+        }
+        for param in func.params.iter() {
+            self.collect_resources(&param.ty, &mut resources);
+        }
+        if let Some(ty) = &func.result {
+            self.collect_resources(ty, &mut resources);
+        }
+
+        FunctionInfo {
+            direction,
+            interface: iface_name.map(String::from),
+            name: func.name.clone(),
+            params: func
+                .params
+                .iter()
+                .map(|param| (param.name.clone(), self.type_name(&param.ty)))
+                .collect(),
+            result: func.result.as_ref().map(|ty| self.type_name(ty)),
+            is_async: func.kind.is_async(),
+            async_porcelain: matches_any_pattern(iface_name, &func.name, async_funcs),
+            resources: resources.into_iter().collect(),
+        }
+    }
+
+    fn resource_name(&self, ty: TypeId) -> &str {
+        self.resolve.types[crate::dealias(self.resolve, ty)]
+            .name
+            .as_deref()
+            .unwrap_or("<unnamed>")
+    }
+
+    /// Collect the names of all resources referenced by handles within a type
+    fn collect_resources(&self, ty: &Type, resources: &mut BTreeSet<String>) {
+        let Type::Id(id) = ty else {
+            return;
+        };
+        let mut visit = |ty: &Type| self.collect_resources(ty, resources);
+        match &self.resolve.types[*id].kind {
+            TypeDefKind::Handle(Handle::Own(ty) | Handle::Borrow(ty)) => {
+                resources.insert(self.resource_name(*ty).to_string());
+            }
+            TypeDefKind::Record(record) => record.fields.iter().for_each(|f| visit(&f.ty)),
+            TypeDefKind::Tuple(tuple) => tuple.types.iter().for_each(visit),
+            TypeDefKind::Variant(variant) => variant
+                .cases
+                .iter()
+                .filter_map(|c| c.ty.as_ref())
+                .for_each(visit),
+            TypeDefKind::Result(result) => {
+                result.ok.iter().chain(result.err.iter()).for_each(visit)
+            }
+            TypeDefKind::Map(k, v) => {
+                visit(k);
+                visit(v);
+            }
+            TypeDefKind::Option(ty)
+            | TypeDefKind::List(ty)
+            | TypeDefKind::FixedLengthList(ty, _)
+            | TypeDefKind::Type(ty) => visit(ty),
+            TypeDefKind::Future(ty) | TypeDefKind::Stream(ty) => ty.iter().for_each(visit),
+            TypeDefKind::Resource
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Unknown => {}
+        }
+    }
+
+    /// Render a type as it would be written in WIT
+    fn type_name(&self, ty: &Type) -> String {
+        let id = match ty {
+            Type::Bool => return "bool".into(),
+            Type::U8 => return "u8".into(),
+            Type::U16 => return "u16".into(),
+            Type::U32 => return "u32".into(),
+            Type::U64 => return "u64".into(),
+            Type::S8 => return "s8".into(),
+            Type::S16 => return "s16".into(),
+            Type::S32 => return "s32".into(),
+            Type::S64 => return "s64".into(),
+            Type::F32 => return "f32".into(),
+            Type::F64 => return "f64".into(),
+            Type::Char => return "char".into(),
+            Type::String => return "string".into(),
+            Type::ErrorContext => return "error-context".into(),
+            Type::Id(id) => *id,
+        };
+
+        let ty = &self.resolve.types[id];
+        if let Some(name) = &ty.name {
+            return name.clone();
+        }
+
+        let maybe = |ty: &Option<Type>| match ty {
+            Some(ty) => self.type_name(ty),
+            None => "_".into(),
+        };
+        match &ty.kind {
+            TypeDefKind::Handle(Handle::Own(ty)) => self.resource_name(*ty).to_string(),
+            TypeDefKind::Handle(Handle::Borrow(ty)) => {
+                format!("borrow<{}>", self.resource_name(*ty))
+            }
+            TypeDefKind::Tuple(tuple) => format!(
+                "tuple<{}>",
+                tuple
+                    .types
+                    .iter()
+                    .map(|ty| self.type_name(ty))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeDefKind::Option(ty) => format!("option<{}>", self.type_name(ty)),
+            TypeDefKind::Result(result) => match (&result.ok, &result.err) {
+                (None, None) => "result".into(),
+                (Some(ok), None) => format!("result<{}>", self.type_name(ok)),
+                (ok, err) => format!("result<{}, {}>", maybe(ok), maybe(err)),
+            },
+            TypeDefKind::List(ty) => format!("list<{}>", self.type_name(ty)),
+            TypeDefKind::FixedLengthList(ty, len) => {
+                format!("list<{}, {len}>", self.type_name(ty))
+            }
+            TypeDefKind::Map(k, v) => format!("map<{}, {}>", self.type_name(k), self.type_name(v)),
+            TypeDefKind::Future(ty) => match ty {
+                Some(ty) => format!("future<{}>", self.type_name(ty)),
+                None => "future".into(),
+            },
+            TypeDefKind::Stream(ty) => match ty {
+                Some(ty) => format!("stream<{}>", self.type_name(ty)),
+                None => "stream".into(),
+            },
+            TypeDefKind::Type(ty) => self.type_name(ty),
+            TypeDefKind::Record(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Variant(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Unknown => ty.kind.as_str().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(wit: &str) -> Vec<u8> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str("test.wit", wit).unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+        let mut module =
+            wit_component::dummy_module(&resolve, world, wit_parser::ManglingAndAbi::Standard32);
+        wit_component::embed_component_metadata(
+            &mut module,
+            &resolve,
+            world,
+            wit_component::StringEncoding::UTF8,
+        )
+        .unwrap();
+        wit_component::ComponentEncoder::default()
+            .module(&module)
+            .unwrap()
+            .encode()
+            .unwrap()
+    }
+
+    const WIT: &str = r#"
+        package test:inspect@0.1.0;
+
+        interface streams {
+            resource input {
+                read: func(len: u64) -> result<list<u8>, string>;
+            }
+            open: func(path: string) -> input;
+        }
+
+        interface run {
+            run: func() -> result;
+        }
+
+        world inspected {
+            import streams;
+            import log: func(msg: string);
+            export run;
+            export hello-world: func(names: list<string>) -> option<tuple<u32, string>>;
+        }
+    "#;
+
+    #[test]
+    fn test_inspect_component() {
+        let map = [(
+            "test:inspect/streams".to_string(),
+            "./streams.js".to_string(),
+        )]
+        .into_iter()
+        .collect();
+        let opts = TranspileOpts::builder()
+            .name("inspected".to_string())
+            .map(map)
+            .async_mode(AsyncMode::JavaScriptPromiseIntegration {
+                imports: vec!["test:inspect/streams@0.1.0#open".into()],
+                exports: vec![],
+            })
+            .build();
+
+        let info = inspect(&component(WIT), opts).unwrap();
+
+        assert_eq!(info.imports, vec!["./streams.js", "log"]);
+        assert_eq!(
+            info.exports,
+            vec![
+                ("run".to_string(), ExportKind::Instance),
+                ("helloWorld".to_string(), ExportKind::LiftedFunction),
+                ("test:inspect/run@0.1.0".to_string(), ExportKind::Instance),
+            ]
+        );
+
+        let read = info
+            .functions
+            .iter()
+            .find(|f| f.name == "[method]input.read")
+            .unwrap();
+        assert_eq!(read.direction, FunctionDirection::Import);
+        assert_eq!(
+            read.interface.as_deref(),
+            Some("test:inspect/streams@0.1.0")
+        );
+        assert_eq!(
+            read.params,
+            vec![
+                ("self".to_string(), "borrow<input>".to_string()),
+                ("len".to_string(), "u64".to_string()),
+            ]
+        );
+        assert_eq!(read.result.as_deref(), Some("result<list<u8>, string>"));
+        assert_eq!(read.resources, vec!["input"]);
+        assert!(!read.async_porcelain);
+
+        let open = info.functions.iter().find(|f| f.name == "open").unwrap();
+        assert_eq!(open.result.as_deref(), Some("input"));
+        assert_eq!(open.resources, vec!["input"]);
+        assert!(open.async_porcelain);
+
+        let hello = info
+            .functions
+            .iter()
+            .find(|f| f.name == "hello-world")
+            .unwrap();
+        assert_eq!(hello.direction, FunctionDirection::Export);
+        assert_eq!(hello.interface, None);
+        assert_eq!(hello.result.as_deref(), Some("option<tuple<u32, string>>"));
+    }
+
+    #[test]
+    fn test_inspect_matches_transpile() {
+        let exports_wit = r#"
+            package test:exports@0.1.0;
+
+            interface run {
+                run: func();
+            }
+
+            interface other {
+                resource thing {
+                    constructor();
+                }
+            }
+
+            world exporter {
+                resource counter {
+                    constructor();
+                    get: func() -> u32;
+                }
+                export run;
+                export other;
+                export local-run: interface {
+                    go: func();
+                }
+                export do-it: func();
+            }
+        "#;
+        let fixture = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../packages/jco-transpile/test/fixtures/components/runtime/resource-borrow-simple.component.wasm"
+        ))
+        .unwrap();
+
+        for component in [component(WIT), component(exports_wit), fixture] {
+            let opts = TranspileOpts::builder().name("component".into()).build();
+            let info = inspect(&component, opts.clone()).unwrap();
+            let transpiled = crate::transpile(&component, opts).unwrap();
+            assert_eq!(info.imports, transpiled.imports);
+            assert_eq!(info.exports, transpiled.exports);
+        }
+    }
+}
@@ -4,6 +4,7 @@ use std::fmt::Write as _;
 
 use crate::intrinsics::p3::async_future::AsyncFutureIntrinsic;
 use crate::intrinsics::p3::async_stream::AsyncStreamIntrinsic;
+use crate::intrinsics::p3::async_task::AsyncTaskIntrinsic;
 use crate::intrinsics::p3::waitable::WaitableIntrinsic;
 use crate::intrinsics::{Intrinsic, RenderIntrinsicsArgs};
 use crate::source::Source;
@@ -55,9 +56,34 @@ pub enum ComponentIntrinsic {
     /// forever.
     ///
     ComponentStateSetAllError,
+
+    /// Detects async tasks that can never make progress again
+    ///
+    /// Once every live task is blocked on a waitable set that only the blocked
+    /// tasks themselves could make ready (with nothing pending on the host side) for
+    /// longer than the configured timeout, the blocked tasks are failed with an
+    /// error that describes what each one is waiting on.
+    ///
+    /// ```ts
+    /// const _asyncDeadlockDetector: { check(): void };
+    /// ```
+    AsyncDeadlockDetector,
 }
 
 impl ComponentIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::GlobalAsyncStateMap,
+            Self::GetOrCreateAsyncState,
+            Self::BackpressureInc,
+            Self::BackpressureDec,
+            Self::ComponentAsyncStateClass,
+            Self::ComponentStateSetAllError,
+            Self::AsyncDeadlockDetector,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -77,11 +103,12 @@ impl ComponentIntrinsic {
             Self::BackpressureDec => "backpressureDec",
             Self::ComponentAsyncStateClass => "ComponentAsyncState",
             Self::ComponentStateSetAllError => "_ComponentStateSetAllError",
+            Self::AsyncDeadlockDetector => "_asyncDeadlockDetector",
         }
     }
 
     /// Render an intrinsic to a string
-    pub fn render(&self, output: &mut Source, _render_args: &RenderIntrinsicsArgs<'_>) {
+    pub fn render(&self, output: &mut Source, render_args: &RenderIntrinsicsArgs<'_>) {
         match self {
             Self::GlobalAsyncStateMap => {
                 let var_name = Self::GlobalAsyncStateMap.name();
@@ -92,6 +119,8 @@ impl ComponentIntrinsic {
                 let debug_log_fn = Intrinsic::DebugLog.name();
                 let backpressure_inc_fn = Self::BackpressureInc.name();
                 let get_or_create_async_state_fn = Self::GetOrCreateAsyncState.name();
+                let trace_changed =
+                    render_args.async_trace("backpressureChanged(componentIdx, newValue)");
                 output.push_str(&format!(
                     r#"
                     function {backpressure_inc_fn}(componentIdx) {{
@@ -100,6 +129,7 @@ impl ComponentIntrinsic {
                         if (!state) {{ throw new Error(`missing component state for component [${{componentIdx}}]`); }}
                         const newValue = state.incrementBackpressure();
                         {debug_log_fn}('[{backpressure_inc_fn}()] incremented', {{ componentIdx, newValue }});
+                        {trace_changed}
                     }}
                     "#,
                 ));
@@ -109,6 +139,8 @@ impl ComponentIntrinsic {
                 let debug_log_fn = Intrinsic::DebugLog.name();
                 let backpressure_dec_fn = Self::BackpressureDec.name();
                 let get_or_create_async_state_fn = Self::GetOrCreateAsyncState.name();
+                let trace_changed =
+                    render_args.async_trace("backpressureChanged(componentIdx, newValue)");
                 output.push_str(&format!(
                     "
                     function {backpressure_dec_fn}(componentIdx) {{
@@ -116,6 +148,7 @@ impl ComponentIntrinsic {
                         const state = {get_or_create_async_state_fn}(componentIdx);
                         const newValue = state.decrementBackpressure();
                         {debug_log_fn}('[{backpressure_dec_fn}()] decremented', {{ componentIdx, newValue }});
+                        {trace_changed}
                     }}
                 "
                 ));
@@ -136,6 +169,11 @@ impl ComponentIntrinsic {
                 let promise_with_resolvers_fn = Intrinsic::PromiseWithResolversPonyfill.name();
                 let stream_readable_end_class =
                     Intrinsic::AsyncStream(AsyncStreamIntrinsic::StreamReadableEndClass).name();
+                let check_deadlock = if render_args.async_deadlock_timeout() > 0 {
+                    format!("{}.check();", Self::AsyncDeadlockDetector.name())
+                } else {
+                    String::new()
+                };
 
                 output.push_str(&format!(
                     r#"
@@ -442,6 +480,7 @@ impl ComponentIntrinsic {
                                     // TODO(threads): it's thread cancellation we should be checking for below, not task
                                     resolve(!task.isCancelled());
                                 }},
+                                fail: (err) => reject(err),
                             }});
 
                             this.runTickLoop();
@@ -456,12 +495,21 @@ impl ComponentIntrinsic {
                             meta.resume();
                         }}
 
+                        suspendedTasks() {{ return [...this.#suspendedTasksByTaskID.values()]; }}
+
+                        failSuspendedTask(taskID, err) {{
+                            const meta = this.#removeSuspendedTaskMeta(taskID);
+                            if (!meta) {{ return; }}
+                            meta.fail(err);
+                        }}
+
                         async runTickLoop() {{
                             if (this.#tickLoop !== null) {{ return; }}
                             this.#tickLoop = 1;
                             setTimeout(async () => {{
                                 let done = this.tick();
                                 while (!done) {{
+                                    {check_deadlock}
                                     await new Promise((resolve) => setTimeout(resolve, 30));
                                     done = this.tick();
                                 }}
@@ -878,6 +926,103 @@ impl ComponentIntrinsic {
                     "#
                 ));
             }
+
+            Self::AsyncDeadlockDetector => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let detector = Self::AsyncDeadlockDetector.name();
+                let async_state_map = Self::GlobalAsyncStateMap.name();
+                let global_task_map =
+                    Intrinsic::AsyncTask(AsyncTaskIntrinsic::GlobalAsyncCurrentTaskMap).name();
+                let dump_tasks_fn = Intrinsic::AsyncTask(AsyncTaskIntrinsic::DumpTasks).name();
+                let timeout_ms = render_args.async_deadlock_timeout();
+                output.push_str(&format!(
+                    r#"
+                    const {detector} = {{
+                        blocked: [],
+                        since: null,
+
+                        reset() {{
+                            this.blocked = [];
+                            this.since = null;
+                        }},
+
+                        check() {{
+                            const blocked = [];
+                            for (const cstate of {async_state_map}.values()) {{
+                                for (const meta of cstate.suspendedTasks()) {{
+                                    // Only tasks waiting on a waitable set are considered, anything else
+                                    // (e.g. waiting for backpressure) may still be released
+                                    if (meta.task.isRejected() || !meta.task.waitingOn() || meta.readyFn()) {{
+                                        return this.reset();
+                                    }}
+                                    blocked.push({{ cstate, meta }});
+                                }}
+                            }}
+                            if (blocked.length === 0) {{ return this.reset(); }}
+
+                            // Any other live task (e.g. a host import call in progress) could still
+                            // unblock the suspended ones
+                            const blockedTasks = new Set(blocked.map(({{ meta }}) => meta.task));
+                            for (const taskMetas of {global_task_map}.values()) {{
+                                for (const {{ task }} of taskMetas) {{
+                                    if (!task || task.isExited() || task.isRejected()) {{ continue; }}
+                                    if (!blockedTasks.has(task)) {{ return this.reset(); }}
+                                }}
+                            }}
+
+                            // Every waitable must depend only on blocked tasks, rather than the host
+                            for (const {{ meta }} of blocked) {{
+                                for (const waitable of meta.task.waitingOn().waitables()) {{
+                                    const owner = waitable.owner();
+                                    if (owner?.getChildTask) {{
+                                        if (!blockedTasks.has(owner.getChildTask())) {{ return this.reset(); }}
+                                    }} else if (!owner?.isHostDriven || owner.isHostDriven()) {{
+                                        return this.reset();
+                                    }}
+                                }}
+                            }}
+
+                            // The same suspensions must persist for the whole timeout
+                            const metas = blocked.map(({{ meta }}) => meta);
+                            const unchanged = metas.length === this.blocked.length
+                                && metas.every(m => this.blocked.includes(m));
+                            if (!unchanged) {{
+                                this.blocked = metas;
+                                this.since = Date.now();
+                                return;
+                            }}
+                            if (Date.now() - this.since < {timeout_ms}) {{ return; }}
+                            this.reset();
+
+                            const snapshots = {dump_tasks_fn}().filter(t => metas.some(m => Number(m.taskID) === t.taskID));
+                            const lines = [
+                                `deadlock detected: no async task can make progress and nothing is pending on the host (blocked for at least {timeout_ms}ms)`,
+                            ];
+                            for (const t of snapshots) {{
+                                lines.push(`task [${{t.taskID}}] (component [${{t.componentIdx}}], fn [${{t.entryFnName}}]) is waiting on ${{t.waitingOn.waitableSet}}:`);
+                                if (t.waitingOn.waitables.length === 0) {{ lines.push('  (no waitables)'); }}
+                                for (const w of t.waitingOn.waitables) {{
+                                    if (w.subtask) {{
+                                        lines.push(`  - ${{w.target}}: subtask [${{w.subtask.id}}] calling [${{w.subtask.fnName}}], blocked on task [${{w.subtask.childTaskID}}]`);
+                                    }} else if (w.end) {{
+                                        lines.push(`  - ${{w.target}}: ${{w.end.target}}, other end ${{w.end.otherEnd}}`);
+                                    }} else {{
+                                        lines.push(`  - ${{w.target}}`);
+                                    }}
+                                }}
+                            }}
+                            const err = new Error(lines.join('\n'));
+                            err.blockedTasks = snapshots;
+
+                            {debug_log_fn}('[{detector}#check()] deadlock detected', {{ blockedTasks: snapshots }});
+                            for (const {{ cstate, meta }} of blocked) {{
+                                cstate.failSuspendedTask(meta.taskID, err);
+                            }}
+                        }},
+                    }};
+                    "#
+                ));
+            }
         }
     }
 }
@@ -50,6 +50,31 @@ pub enum ConversionIntrinsic {
 }
 
 impl ConversionIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::I32ToF32,
+            Self::I64ToF64,
+            Self::F32ToI32,
+            Self::F64ToI64,
+            Self::I32ToChar,
+            Self::ToBigInt64,
+            Self::ToBigUint64,
+            Self::ToInt16,
+            Self::ToInt32,
+            Self::ToInt8,
+            Self::ToString,
+            Self::ToUint16,
+            Self::ToUint32,
+            Self::ToUint8,
+            Self::ToResultString,
+            Self::RequireValidNumericPrimitive,
+            Self::IsValidNumericPrimitive,
+            Self::MergeBigInt64,
+            Self::SplitBigInt64,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -396,7 +421,7 @@ impl ConversionIntrinsic {
             Self::MergeBigInt64 => {
                 let name = self.name();
                 uwriteln!(output, "
-                const {name} = (l, h) => (BigInt(l|0) | (BigInt(h|0) << 32n));
+                const {name} = (l, h) => (BigInt(l >>> 0) | (BigInt(h|0) << 32n));
                 ");
             },
         }
@@ -19,6 +19,11 @@ pub enum JsHelperIntrinsic {
 }
 
 impl JsHelperIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[Self::EmptyFunc, Self::DataView]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
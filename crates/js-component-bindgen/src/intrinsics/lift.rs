@@ -207,6 +207,41 @@ pub enum LiftIntrinsic {
 }
 
 impl LiftIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::LiftFlatBool,
+            Self::LiftFlatS8,
+            Self::LiftFlatU8,
+            Self::LiftFlatS16,
+            Self::LiftFlatU16,
+            Self::LiftFlatS32,
+            Self::LiftFlatU32,
+            Self::LiftFlatS64,
+            Self::LiftFlatU64,
+            Self::LiftFlatFloat32,
+            Self::LiftFlatFloat64,
+            Self::LiftFlatChar,
+            Self::LiftFlatStringAny,
+            Self::LiftFlatStringUtf8,
+            Self::LiftFlatStringUtf16,
+            Self::LiftFlatRecord,
+            Self::LiftFlatVariant,
+            Self::LiftFlatList,
+            Self::LiftFlatMap,
+            Self::LiftFlatTuple,
+            Self::LiftFlatFlags,
+            Self::LiftFlatEnum,
+            Self::LiftFlatOption,
+            Self::LiftFlatResult,
+            Self::LiftFlatOwn,
+            Self::LiftFlatBorrow,
+            Self::LiftFlatFuture,
+            Self::LiftFlatStream,
+            Self::LiftFlatErrorContext,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -710,7 +745,7 @@ impl LiftIntrinsic {
                         val = {decoder}.decode(new Uint8Array(ctx.memory.buffer, start, codeUnits));
 
                         ctx.storagePtr += 8;
-                        if (ctx.storageLen !== undefined) {{ ctx.storagelen -= 8; }}
+                        if (ctx.storageLen !== undefined) {{ ctx.storageLen -= 8; }}
 
                         return [val, ctx];
                     }}
@@ -738,12 +773,16 @@ impl LiftIntrinsic {
                             return [val, ctx];
                         }}
 
-                        const data = new DataView(ctx.memory.buffer)
-                        const start = data.getUint32(ctx.storagePtr, vals[0], true);
-                        const codeUnits = data.getUint32(ctx.storagePtr, vals[0] + 4, true);
+                        const rem = ctx.storagePtr % 4;
+                        if (rem !== 0) {{ ctx.storagePtr += (4 - rem); }}
+
+                        const data = new DataView(ctx.memory.buffer);
+                        const start = data.getUint32(ctx.storagePtr, true);
+                        const codeUnits = data.getUint32(ctx.storagePtr + 4, true);
                         val = {decoder}.decode(new Uint16Array(ctx.memory.buffer, start, codeUnits));
-                        ctx.storagePtr = ctx.storagePtr + 2 * codeUnits;
-                        if (ctx.storageLen !== undefined) {{ ctx.storageLen = ctx.storageLen - 2 * codeUnits }}
+
+                        ctx.storagePtr += 8;
+                        if (ctx.storageLen !== undefined) {{ ctx.storageLen -= 8; }}
 
                         return [val, ctx];
                     }}
@@ -1396,6 +1435,7 @@ impl LiftIntrinsic {
                             if (streamEnd.isWritable()) {{ throw new Error('only readable streams can be lifted'); }}
                             if (!streamEnd.isIdleState()) {{ throw new Error('streams must be in idle state'); }}
                             if (streamEnd.isInSet()) {{ throw new Error('trap: streams in waitable sets cannot be lifted'); }}
+                            streamEnd.setHostHeld(true);
 
                             const stream = new {external_stream_class}({{
                                 globalRep: streamEnd.globalStreamMapRep(),
@@ -1416,17 +1456,17 @@ impl LiftIntrinsic {
             // component model value, we assume that the host has taken control of it until it has been lowered
             // into a component
             //
-            // When we lift an error context, we increase the global ref count as it must be accounted for
-            // at the component model (host) level.
+            // When we lift an error context, the host value that represents it holds a reference to the
+            // global error context (see `ErrCtxIntrinsic::HostValue`).
             //
             // This means that *before* lifting an error context object's `val` property represents a local
-            // handle (an index into a component-local error context table), but *after* lifting, it represents
-            // a component-global "rep" (i.e. the component model represenation).
+            // handle (an index into a component-local error context table), but *after* lifting, it is an
+            // `ErrorContext` which stands for a component-global "rep" (i.e. the component model represenation).
             //
             Self::LiftFlatErrorContext => {
                 let debug_log_fn = Intrinsic::DebugLog.name();
                 let get_err_ctx_local_table_fn = ErrCtxIntrinsic::GetLocalTable.name();
-                let err_ctx_ref_count_add_fn = ErrCtxIntrinsic::GlobalRefCountAdd.name();
+                let err_ctx_host_value_fn = ErrCtxIntrinsic::HostValue.name();
                 let lift_flat_error_fn = self.name();
                 let get_or_create_async_state_fn =
                     Intrinsic::Component(ComponentIntrinsic::GetOrCreateAsyncState).name();
@@ -1460,9 +1500,7 @@ impl LiftIntrinsic {
                         errCtx.refCount -= 1;
                         // NOTE: we aovoid doing clean up eagerly here
 
-                        {err_ctx_ref_count_add_fn}(errCtx.globalRep);
-
-                        return [errCtx.globalRep, ctx];
+                        return [{err_ctx_host_value_fn}(errCtx.globalRep), ctx];
                     }}
                 "#));
             }
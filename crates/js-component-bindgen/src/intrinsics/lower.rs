@@ -207,6 +207,41 @@ pub enum LowerIntrinsic {
 }
 
 impl LowerIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::LowerFlatBool,
+            Self::LowerFlatS8,
+            Self::LowerFlatU8,
+            Self::LowerFlatS16,
+            Self::LowerFlatU16,
+            Self::LowerFlatS32,
+            Self::LowerFlatU32,
+            Self::LowerFlatS64,
+            Self::LowerFlatU64,
+            Self::LowerFlatFloat32,
+            Self::LowerFlatFloat64,
+            Self::LowerFlatChar,
+            Self::LowerFlatStringAny,
+            Self::LowerFlatStringUtf8,
+            Self::LowerFlatStringUtf16,
+            Self::LowerFlatRecord,
+            Self::LowerFlatVariant,
+            Self::LowerFlatList,
+            Self::LowerFlatMap,
+            Self::LowerFlatTuple,
+            Self::LowerFlatFlags,
+            Self::LowerFlatEnum,
+            Self::LowerFlatOption,
+            Self::LowerFlatResult,
+            Self::LowerFlatOwn,
+            Self::LowerFlatBorrow,
+            Self::LowerFlatFuture,
+            Self::LowerFlatStream,
+            Self::LowerFlatErrorContext,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -1249,8 +1284,8 @@ impl LowerIntrinsic {
                 ));
             }
 
-            // When a component-model level error context is lowered, it contains the global error-context
-            // and not a component-local handle value (as it did pre-lift).
+            // When a component-model level error context is lowered, it is a host value that stands for the
+            // global error-context and not a component-local handle value (as it did pre-lift).
             //
             // By lowering the error context into a given component (w/ a given error context table)
             // we translate the global component model level rep into a local handle.
@@ -1261,7 +1296,7 @@ impl LowerIntrinsic {
                 let lower_flat_error_context_fn = self.name();
                 let lower_u32_fn = Self::LowerFlatU32.name();
                 let create_local_handle_fn = ErrCtxIntrinsic::CreateLocalHandle.name();
-                let err_ctx_global_ref_count_add_fn = ErrCtxIntrinsic::GlobalRefCountAdd.name();
+                let err_ctx_host_rep_fn = ErrCtxIntrinsic::HostRep.name();
                 let get_or_create_async_state_fn = ComponentIntrinsic::GetOrCreateAsyncState.name();
                 let global_tbl = ErrCtxIntrinsic::ComponentGlobalTable.name();
                 let get_local_tbl_fn = ErrCtxIntrinsic::GetLocalTable.name();
@@ -1273,15 +1308,17 @@ impl LowerIntrinsic {
                         {debug_log_fn}('[{lower_flat_error_context_fn}()] args', {{ errCtxTableIdx, ctx }});
                         const {{ memory, realloc, vals, storagePtr, storageLen, componentIdx }} = ctx;
 
-                        const errCtxGlobalRep = vals[0];
+                        const errCtxGlobalRep = {err_ctx_host_rep_fn}(vals[0]);
 
                         const globalTable = {global_tbl}.get();
                         const globalErrCtx = globalTable.get(errCtxGlobalRep);
 
-                        // Clean up the previous error context, if necessary
-                        const prevComponentState = {get_or_create_async_state_fn}(globalErrCtx.componentIdx);
-                        const prevLocalErrCtx = prevComponentState.handles.get(globalErrCtx.waitableIdx);
-                        if (prevLocalErrCtx.refCount === 0) {{
+                        // Clean up the previous error context (if it came from a component), if necessary
+                        const prevComponentState = globalErrCtx.componentIdx === undefined
+                            ? undefined
+                            : {get_or_create_async_state_fn}(globalErrCtx.componentIdx);
+                        const prevLocalErrCtx = prevComponentState?.handles.get(globalErrCtx.waitableIdx);
+                        if (prevLocalErrCtx?.refCount === 0) {{
                             const removed = prevComponentState.remove(globalErrCtx.waitableIdx);
                             if (!removed) {{
                                 throw new Error(`failed to remove err ctx [${{globalErrCtx.waitableIdx}}], component [${{globalErrCtx.componentIdx}}]`);
@@ -1310,8 +1347,6 @@ impl LowerIntrinsic {
                             localErrCtx.localTableIdx = errCtxTableIdx;
                         }}
 
-                        {err_ctx_global_ref_count_add_fn}(errCtxGlobalRep, -1);
-
                         {lower_u32_fn}({{ memory, realloc, vals: [handle], storagePtr, storageLen, componentIdx }});
                     }}
                 "#));
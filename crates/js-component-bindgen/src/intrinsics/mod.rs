@@ -4,7 +4,7 @@ use std::collections::{BTreeSet, HashSet};
 use std::fmt::Write;
 
 use crate::source::Source;
-use crate::{TranspileOpts, uwrite, uwriteln};
+use crate::{ModuleFormat, TranspileOpts, uwrite, uwriteln};
 
 pub(crate) mod conversion;
 use conversion::ConversionIntrinsic;
@@ -66,12 +66,36 @@ pub enum Intrinsic {
     /// Enable debug logging
     DebugLog,
 
+    /// Dispatcher of tracing events (function entry, exit and errors), which are
+    /// passed to hooks registered at runtime, or logged to the console by default
+    Tracer,
+
+    /// Recorder of async task, subtask, waitable, backpressure and stream events
+    /// as Chrome trace events, which can be viewed with tools like Perfetto
+    AsyncTrace,
+
     /// Global setting for determinism (used in async)
     GlobalAsyncDeterminism,
 
+    /// Source of randomness for async scheduling decisions, which is either
+    /// `Math.random()` or a pseudo-random generator that can be (re)seeded at runtime
+    AsyncRandom,
+
     /// Randomly produce a boolean true/false
     CoinFlip,
 
+    /// Shared state of asyncify-instrumented core modules: the state global,
+    /// the stack of saved locals, and the `Promise` being suspended on
+    AsyncifyContext,
+
+    /// Wrap an async host import so that it suspends asyncify-instrumented
+    /// core Wasm (in place of `WebAssembly.Suspending`)
+    AsyncifySuspending,
+
+    /// Wrap an asyncify-instrumented core export so that it drives the
+    /// unwind/rewind loop (in place of `WebAssembly.promising`)
+    AsyncifyPromising,
+
     // Basic type helpers
     ConstantI32Max,
     ConstantI32Min,
@@ -80,6 +104,7 @@ pub enum Intrinsic {
     AsyncFunctionCtor,
 
     Base64Compile,
+    Base64Decode,
     ClampGuest,
     FetchCompile,
 
@@ -94,6 +119,10 @@ pub enum Intrinsic {
     ScopeId,
     HandleTables,
 
+    /// Allocate a range of component instance and table indices, used by components
+    /// that share a runtime so that their entries in global tables do not collide
+    AllocIndexBase,
+
     /// Class that conforms to a `ReadableStreams`-like interface and is usable externally
     ///
     /// This is normally the `ReadableStream` class provided by the platform itself.
@@ -109,6 +138,12 @@ pub enum Intrinsic {
     GetErrorPayload,
     GetErrorPayloadString,
 
+    /// Journal of the calls made to host imports, along with their results
+    ///
+    /// Calls are recorded with their lifted arguments and the results returned by the
+    /// host, and can be replayed in order without calling into the host.
+    ImportJournal,
+
     /// Class that manages (and synchronizes) writes to managed buffers
     ManagedBufferClass,
 
@@ -187,11 +222,142 @@ impl Intrinsic {
                 );
             }
 
+            Intrinsic::AsyncRandom => {
+                let seed = match args.determinism_profile {
+                    AsyncDeterminismProfile::Seeded { seed } => seed.to_string(),
+                    AsyncDeterminismProfile::Random | AsyncDeterminismProfile::Deterministic => {
+                        "null".into()
+                    }
+                };
+                // Seeded values are produced by mulberry32, so that a given seed always
+                // produces the same sequence of scheduling decisions
+                output.push_str(&format!(
+                    "
+                    const {var_name} = {{
+                        state: {seed},
+                        seed(seed) {{
+                            if (!Number.isInteger(seed)) {{ throw new TypeError('async scheduling seed must be an integer'); }}
+                            this.state = seed >>> 0;
+                        }},
+                        next() {{
+                            if (this.state === null) {{ return Math.random(); }}
+                            let t = this.state = (this.state + 0x6d2b79f5) >>> 0;
+                            t = Math.imul(t ^ (t >>> 15), t | 1);
+                            t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+                            return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+                        }},
+                    }};
+                    ",
+                    var_name = self.name(),
+                ));
+            }
+
             Intrinsic::CoinFlip => {
                 uwriteln!(
                     output,
-                    "const {var_name} = () => {{ return Math.random() > 0.5; }};",
+                    "const {var_name} = () => {{ return {async_random}.next() > 0.5; }};",
                     var_name = self.name(),
+                    async_random = Intrinsic::AsyncRandom.name(),
+                );
+            }
+
+            Intrinsic::AsyncifyContext => {
+                let var_name = self.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      const {var_name} = {{
+                          // 0 when running normally, 1 when unwinding and 2 when rewinding
+                          state: globalThis.WebAssembly
+                              ? new globalThis.WebAssembly.Global({{ value: 'i32', mutable: true }}, 0)
+                              : {{ value: 0 }},
+                          stack: [],
+                          driving: false,
+                          pending: null,
+                          resumed: null,
+                          push: (value) => {{ {var_name}.stack.push(value); }},
+                          pop: () => {var_name}.stack.pop(),
+                      }};
+                    "#,
+                );
+            }
+
+            Intrinsic::AsyncifySuspending => {
+                let fn_name = self.name();
+                let asyncify = Intrinsic::AsyncifyContext.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      function {fn_name}(fn) {{
+                          return function(...args) {{
+                              if ({asyncify}.state.value === 2) {{
+                                  {asyncify}.state.value = 0;
+                                  const resumed = {asyncify}.resumed;
+                                  {asyncify}.resumed = null;
+                                  if (resumed.rejected) {{ throw resumed.value; }}
+                                  return resumed.value;
+                              }}
+
+                              const ret = fn.apply(this, args);
+                              if (!ret || typeof ret.then !== 'function') {{ return ret; }}
+                              if (!{asyncify}.driving) {{
+                                  throw new Error('cannot suspend on an async import outside of an async export');
+                              }}
+
+                              {asyncify}.pending = ret;
+                              {asyncify}.state.value = 1;
+                              // The result is discarded while unwinding, and converts to any number type
+                              return '0';
+                          }};
+                      }}
+                    "#,
+                );
+            }
+
+            Intrinsic::AsyncifyPromising => {
+                let fn_name = self.name();
+                let asyncify = Intrinsic::AsyncifyContext.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      function {fn_name}(fn) {{
+                          return async function(...args) {{
+                              // Each call saves its locals to its own stack, so that calls can be interleaved
+                              const stack = [];
+                              let resumed = null;
+                              while (true) {{
+                                  const {{ stack: prevStack, driving: prevDriving }} = {asyncify};
+                                  {asyncify}.stack = stack;
+                                  {asyncify}.driving = true;
+                                  if (resumed) {{
+                                      {asyncify}.resumed = resumed;
+                                      {asyncify}.state.value = 2;
+                                  }}
+
+                                  let ret;
+                                  try {{
+                                      ret = fn.apply(this, args);
+                                  }} catch (err) {{
+                                      {asyncify}.state.value = 0;
+                                      throw err;
+                                  }} finally {{
+                                      {asyncify}.stack = prevStack;
+                                      {asyncify}.driving = prevDriving;
+                                  }}
+                                  if ({asyncify}.state.value !== 1) {{ return ret; }}
+
+                                  {asyncify}.state.value = 0;
+                                  const pending = {asyncify}.pending;
+                                  {asyncify}.pending = null;
+                                  try {{
+                                      resumed = {{ value: await pending }};
+                                  }} catch (value) {{
+                                      resumed = {{ rejected: true, value }};
+                                  }}
+                              }}
+                          }};
+                      }}
+                    "#,
                 );
             }
 
@@ -266,6 +432,26 @@ impl Intrinsic {
                 }
             }
 
+            Intrinsic::Base64Decode => {
+                if !args.transpile_opts.nodejs_compat_disabled {
+                    uwriteln!(
+                        output,
+                        r#"
+                          const base64Decode = str => typeof Buffer !== 'undefined'
+                              ? new Uint8Array(Buffer.from(str, 'base64'))
+                              : Uint8Array.from(atob(str), b => b.charCodeAt(0));
+                        "#
+                    );
+                } else {
+                    uwriteln!(
+                        output,
+                        r#"
+                          const base64Decode = str => Uint8Array.from(atob(str), b => b.charCodeAt(0));
+                        "#
+                    );
+                }
+            }
+
             Intrinsic::ClampGuest => {
                 uwriteln!(
                     output,
@@ -353,6 +539,154 @@ impl Intrinsic {
                 )
             }
 
+            Intrinsic::ImportJournal => {
+                let hop = Intrinsic::HasOwnProperty.name();
+                let symbol_dispose = Intrinsic::SymbolDispose.name();
+                // Values are encoded into JSON-compatible values, with values that JSON cannot
+                // represent tagged by a `$` property (which never appears in lifted records).
+                //
+                // Objects other than plain objects (e.g. resources) are recorded by identity,
+                // and replayed as placeholder instances of their (imported) class.
+                output.push_str(&format!(
+                    "
+                    const {var_name} = {{
+                        entries: [],
+                        position: 0,
+                        classes: new Map(),
+                        objects: new Map(),
+                        ids: new WeakMap(),
+                        nextId: 0,
+                        register(classes) {{
+                            for (const [name, cls] of Object.entries(classes)) {{ this.classes.set(name, cls); }}
+                        }},
+                        encode(val) {{
+                            switch (typeof val) {{
+                                case 'undefined': return {{ $: 'undefined' }};
+                                case 'bigint': return {{ $: 'bigint', val: val.toString() }};
+                                case 'number':
+                                    if (Number.isFinite(val) && !Object.is(val, -0)) {{ return val; }}
+                                    return {{ $: 'number', val: Object.is(val, -0) ? '-0' : String(val) }};
+                                case 'string':
+                                case 'boolean':
+                                    return val;
+                            }}
+                            if (val === null) {{ return null; }}
+                            if (Array.isArray(val)) {{ return val.map(v => this.encode(v)); }}
+                            if (ArrayBuffer.isView(val)) {{
+                                return {{ $: 'typed-array', type: val.constructor.name, val: Array.from(val, v => this.encode(v)) }};
+                            }}
+                            if (val instanceof Map) {{
+                                return {{ $: 'map', val: Array.from(val, ([k, v]) => [this.encode(k), this.encode(v)]) }};
+                            }}
+                            const proto = Object.getPrototypeOf(val);
+                            if (proto === Object.prototype || proto === null) {{
+                                return Object.fromEntries(Object.entries(val).map(([k, v]) => [k, this.encode(v)]));
+                            }}
+                            let id = this.ids.get(val);
+                            if (id === undefined) {{
+                                id = this.nextId++;
+                                this.ids.set(val, id);
+                            }}
+                            const entry = [...this.classes].find(([, cls]) => val instanceof cls);
+                            return entry ? {{ $: 'resource', class: entry[0], id }} : {{ $: 'resource', id }};
+                        }},
+                        decode(val) {{
+                            if (Array.isArray(val)) {{ return val.map(v => this.decode(v)); }}
+                            if (val === null || typeof val !== 'object') {{ return val; }}
+                            switch (val.$) {{
+                                case undefined:
+                                    return Object.fromEntries(Object.entries(val).map(([k, v]) => [k, this.decode(v)]));
+                                case 'undefined': return undefined;
+                                case 'bigint': return BigInt(val.val);
+                                case 'number': return Number(val.val);
+                                case 'typed-array': {{
+                                    const ctor = globalThis[val.type];
+                                    if (typeof ctor !== 'function' || !ArrayBuffer.isView(ctor.prototype)) {{
+                                        throw new TypeError(`invalid typed array [${{val.type}}] in import journal`);
+                                    }}
+                                    return new ctor(val.val.map(v => this.decode(v)));
+                                }}
+                                case 'map': return new Map(val.val.map(([k, v]) => [this.decode(k), this.decode(v)]));
+                                case 'resource': {{
+                                    let obj = this.objects.get(val.id);
+                                    if (obj !== undefined) {{ return obj; }}
+                                    const cls = this.classes.get(val.class);
+                                    if (!cls) {{
+                                        throw new TypeError(`cannot replay object [${{val.id}}] of unknown class [${{val.class ?? '<unknown>'}}] from import journal`);
+                                    }}
+                                    // Placeholders stand in for host objects, and must not be disposed by the host
+                                    obj = Object.create(cls.prototype, {{ [{symbol_dispose}]: {{ value() {{}} }} }});
+                                    this.objects.set(val.id, obj);
+                                    this.ids.set(obj, val.id);
+                                    this.nextId = Math.max(this.nextId, val.id + 1);
+                                    return obj;
+                                }}
+                                default: throw new TypeError(`invalid value [${{val.$}}] in import journal`);
+                            }}
+                        }},
+                        encodeThrown(err) {{
+                            if (err && {hop}.call(err, 'payload')) {{ return {{ message: err.message, payload: this.encode(err.payload) }}; }}
+                            if (err instanceof Error) {{ return {{ message: err.message }}; }}
+                            return {{ value: this.encode(err) }};
+                        }},
+                        decodeThrown(thrown) {{
+                            if ({hop}.call(thrown, 'value')) {{ return this.decode(thrown.value); }}
+                            const err = new Error(thrown.message);
+                            if ({hop}.call(thrown, 'payload')) {{ err.payload = this.decode(thrown.payload); }}
+                            return err;
+                        }},
+                        record(fnName, args, classes, call) {{
+                            this.register(classes);
+                            const entry = {{ fn: fnName, args: this.encode(args) }};
+                            this.entries.push(entry);
+                            let ret;
+                            try {{
+                                ret = call();
+                            }} catch (err) {{
+                                entry.thrown = this.encodeThrown(err);
+                                throw err;
+                            }}
+                            if (ret instanceof Promise) {{
+                                entry.async = true;
+                                return ret.then(
+                                    val => {{ entry.result = this.encode(val); return val; }},
+                                    err => {{ entry.thrown = this.encodeThrown(err); throw err; }},
+                                );
+                            }}
+                            entry.result = this.encode(ret);
+                            return ret;
+                        }},
+                        replay(fnName, args, classes) {{
+                            this.register(classes);
+                            const entry = this.entries[this.position];
+                            if (!entry) {{ throw new Error(`import journal exhausted, unexpected call to [${{fnName}}]`); }}
+                            const encodedArgs = JSON.stringify(this.encode(args));
+                            const recordedArgs = JSON.stringify(entry.args);
+                            if (entry.fn !== fnName || encodedArgs !== recordedArgs) {{
+                                throw new Error(`import journal diverged at entry [${{this.position}}]: expected call to [${{entry.fn}}] with args ${{recordedArgs}}, got call to [${{fnName}}] with args ${{encodedArgs}}`);
+                            }}
+                            if (!{hop}.call(entry, 'result') && !{hop}.call(entry, 'thrown')) {{
+                                throw new Error(`import journal entry [${{this.position}}] for [${{fnName}}] has no recorded result`);
+                            }}
+                            this.position++;
+                            const thrown = {hop}.call(entry, 'thrown') ? this.decodeThrown(entry.thrown) : undefined;
+                            if (entry.async) {{
+                                return thrown !== undefined ? Promise.reject(thrown) : Promise.resolve(this.decode(entry.result));
+                            }}
+                            if (thrown !== undefined) {{ throw thrown; }}
+                            return this.decode(entry.result);
+                        }},
+                        load(entries) {{
+                            if (!Array.isArray(entries)) {{ throw new TypeError('import journal must be an array of entries'); }}
+                            this.entries = entries;
+                            this.position = 0;
+                        }},
+                    }};
+                    ",
+                    var_name = self.name(),
+                ));
+            }
+
             Intrinsic::WebIdl(w) => w.render(output),
 
             Intrinsic::HandleTables => {
@@ -373,11 +707,20 @@ impl Intrinsic {
 
             Intrinsic::InstantiateCore => {
                 if !args.instantiation_occurred {
-                    output.push_str(
-                        "
-                    const instantiateCore = WebAssembly.instantiate;
-                ",
-                    )
+                    if args.transpile_opts.compile_core_to_js {
+                        // Core modules compiled to JS export their instantiation function
+                        output.push_str(
+                            "
+                        const instantiateCore = (module, imports) => ({ exports: module(imports) });
+                    ",
+                        )
+                    } else {
+                        output.push_str(
+                            "
+                        const instantiateCore = WebAssembly.instantiate;
+                    ",
+                        )
+                    }
                 }
             }
 
@@ -404,6 +747,17 @@ impl Intrinsic {
                 uwriteln!(output, "let {name} = 0;");
             }
 
+            Intrinsic::AllocIndexBase => output.push_str(
+                "
+                let NEXT_INDEX_BASE = 0;
+                function _allocIndexBase(count) {
+                    const base = NEXT_INDEX_BASE;
+                    NEXT_INDEX_BASE += count;
+                    return base;
+                }
+            ",
+            ),
+
             Intrinsic::SymbolResourceHandle => output.push_str(
                 "
                 const symbolRscHandle = Symbol('handle');
@@ -462,6 +816,182 @@ impl Intrinsic {
                 ));
             }
 
+            Intrinsic::Tracer => {
+                let to_result_string =
+                    Intrinsic::Conversion(ConversionIntrinsic::ToResultString).name();
+                // Spans carry the function, task and start time of a call between its
+                // enter and exit (or error) events. Errors thrown by hooks are logged rather
+                // than propagated, so that tracing cannot interfere with the traced calls.
+                output.push_str(&format!(
+                    "
+                    const {var_name} = {{
+                        hooks: null,
+                        set(hooks) {{
+                            if (hooks !== null && (typeof hooks !== 'object' || Array.isArray(hooks))) {{
+                                throw new TypeError('tracer must be an object with enter, exit and/or error hooks, or null');
+                            }}
+                            this.hooks = hooks;
+                        }},
+                        label(fn) {{
+                            return `[iface=\"${{fn.iface ?? '<no iface>'}}\", function=\"${{fn.name}}\"]`;
+                        }},
+                        dispatch(hook, event) {{
+                            try {{
+                                hook?.call(this.hooks, event);
+                            }} catch (err) {{
+                                console.error('tracer hook failed', err);
+                            }}
+                        }},
+                        enter(fn, taskID, componentIdx, args) {{
+                            const span = {{ fn, taskID, componentIdx, start: performance.now() }};
+                            if (this.hooks) {{
+                                this.dispatch(this.hooks.enter, {{ fn, taskID, componentIdx, args, time: span.start }});
+                            }} else {{
+                                console.error(`${{this.label(fn)}} call ${{fn.params.map((p, i) => `${{p}}=${{args[i]}}`).join(', ')}}`);
+                            }}
+                            return span;
+                        }},
+                        exit(span, result) {{
+                            const {{ fn, taskID, componentIdx, start }} = span;
+                            const time = performance.now();
+                            if (this.hooks) {{
+                                this.dispatch(this.hooks.exit, {{ fn, taskID, componentIdx, result, time, duration: time - start }});
+                            }} else {{
+                                console.error(`${{this.label(fn)}} return${{result === undefined ? '' : ` result=${{{to_result_string}(result)}}`}}`);
+                            }}
+                        }},
+                        error(span, error) {{
+                            const {{ fn, taskID, componentIdx, start }} = span;
+                            const time = performance.now();
+                            if (this.hooks) {{
+                                this.dispatch(this.hooks.error, {{ fn, taskID, componentIdx, error, time, duration: time - start }});
+                            }} else {{
+                                console.error(`${{this.label(fn)}} error ${{error}}`);
+                            }}
+                        }},
+                    }};
+                    ",
+                    var_name = self.name(),
+                ));
+            }
+
+            Intrinsic::AsyncTrace => {
+                // Events use the Chrome trace event format, with a thread per component.
+                // Task, subtask and stream operation lifetimes are async spans, while joins,
+                // subtask progress and backpressure are instant and counter events.
+                output.push_str(&format!(
+                    "
+                    const {var_name} = {{
+                        events: [],
+                        threads: new Set(),
+                        nextID: 0,
+                        emit(ph, cat, name, componentIdx, fields) {{
+                            if (!this.threads.has(componentIdx)) {{
+                                this.threads.add(componentIdx);
+                                this.events.push({{
+                                    name: 'thread_name',
+                                    ph: 'M',
+                                    pid: 1,
+                                    tid: componentIdx,
+                                    args: {{ name: componentIdx === -1 ? 'host' : `component [${{componentIdx}}]` }},
+                                }});
+                            }}
+                            this.events.push({{ name, cat, ph, ts: performance.now() * 1000, pid: 1, tid: componentIdx, ...fields }});
+                        }},
+                        instant(cat, name, componentIdx, args) {{
+                            this.emit('i', cat, name, componentIdx, {{ s: 't', args }});
+                        }},
+                        taskName(task) {{
+                            return task.entryFnName() ?? `task [${{task.id()}}]`;
+                        }},
+                        taskCreated(task) {{
+                            this.emit('b', 'task', this.taskName(task), task.componentIdx(), {{
+                                id: `task-${{task.id()}}`,
+                                args: {{
+                                    taskID: Number(task.id()),
+                                    isAsync: task.isAsync(),
+                                    parentSubtaskID: task.getParentSubtask() ? Number(task.getParentSubtask().id()) : null,
+                                }},
+                            }});
+                        }},
+                        taskReturned(task) {{
+                            this.instant('task', 'task.return', task.componentIdx(), {{ taskID: Number(task.id()) }});
+                        }},
+                        taskCancelled(task) {{
+                            this.instant('task', 'task.cancel', task.componentIdx(), {{ taskID: Number(task.id()) }});
+                        }},
+                        taskExited(task) {{
+                            this.emit('e', 'task', this.taskName(task), task.componentIdx(), {{
+                                id: `task-${{task.id()}}`,
+                                args: {{ state: task.taskState() }},
+                            }});
+                        }},
+                        subtaskName(subtask) {{
+                            return subtask.fnName ?? `subtask [${{subtask.id()}}]`;
+                        }},
+                        subtaskCreated(subtask) {{
+                            this.emit('b', 'subtask', this.subtaskName(subtask), subtask.componentIdx(), {{
+                                id: `subtask-${{subtask.id()}}`,
+                                args: {{
+                                    subtaskID: Number(subtask.id()),
+                                    parentTaskID: Number(subtask.parentTaskID()),
+                                    isAsync: subtask.isAsync,
+                                }},
+                            }});
+                        }},
+                        subtaskStarted(subtask) {{
+                            const childTaskID = subtask.childTaskID();
+                            this.instant('subtask', 'subtask.start', subtask.componentIdx(), {{
+                                subtaskID: Number(subtask.id()),
+                                childTaskID: childTaskID === undefined ? null : Number(childTaskID),
+                            }});
+                        }},
+                        subtaskResolved(subtask, outcome) {{
+                            const childTaskID = subtask.childTaskID();
+                            const args = {{
+                                subtaskID: Number(subtask.id()),
+                                childTaskID: childTaskID === undefined ? null : Number(childTaskID),
+                            }};
+                            this.instant('subtask', `subtask.${{outcome}}`, subtask.componentIdx(), args);
+                            this.emit('e', 'subtask', this.subtaskName(subtask), subtask.componentIdx(), {{
+                                id: `subtask-${{subtask.id()}}`,
+                                args: {{ ...args, outcome }},
+                            }});
+                        }},
+                        waitableJoined(componentIdx, waitableRep, waitableSetRep, waitable) {{
+                            const name = waitableSetRep === 0 ? 'waitable.leave' : 'waitable.join';
+                            this.instant('waitable', name, componentIdx, {{ waitableRep, waitableSetRep, target: waitable.target }});
+                        }},
+                        backpressureChanged(componentIdx, value) {{
+                            this.emit('C', 'backpressure', `backpressure [component ${{componentIdx}}]`, componentIdx, {{
+                                args: {{ value }},
+                            }});
+                        }},
+                        async streamOp(name, componentIdx, args, op) {{
+                            const id = `stream-${{++this.nextID}}`;
+                            this.emit('b', 'stream', name, componentIdx, {{ id, args }});
+                            try {{
+                                const result = await op();
+                                this.emit('e', 'stream', name, componentIdx, {{ id, args: {{ result }} }});
+                                return result;
+                            }} catch (err) {{
+                                this.emit('e', 'stream', name, componentIdx, {{ id, args: {{ error: String(err) }} }});
+                                throw err;
+                            }}
+                        }},
+                        traceEvents() {{
+                            return {{ traceEvents: [...this.events], displayTimeUnit: 'ms' }};
+                        }},
+                        clear() {{
+                            this.events = [];
+                            this.threads.clear();
+                        }},
+                    }};
+                    ",
+                    var_name = self.name(),
+                ));
+            }
+
             Intrinsic::PromiseWithResolversPonyfill => {
                 let fn_name = self.name();
                 output.push_str(&format!(
@@ -1066,15 +1596,25 @@ impl Intrinsic {
 }
 
 /// Profile for determinism to be used by async implementation
-#[derive(Debug, Default, PartialEq, Eq)]
+///
+/// The profile controls the scheduling decisions that the component model leaves
+/// to the host, such as whether a task that is ready to continue yields anyway,
+/// and which of several waitables with pending events is delivered first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum AsyncDeterminismProfile {
     /// Allow random ordering non-determinism
     #[default]
     Random,
 
-    /// Require determinism
-    #[allow(unused)]
+    /// Require determinism, always making the same scheduling decisions
     Deterministic,
+
+    /// Make random scheduling decisions with a pseudo-random generator, which
+    /// produces the same interleaving for a given seed
+    ///
+    /// The seed can also be changed at runtime via `_util.seedAsyncScheduler(seed)`.
+    Seeded { seed: u32 },
 }
 
 impl std::fmt::Display for AsyncDeterminismProfile {
@@ -1085,6 +1625,7 @@ impl std::fmt::Display for AsyncDeterminismProfile {
             match self {
                 Self::Deterministic => "deterministic",
                 Self::Random => "random",
+                Self::Seeded { .. } => "seeded",
             }
         )
     }
@@ -1106,8 +1647,31 @@ pub struct RenderIntrinsicsArgs<'a> {
     pub(crate) transpile_opts: &'a TranspileOpts,
 }
 
+/// Default time (in milliseconds) async tasks must be deadlocked before they are failed
+const DEFAULT_ASYNC_DEADLOCK_TIMEOUT_MS: u32 = 1000;
+
+impl RenderIntrinsicsArgs<'_> {
+    /// Render a statement that records an event with [`Intrinsic::AsyncTrace`],
+    /// or nothing if async tracing is disabled
+    pub(crate) fn async_trace(&self, call: &str) -> String {
+        if self.transpile_opts.async_trace {
+            format!("{}.{call};", Intrinsic::AsyncTrace.name())
+        } else {
+            String::new()
+        }
+    }
+
+    /// Time (in milliseconds) async tasks must be deadlocked before they are failed,
+    /// where zero means deadlocks are never detected
+    pub(crate) fn async_deadlock_timeout(&self) -> u32 {
+        self.transpile_opts
+            .async_deadlock_timeout
+            .unwrap_or(DEFAULT_ASYNC_DEADLOCK_TIMEOUT_MS)
+    }
+}
+
 /// Intrinsics that should be rendered as early as possible
-const EARLY_INTRINSICS: [Intrinsic; 43] = [
+const EARLY_INTRINSICS: [Intrinsic; 44] = [
     Intrinsic::PromiseWithResolversPonyfill,
     Intrinsic::SymbolDispose,
     Intrinsic::SymbolAsyncIterator,
@@ -1124,6 +1688,7 @@ const EARLY_INTRINSICS: [Intrinsic; 43] = [
     Intrinsic::LookupMemoriesForComponent,
     Intrinsic::RegisterGlobalMemoryForComponent,
     Intrinsic::RepTableClass,
+    Intrinsic::AsyncRandom,
     Intrinsic::CoinFlip,
     Intrinsic::ScopeId,
     // Type checking helpers
@@ -1180,6 +1745,14 @@ pub fn render_intrinsics(args: RenderIntrinsicsArgs) -> Source {
     {
         args.intrinsics.insert(Intrinsic::HasOwnProperty);
     }
+    if args.intrinsics.contains(&Intrinsic::Tracer) {
+        args.intrinsics
+            .insert(Intrinsic::Conversion(ConversionIntrinsic::ToResultString));
+    }
+    if args.intrinsics.contains(&Intrinsic::ImportJournal) {
+        args.intrinsics.insert(Intrinsic::HasOwnProperty);
+        args.intrinsics.insert(Intrinsic::SymbolDispose);
+    }
     if args
         .intrinsics
         .contains(&Intrinsic::String(StringIntrinsic::Utf16Encode))
@@ -1187,6 +1760,12 @@ pub fn render_intrinsics(args: RenderIntrinsicsArgs) -> Source {
         args.intrinsics.insert(Intrinsic::IsLE);
     }
 
+    if args.intrinsics.contains(&Intrinsic::AsyncifySuspending)
+        || args.intrinsics.contains(&Intrinsic::AsyncifyPromising)
+    {
+        args.intrinsics.insert(Intrinsic::AsyncifyContext);
+    }
+
     if args
         .intrinsics
         .contains(&Intrinsic::Conversion(ConversionIntrinsic::F32ToI32))
@@ -1250,6 +1829,47 @@ pub fn render_intrinsics(args: RenderIntrinsicsArgs) -> Source {
         args.intrinsics.extend([&Intrinsic::IsLE]);
     }
 
+    // Error contexts that cross into or out of the host are tracked in the global table
+    if [
+        Intrinsic::Lift(LiftIntrinsic::LiftFlatErrorContext),
+        Intrinsic::Lower(LowerIntrinsic::LowerFlatErrorContext),
+    ]
+    .iter()
+    .any(|i| args.intrinsics.contains(i))
+    {
+        args.intrinsics.extend([
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::HostValue),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::HostRep),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::GlobalRefCountAdd),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::CreateLocalHandle),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::GetLocalTable),
+        ]);
+    }
+    if args
+        .intrinsics
+        .contains(&Intrinsic::ErrCtx(ErrCtxIntrinsic::HostValue))
+    {
+        args.intrinsics.extend([
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::HostClass),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::HostRefs),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::ComponentGlobalTable),
+            &Intrinsic::Component(ComponentIntrinsic::GetOrCreateAsyncState),
+        ]);
+    }
+    if args
+        .intrinsics
+        .contains(&Intrinsic::ErrCtx(ErrCtxIntrinsic::HostRep))
+    {
+        args.intrinsics.extend([
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::HostRefs),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::ReserveGlobalRep),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::ComponentGlobalTable),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::CreateLocalHandle),
+            &Intrinsic::ErrCtx(ErrCtxIntrinsic::GetLocalTable),
+            &Intrinsic::Component(ComponentIntrinsic::GetOrCreateAsyncState),
+        ]);
+    }
+
     // Attempting to perform a debug message hoist will require string encoding to memory
     if args.intrinsics.contains(&Intrinsic::ErrCtx(
         ErrCtxIntrinsic::ErrorContextDebugMessage,
@@ -1315,6 +1935,20 @@ pub fn render_intrinsics(args: RenderIntrinsicsArgs) -> Source {
         ]);
     }
 
+    if [
+        AsyncTaskIntrinsic::ThreadIndex,
+        AsyncTaskIntrinsic::ThreadNewIndirect,
+        AsyncTaskIntrinsic::ThreadSuspend,
+        AsyncTaskIntrinsic::ThreadSwitchTo,
+        AsyncTaskIntrinsic::ThreadUnsuspend,
+    ]
+    .into_iter()
+    .any(|i| args.intrinsics.contains(&Intrinsic::AsyncTask(i)))
+    {
+        args.intrinsics
+            .insert(Intrinsic::AsyncTask(AsyncTaskIntrinsic::ThreadClass));
+    }
+
     if args
         .intrinsics
         .contains(&Intrinsic::Waitable(WaitableIntrinsic::WaitableSetNew))
@@ -1357,6 +1991,27 @@ pub fn render_intrinsics(args: RenderIntrinsicsArgs) -> Source {
         args.intrinsics.extend([&Intrinsic::AsyncStream(
             AsyncStreamIntrinsic::GlobalStreamMap,
         )]);
+        if args.async_deadlock_timeout() > 0 {
+            args.intrinsics.insert(Intrinsic::Component(
+                ComponentIntrinsic::AsyncDeadlockDetector,
+            ));
+        }
+    }
+
+    if args.intrinsics.contains(&Intrinsic::Component(
+        ComponentIntrinsic::AsyncDeadlockDetector,
+    )) {
+        args.intrinsics
+            .insert(Intrinsic::AsyncTask(AsyncTaskIntrinsic::DumpTasks));
+    }
+
+    if args
+        .intrinsics
+        .contains(&Intrinsic::AsyncTask(AsyncTaskIntrinsic::DumpTasks))
+    {
+        args.intrinsics.insert(Intrinsic::Component(
+            ComponentIntrinsic::GlobalAsyncStateMap,
+        ));
     }
 
     if args
@@ -1669,13 +2324,225 @@ pub fn render_intrinsics(args: RenderIntrinsicsArgs) -> Source {
     output
 }
 
+/// Intrinsics that are always rendered into the component itself rather than
+/// the shared runtime, as they depend on how the component is instantiated
+const LOCAL_INTRINSICS: [Intrinsic; 1] = [Intrinsic::InstantiateCore];
+
+/// Whether an intrinsic can be provided by the shared runtime module
+pub(crate) fn is_shared_intrinsic(intrinsic: &Intrinsic) -> bool {
+    !LOCAL_INTRINSICS.contains(intrinsic)
+}
+
+/// Names of the intrinsics a component must import from the shared runtime,
+/// given the intrinsics it uses
+///
+/// Early intrinsics are always included, as they are always available to
+/// generated code when intrinsics are rendered inline.
+pub(crate) fn shared_runtime_imports(intrinsics: &BTreeSet<Intrinsic>) -> BTreeSet<&'static str> {
+    EARLY_INTRINSICS
+        .iter()
+        .chain(intrinsics.iter())
+        .filter(|i| is_shared_intrinsic(i))
+        .map(|i| i.name())
+        .collect()
+}
+
+/// Name of the module-level constant that holds the base index allocated for a
+/// component by [`Intrinsic::AllocIndexBase`] when using the shared runtime
+pub(crate) const INDEX_BASE: &str = "COMPONENT_INDEX_BASE";
+
+/// Render a component instance or table index as a JS expression
+///
+/// With a shared runtime, indices are offset by the base allocated for the
+/// component when it is loaded.
+pub(crate) fn index_expr(idx: u32, shared_runtime: bool) -> String {
+    if shared_runtime {
+        format!("({INDEX_BASE} + {idx})")
+    } else {
+        idx.to_string()
+    }
+}
+
+/// Name of the shared runtime module file for the given options
+///
+/// The name is versioned, and includes the options that change how intrinsics
+/// are rendered, so that components transpiled with compatible options share
+/// a single runtime.
+pub(crate) fn shared_runtime_file_name(opts: &TranspileOpts) -> String {
+    let mut name = format!("jco-runtime-{}", env!("CARGO_PKG_VERSION"));
+    if opts.strict {
+        name.push_str("-strict");
+    }
+    if opts.nodejs_compat_disabled {
+        name.push_str("-no-nodejs-compat");
+    }
+    if opts.module_format == ModuleFormat::CommonJs {
+        name.push_str("-cjs");
+    }
+    match opts.async_determinism {
+        AsyncDeterminismProfile::Random => {}
+        AsyncDeterminismProfile::Deterministic => name.push_str("-deterministic"),
+        AsyncDeterminismProfile::Seeded { seed } => name.push_str(&format!("-seed-{seed}")),
+    }
+    if opts.async_trace {
+        name.push_str("-async-trace");
+    }
+    if let Some(timeout) = opts.async_deadlock_timeout {
+        name.push_str(&format!("-deadlock-timeout-{timeout}"));
+    }
+    name.push('.');
+    name.push_str(opts.module_format.js_extension());
+    name
+}
+
+/// Render the shared runtime module, which defines and exports every intrinsic
+/// that can be shared between components
+///
+/// All intrinsics are included (rather than only those used by a given component)
+/// so that every component transpiled with the same version and options produces
+/// an identical runtime module, which holds a single copy of all global state.
+pub(crate) fn render_shared_runtime(opts: &TranspileOpts) -> Source {
+    let mut intrinsics: BTreeSet<Intrinsic> =
+        Intrinsic::all().filter(is_shared_intrinsic).collect();
+    let js_intrinsics = render_intrinsics(
+        RenderIntrinsicsArgs::builder()
+            .intrinsics(&mut intrinsics)
+            .determinism_profile(opts.async_determinism)
+            .transpile_opts(opts)
+            .build(),
+    );
+
+    let mut output = Source::default();
+    uwriteln!(
+        output,
+        "// Shared runtime for components transpiled by jco {}",
+        env!("CARGO_PKG_VERSION")
+    );
+    output.push_str(&js_intrinsics);
+    let names = intrinsics
+        .iter()
+        .filter(|i| is_shared_intrinsic(i))
+        .map(|i| i.name())
+        .collect::<BTreeSet<_>>();
+    let names = names.into_iter().collect::<Vec<_>>().join(", ");
+    // Written to the underlying string, as the exports must not pick up
+    // any indentation left over from the rendered intrinsics
+    let exports = output.as_mut_string();
+    match opts.module_format {
+        ModuleFormat::Esm => uwriteln!(exports, "\nexport {{ {names} }};"),
+        ModuleFormat::CommonJs => uwriteln!(exports, "\nmodule.exports = {{ {names} }};"),
+    }
+    output
+}
+
 impl Intrinsic {
+    /// All intrinsics
+    pub fn all() -> impl Iterator<Item = Intrinsic> {
+        [
+            Self::PromiseWithResolversPonyfill,
+            Self::DebugLog,
+            Self::Tracer,
+            Self::AsyncTrace,
+            Self::GlobalAsyncDeterminism,
+            Self::AsyncRandom,
+            Self::CoinFlip,
+            Self::AsyncifyContext,
+            Self::AsyncifySuspending,
+            Self::AsyncifyPromising,
+            Self::ConstantI32Max,
+            Self::ConstantI32Min,
+            Self::TypeCheckValidI32,
+            Self::TypeCheckAsyncFn,
+            Self::AsyncFunctionCtor,
+            Self::Base64Compile,
+            Self::Base64Decode,
+            Self::ClampGuest,
+            Self::FetchCompile,
+            Self::SymbolCabiDispose,
+            Self::SymbolCabiLower,
+            Self::SymbolResourceHandle,
+            Self::SymbolResourceRep,
+            Self::SymbolDispose,
+            Self::SymbolAsyncIterator,
+            Self::SymbolIterator,
+            Self::ScopeId,
+            Self::HandleTables,
+            Self::AllocIndexBase,
+            Self::PlatformReadableStreamClass,
+            Self::FinalizationRegistryCreate,
+            Self::ComponentError,
+            Self::GetErrorPayload,
+            Self::GetErrorPayloadString,
+            Self::ImportJournal,
+            Self::ManagedBufferClass,
+            Self::BufferManagerClass,
+            Self::GlobalBufferManager,
+            Self::RepTableClass,
+            Self::AsyncEventCodeEnum,
+            Self::IsLE,
+            Self::ThrowInvalidBool,
+            Self::ThrowUninitialized,
+            Self::HasOwnProperty,
+            Self::InstantiateCore,
+            Self::GlobalComponentMemoryMap,
+            Self::RegisterGlobalMemoryForComponent,
+            Self::LookupMemoriesForComponent,
+            Self::GlobalCurrentTaskMeta,
+            Self::GetGlobalCurrentTaskMetaFn,
+            Self::SetGlobalCurrentTaskMetaFn,
+            Self::WithGlobalCurrentTaskMetaFn,
+            Self::WithGlobalCurrentTaskMetaFnAsync,
+            Self::ClearGlobalCurrentTaskMetaFn,
+        ]
+        .into_iter()
+        .chain(JsHelperIntrinsic::all().iter().copied().map(Self::JsHelper))
+        .chain(WebIdlIntrinsic::all().iter().copied().map(Self::WebIdl))
+        .chain(
+            ConversionIntrinsic::all()
+                .iter()
+                .copied()
+                .map(Self::Conversion),
+        )
+        .chain(StringIntrinsic::all().iter().copied().map(Self::String))
+        .chain(ResourceIntrinsic::all().iter().copied().map(Self::Resource))
+        .chain(ErrCtxIntrinsic::all().iter().copied().map(Self::ErrCtx))
+        .chain(
+            AsyncTaskIntrinsic::all()
+                .iter()
+                .copied()
+                .map(Self::AsyncTask),
+        )
+        .chain(WaitableIntrinsic::all().iter().copied().map(Self::Waitable))
+        .chain(LiftIntrinsic::all().iter().copied().map(Self::Lift))
+        .chain(LowerIntrinsic::all().iter().copied().map(Self::Lower))
+        .chain(
+            AsyncStreamIntrinsic::all()
+                .iter()
+                .copied()
+                .map(Self::AsyncStream),
+        )
+        .chain(
+            AsyncFutureIntrinsic::all()
+                .iter()
+                .copied()
+                .map(Self::AsyncFuture),
+        )
+        .chain(
+            ComponentIntrinsic::all()
+                .iter()
+                .copied()
+                .map(Self::Component),
+        )
+        .chain(HostIntrinsic::all().iter().copied().map(Self::Host))
+    }
+
     pub fn get_global_names() -> impl IntoIterator<Item = &'static str> {
         JsHelperIntrinsic::get_global_names()
             .into_iter()
             .chain(vec![
                 // Intrinsic list exactly as below
                 "base64Compile",
+                "base64Decode",
                 "clampGuest",
                 "ComponentError",
                 "fetchCompile",
@@ -1741,13 +2608,16 @@ impl Intrinsic {
             Intrinsic::Host(i) => i.name(),
 
             Intrinsic::Base64Compile => "base64Compile",
+            Intrinsic::Base64Decode => "base64Decode",
             Intrinsic::ClampGuest => "clampGuest",
             Intrinsic::ComponentError => "ComponentError",
             Intrinsic::FetchCompile => "fetchCompile",
             Intrinsic::FinalizationRegistryCreate => "finalizationRegistryCreate",
             Intrinsic::GetErrorPayload => "getErrorPayload",
             Intrinsic::GetErrorPayloadString => "getErrorPayloadString",
+            Intrinsic::ImportJournal => "_importJournal",
             Intrinsic::HandleTables => "HANDLE_TABLES",
+            Intrinsic::AllocIndexBase => "_allocIndexBase",
             Intrinsic::HasOwnProperty => "hasOwnProperty",
             Intrinsic::InstantiateCore => "instantiateCore",
             Intrinsic::IsLE => "isLE",
@@ -1766,6 +2636,8 @@ impl Intrinsic {
 
             // Debugging
             Intrinsic::DebugLog => "_debugLog",
+            Intrinsic::Tracer => "_tracer",
+            Intrinsic::AsyncTrace => "_asyncTrace",
             Intrinsic::PromiseWithResolversPonyfill => "promiseWithResolvers",
 
             // Types
@@ -1780,7 +2652,11 @@ impl Intrinsic {
 
             // Async
             Intrinsic::GlobalAsyncDeterminism => "ASYNC_DETERMINISM",
+            Intrinsic::AsyncRandom => "_asyncRandom",
             Intrinsic::CoinFlip => "_coinFlip",
+            Intrinsic::AsyncifyContext => "ASYNCIFY",
+            Intrinsic::AsyncifySuspending => "_asyncifySuspending",
+            Intrinsic::AsyncifyPromising => "_asyncifyPromising",
 
             // Global current task tracking machinery
             Self::GlobalCurrentTaskMeta => "CURRENT_TASK_META",
@@ -1808,3 +2684,117 @@ impl Intrinsic {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_runtime_defines_exports() {
+        let opts = TranspileOpts::builder().name("component".into()).build();
+        let runtime = render_shared_runtime(&opts);
+        let (body, exports) = runtime
+            .rsplit_once("export {")
+            .expect("missing runtime exports");
+
+        for name in exports.trim_end().trim_end_matches("};").split(',') {
+            let name = name.trim();
+            let defined = ["function", "class", "const", "let", "var"]
+                .iter()
+                .any(|decl| {
+                    body.match_indices(&format!("{decl} {name}"))
+                        .any(|(idx, m)| {
+                            !body[idx + m.len()..]
+                                .starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+                        })
+                });
+            assert!(
+                defined,
+                "intrinsic [{name}] is exported but not defined by the runtime"
+            );
+        }
+        assert!(!exports.contains(Intrinsic::InstantiateCore.name()));
+    }
+
+    #[test]
+    fn shared_runtime_commonjs() {
+        let esm = TranspileOpts::builder().name("component".into()).build();
+        let cjs = TranspileOpts::builder()
+            .name("component".into())
+            .module_format(ModuleFormat::CommonJs)
+            .build();
+        assert_ne!(
+            shared_runtime_file_name(&esm),
+            shared_runtime_file_name(&cjs)
+        );
+
+        let runtime = render_shared_runtime(&cjs);
+        assert!(runtime.contains("\nmodule.exports = {"));
+        assert!(!runtime.contains("\nexport "));
+    }
+
+    #[test]
+    fn async_determinism_profiles() {
+        let seeded = TranspileOpts::builder()
+            .name("component".into())
+            .async_determinism(AsyncDeterminismProfile::Seeded { seed: 42 })
+            .build();
+        let runtime = render_shared_runtime(&seeded);
+        assert!(runtime.contains("const ASYNC_DETERMINISM = 'seeded';"));
+        assert!(runtime.contains("state: 42,"));
+        assert!(runtime.contains("const _coinFlip = () => { return _asyncRandom.next() > 0.5; };"));
+
+        let random = TranspileOpts::builder().name("component".into()).build();
+        assert!(render_shared_runtime(&random).contains("state: null,"));
+        assert_ne!(
+            shared_runtime_file_name(&seeded),
+            shared_runtime_file_name(&random)
+        );
+    }
+
+    #[test]
+    fn async_trace_records_events() {
+        let traced = TranspileOpts::builder()
+            .name("component".into())
+            .async_trace(true)
+            .build();
+        let runtime = render_shared_runtime(&traced);
+        assert!(runtime.contains("const _asyncTrace = {"));
+        assert!(runtime.contains("_asyncTrace.taskCreated(this);"));
+        assert!(runtime.contains("_asyncTrace.backpressureChanged(componentIdx, newValue);"));
+        assert!(runtime.contains("await _asyncTrace.streamOp('stream.read', componentIdx,"));
+
+        let untraced = TranspileOpts::builder().name("component".into()).build();
+        assert!(!render_shared_runtime(&untraced).contains("_asyncTrace."));
+        assert_ne!(
+            shared_runtime_file_name(&traced),
+            shared_runtime_file_name(&untraced)
+        );
+    }
+
+    #[test]
+    fn async_deadlock_detection() {
+        let default = TranspileOpts::builder().name("component".into()).build();
+        let runtime = render_shared_runtime(&default);
+        assert!(runtime.contains("const _asyncDeadlockDetector = {"));
+        assert!(runtime.contains("_asyncDeadlockDetector.check();"));
+        assert!(runtime.contains("if (Date.now() - this.since < 1000) { return; }"));
+        assert!(runtime.contains("function _dumpTasks() {"));
+
+        let disabled = TranspileOpts::builder()
+            .name("component".into())
+            .async_deadlock_timeout(0)
+            .build();
+        assert!(!render_shared_runtime(&disabled).contains("_asyncDeadlockDetector.check();"));
+        assert_ne!(
+            shared_runtime_file_name(&default),
+            shared_runtime_file_name(&disabled)
+        );
+    }
+
+    #[test]
+    fn index_expr_offsets_with_shared_runtime() {
+        assert_eq!(index_expr(3, false), "3");
+        assert_eq!(index_expr(3, true), format!("({INDEX_BASE} + 3)"));
+    }
+}
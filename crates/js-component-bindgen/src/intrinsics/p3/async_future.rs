@@ -221,6 +221,32 @@ pub enum AsyncFutureIntrinsic {
 }
 
 impl AsyncFutureIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::GlobalFutureMap,
+            Self::NestedFutureSymbol,
+            Self::FutureValueClass,
+            Self::GlobalFutureTableMap,
+            Self::FutureEndClass,
+            Self::HostFutureClass,
+            Self::InternalFutureClass,
+            Self::FutureWritableEndClass,
+            Self::FutureReadableEndClass,
+            Self::FutureNew,
+            Self::FutureNewFromLift,
+            Self::FutureRead,
+            Self::FutureWrite,
+            Self::FutureCancelRead,
+            Self::FutureCancelWrite,
+            Self::FutureDropReadable,
+            Self::FutureDropWritable,
+            Self::FutureTransfer,
+            Self::GenFutureHostInjectFn,
+            Self::IsFutureLowerableObject,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -472,6 +498,9 @@ impl AsyncFutureIntrinsic {
 
                         #dropped = false;
 
+                        #peer = null;
+                        #hostHeld = false;
+
                         constructor(args) {{
                             {debug_log_fn}('[{future_end_class}#constructor()] args', args);
 
@@ -480,10 +509,22 @@ impl AsyncFutureIntrinsic {
 
                             if (!args.waitable) {{ throw new Error("missing pending buffer"); }}
                             this.#waitable = args.waitable;
+                            this.#waitable.setOwner(this);
                         }}
 
+                        // the other end of the same stream/future (if it was created in a component)
+                        peer() {{ return this.#peer; }}
+                        setPeer(end) {{ this.#peer = end; }}
+
+                        // whether this end has been handed out to the host (e.g. lifted out of a component)
+                        isHostHeld() {{ return this.#hostHeld; }}
+                        setHostHeld(v) {{ this.#hostHeld = v; }}
+
                         getWaitable() {{ return this.#waitable; }}
-                        setWaitable(w) {{ this.#waitable = w; }}
+                        setWaitable(w) {{
+                            this.#waitable = w;
+                            this.#waitable.setOwner(this);
+                        }}
 
                         setCopyState(state) {{ this.#copyState = state; }}
                         getCopyState() {{ return this.#copyState; }}
@@ -1028,8 +1069,14 @@ impl AsyncFutureIntrinsic {
                             this.#hostInjectFn = f;
                         }}
 
+                        isHostDriven() {{
+                            const peer = this.peer();
+                            return !!this.#hostInjectFn || !peer || peer.isHostHeld();
+                        }}
+
                         promise() {{
                             if (this.#promise) {{ return this.#promise; }}
+                            this.setHostHeld(true);
                             // NOTE: we return a "thenable" here to ensure that simply lifting the future does
                             // not trigger a host read.
 
@@ -1117,6 +1164,9 @@ impl AsyncFutureIntrinsic {
                                 setDroppedFn,
                                 isDroppedFn,
                             }});
+
+                            this.#readEnd.setPeer(this.#writeEnd);
+                            this.#writeEnd.setPeer(this.#readEnd);
                         }}
 
                         elemMeta() {{ return this.#elemMeta; }}
@@ -232,6 +232,33 @@ pub enum AsyncStreamIntrinsic {
 }
 
 impl AsyncStreamIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::GlobalStreamMap,
+            Self::GlobalStreamTableMap,
+            Self::StreamEndClass,
+            Self::InternalStreamClass,
+            Self::StreamReadableEndClass,
+            Self::StreamWritableEndClass,
+            Self::HostStreamClass,
+            Self::ExternalStreamClass,
+            Self::PendingValueQueueClass,
+            Self::StreamNew,
+            Self::StreamNewFromLift,
+            Self::StreamRead,
+            Self::StreamWrite,
+            Self::StreamCancelRead,
+            Self::StreamCancelWrite,
+            Self::StreamDropReadable,
+            Self::StreamDropWritable,
+            Self::StreamTransfer,
+            Self::IsStreamLowerableObject,
+            Self::GenStreamHostInjectFn,
+            Self::GenReadFnFromLowerableStream,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -270,7 +297,7 @@ impl AsyncStreamIntrinsic {
     }
 
     /// Render an intrinsic to a string
-    pub fn render(&self, output: &mut Source, _render_args: &RenderIntrinsicsArgs<'_>) {
+    pub fn render(&self, output: &mut Source, render_args: &RenderIntrinsicsArgs<'_>) {
         match self {
             Self::StreamEndClass => {
                 let debug_log_fn = Intrinsic::DebugLog.name();
@@ -305,6 +332,9 @@ impl AsyncStreamIntrinsic {
                         #setDroppedFn;
                         #isDroppedFn;
 
+                        #peer = null;
+                        #hostHeld = false;
+
                         target;
 
                         constructor(args) {{
@@ -319,6 +349,7 @@ impl AsyncStreamIntrinsic {
 
                             this.#tableIdx = args.tableIdx;
                             this.#waitable = args.waitable;
+                            this.#waitable.setOwner(this);
 
                             if (args.setDroppedFn && args.isDroppedFn) {{
                                 this.#setDroppedFn = args.setDroppedFn;
@@ -340,8 +371,19 @@ impl AsyncStreamIntrinsic {
 
                         setTarget(tgt) {{ this.target = tgt; }}
 
+                        // the other end of the same stream/future (if it was created in a component)
+                        peer() {{ return this.#peer; }}
+                        setPeer(end) {{ this.#peer = end; }}
+
+                        // whether this end has been handed out to the host (e.g. lifted out of a component)
+                        isHostHeld() {{ return this.#hostHeld; }}
+                        setHostHeld(v) {{ this.#hostHeld = v; }}
+
                         getWaitable() {{ return this.#waitable; }}
-                        setWaitable(w) {{ this.#waitable = w; }}
+                        setWaitable(w) {{
+                            this.#waitable = w;
+                            this.#waitable.setOwner(this);
+                        }}
 
                         setCopyState(state) {{ this.#copyState = state; }}
                         getCopyState() {{ return this.#copyState; }}
@@ -1242,6 +1284,11 @@ impl AsyncStreamIntrinsic {
                         }}
                         setHostCancelFn(f) {{ this.#hostCancelFn = f; }}
 
+                        isHostDriven() {{
+                            const peer = this.peer();
+                            return !!this.#hostInjectFn || !peer || peer.isHostHeld();
+                        }}
+
                         getElemMeta() {{ return {{...this.#elemMeta}}; }}
 
                         {type_getter_impl}
@@ -1369,6 +1416,9 @@ impl AsyncStreamIntrinsic {
                                 setDroppedFn,
                                 isDroppedFn,
                             }});
+
+                            this.#readEnd.setPeer(this.#writeEnd);
+                            this.#writeEnd.setPeer(this.#readEnd);
                         }}
 
                         elemMeta() {{ return this.#elemMeta; }}
@@ -1454,6 +1504,7 @@ impl AsyncStreamIntrinsic {
                                throw new Error(`missing stream [${{this.#streamEndWaitableIdx}}] (table [${{this.#streamTableIdx}}], component [${{this.#componentIdx}}]`);
                            }}
                            if (streamEnd.isInSet()) {{ throw new Error('trap: streams in waitable sets cannot be lifted'); }}
+                           streamEnd.setHostHeld(true);
 
                             return new {external_stream_class}({{
                                 isReadable: streamEnd.isReadable(),
@@ -1803,6 +1854,22 @@ impl AsyncStreamIntrinsic {
                     ),
                     _ => unreachable!("unexpected stream operation"),
                 };
+                // Copies are recorded as spans, which stay open while the copy is blocked
+                let (trace_op_start, trace_op_end) = if render_args.transpile_opts.async_trace {
+                    let op_name = match self {
+                        Self::StreamWrite => "stream.write",
+                        _ => "stream.read",
+                    };
+                    (
+                        format!(
+                            "{}.streamOp('{op_name}', componentIdx, {{ streamTableIdx, streamEndWaitableIdx, count, isAsync }}, () => ",
+                            Intrinsic::AsyncTrace.name()
+                        ),
+                        ")",
+                    )
+                } else {
+                    (String::new(), "")
+                };
 
                 output.push_str(&format!(r#"
                     async function {stream_op_fn}(
@@ -1848,7 +1915,7 @@ impl AsyncStreamIntrinsic {
                             throw new Error(`stream end table idx [${{streamEnd.streamTableIdx()}}] != operation table idx [${{streamTableIdx}}]`);
                         }}
 
-                        const result = await streamEnd.copy({{
+                        const result = await {trace_op_start}streamEnd.copy({{
                             isAsync,
                             memory: getMemoryFn(),
                             ptr,
@@ -1858,7 +1925,7 @@ impl AsyncStreamIntrinsic {
                             stringEncoding,
                             realloc: getReallocFn?.(),
                             getReallocFn,
-                        }});
+                        }}){trace_op_end};
 
                         return result;
                     }}
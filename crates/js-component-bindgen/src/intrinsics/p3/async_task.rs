@@ -232,6 +232,36 @@ pub enum AsyncTaskIntrinsic {
     /// ```
     DriverLoop,
 
+    /// Produce a snapshot of all live async tasks, which can be used to inspect
+    /// what tasks are blocked on (and is exposed as `_util.dumpTasks()`)
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// The function that implements this intrinsic has the following definition:
+    ///
+    /// ```ts
+    /// interface WaitableSnapshot {
+    ///     idx: number,
+    ///     target: string,
+    ///     hasPendingEvent: boolean,
+    ///     subtask?: { id: number, fnName: string, childTaskID: number | null },
+    ///     end?: { kind: 'stream' | 'future', target: string, otherEnd: string | null, hostDriven: boolean },
+    /// }
+    ///
+    /// interface TaskSnapshot {
+    ///     taskID: number,
+    ///     componentIdx: number,
+    ///     entryFnName: string,
+    ///     state: string,
+    ///     isAsync: boolean,
+    ///     suspended: boolean,
+    ///     waitingOn: { waitableSet: string, waitables: WaitableSnapshot[] } | null,
+    /// }
+    ///
+    /// function _dumpTasks(): TaskSnapshot[];
+    /// ```
+    DumpTasks,
+
     /// Intrinsic used when components lower imports to be used
     /// from other components or the host.
     ///
@@ -261,9 +291,134 @@ pub enum AsyncTaskIntrinsic {
 
     /// Component index that is saved across sync-to-sync guest calls
     SymmetricSyncGuestCallStack,
+
+    /// Class that represents a single cooperative thread of a task
+    ///
+    /// Threads are stored in the component instance's handle table (alongside
+    /// waitables), and the implicit thread of a task is created when first needed.
+    ///
+    /// Threads other than the implicit thread are run by `WebAssembly.promising`,
+    /// so switching between them relies on JSPI.
+    ThreadClass,
+
+    /// Retrieve the index of the current thread
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// The function that implements this intrinsic has the following definition:
+    ///
+    /// ```ts
+    /// type i32 = number;
+    /// function threadIndex(ctx: { componentIdx: number }): i32;
+    /// ```
+    ThreadIndex,
+
+    /// Create a new (suspended) thread that will run a function from a core table
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// The function that implements this intrinsic has the following definition:
+    ///
+    /// ```ts
+    /// type i32 = number;
+    /// interface ThreadNewIndirectCtx {
+    ///     componentIdx: number,
+    ///     getTableFn: () => WebAssembly.Table,
+    /// }
+    /// function threadNewIndirect(ctx: ThreadNewIndirectCtx, fnIdx: i32, arg: i32): i32;
+    /// ```
+    ThreadNewIndirect,
+
+    /// Suspend the current thread until it is resumed by another thread
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// The function that implements this intrinsic has the following definition:
+    ///
+    /// ```ts
+    /// type i32 = number;
+    /// interface ThreadSuspendCtx {
+    ///     componentIdx: number,
+    ///     isCancellable: boolean,
+    /// }
+    /// function threadSuspend(ctx: ThreadSuspendCtx): Promise<i32>;
+    /// ```
+    ThreadSuspend,
+
+    /// Switch to another thread, either suspending the current thread or leaving it ready
+    ///
+    /// This covers `thread.suspend-to`, `thread.suspend-to-suspended` and
+    /// `thread.yield-to-suspended`.
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// The function that implements this intrinsic has the following definition:
+    ///
+    /// ```ts
+    /// type i32 = number;
+    /// interface ThreadSwitchToCtx {
+    ///     componentIdx: number,
+    ///     isCancellable: boolean,
+    ///     // whether the current thread stays ready (as opposed to being suspended)
+    ///     isYield: boolean,
+    ///     // whether the target thread may already be ready (rather than suspended)
+    ///     allowReady: boolean,
+    /// }
+    /// function threadSwitchTo(ctx: ThreadSwitchToCtx, threadIdx: i32): Promise<i32>;
+    /// ```
+    ThreadSwitchTo,
+
+    /// Mark a suspended thread as ready to run
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// The function that implements this intrinsic has the following definition:
+    ///
+    /// ```ts
+    /// type i32 = number;
+    /// function threadUnsuspend(ctx: { componentIdx: number }, threadIdx: i32);
+    /// ```
+    ThreadUnsuspend,
 }
 
 impl AsyncTaskIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::ContextSet,
+            Self::ContextGet,
+            Self::TaskReturn,
+            Self::SubtaskDrop,
+            Self::Yield,
+            Self::SubtaskCancel,
+            Self::TaskCancel,
+            Self::GetCurrentTask,
+            Self::CreateNewCurrentTask,
+            Self::ClearCurrentTask,
+            Self::GlobalAsyncCurrentTaskMap,
+            Self::GlobalAsyncCurrentTaskIds,
+            Self::GlobalAsyncCurrentComponentIdxs,
+            Self::AsyncTaskClass,
+            Self::AsyncBlockedConstant,
+            Self::AsyncSubtaskClass,
+            Self::UnpackCallbackResult,
+            Self::DriverLoop,
+            Self::DumpTasks,
+            Self::LowerImport,
+            Self::LowerImportBackwardsCompat,
+            Self::CurrentTaskMayBlock,
+            Self::EnterSymmetricSyncGuestCall,
+            Self::ExitSymmetricSyncGuestCall,
+            Self::SymmetricSyncGuestCallStack,
+            Self::ThreadClass,
+            Self::ThreadIndex,
+            Self::ThreadNewIndirect,
+            Self::ThreadSuspend,
+            Self::ThreadSwitchTo,
+            Self::ThreadUnsuspend,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -291,11 +446,18 @@ impl AsyncTaskIntrinsic {
             Self::Yield.name(),
             Self::UnpackCallbackResult.name(),
             Self::DriverLoop.name(),
+            Self::DumpTasks.name(),
             Self::LowerImport.name(),
             Self::LowerImportBackwardsCompat.name(),
             Self::EnterSymmetricSyncGuestCall.name(),
             Self::ExitSymmetricSyncGuestCall.name(),
             Self::SymmetricSyncGuestCallStack.name(),
+            Self::ThreadClass.name(),
+            Self::ThreadIndex.name(),
+            Self::ThreadNewIndirect.name(),
+            Self::ThreadSuspend.name(),
+            Self::ThreadSwitchTo.name(),
+            Self::ThreadUnsuspend.name(),
         ]
     }
 
@@ -321,16 +483,23 @@ impl AsyncTaskIntrinsic {
             Self::Yield => "asyncYield",
             Self::UnpackCallbackResult => "unpackCallbackResult",
             Self::DriverLoop => "_driverLoop",
+            Self::DumpTasks => "_dumpTasks",
             Self::LowerImport => "_lowerImport",
             Self::LowerImportBackwardsCompat => "_lowerImportBackwardsCompat",
             Self::EnterSymmetricSyncGuestCall => "_symmetricSyncGuestCallEnter",
             Self::ExitSymmetricSyncGuestCall => "_symmetricSyncGuestCallExit",
             Self::SymmetricSyncGuestCallStack => "SYMMETRIC_SYNC_GUEST_CALL_STACK",
+            Self::ThreadClass => "ComponentThread",
+            Self::ThreadIndex => "threadIndex",
+            Self::ThreadNewIndirect => "threadNewIndirect",
+            Self::ThreadSuspend => "threadSuspend",
+            Self::ThreadSwitchTo => "threadSwitchTo",
+            Self::ThreadUnsuspend => "threadUnsuspend",
         }
     }
 
     /// Render an intrinsic to a string
-    pub fn render(&self, output: &mut Source, _render_args: &RenderIntrinsicsArgs<'_>) {
+    pub fn render(&self, output: &mut Source, render_args: &RenderIntrinsicsArgs<'_>) {
         match self {
             Self::CurrentTaskMayBlock => {
                 let var_name = self.name();
@@ -518,7 +687,7 @@ impl AsyncTaskIntrinsic {
 
                         let liftCtx = {{ memory, useDirectParams, params, componentIdx, stringEncoding }};
                         if (!useDirectParams) {{
-                            if (!ctx.memory) {{
+                            if (!memory) {{
                                 {debug_log_fn}('missing memory despite indirect param usage', {{ useDirectParams, liftCtx, ctx }});
                                 throw new Error('missing memory despite indirect param usage');
                             }}
@@ -823,6 +992,10 @@ impl AsyncTaskIntrinsic {
                 let promise_with_resolvers_fn = Intrinsic::PromiseWithResolversPonyfill.name();
                 let future_value_class =
                     Intrinsic::AsyncFuture(AsyncFutureIntrinsic::FutureValueClass).name();
+                let trace_created = render_args.async_trace("taskCreated(this)");
+                let trace_returned = render_args.async_trace("taskReturned(this)");
+                let trace_cancelled = render_args.async_trace("taskCancelled(this)");
+                let trace_exited = render_args.async_trace("taskExited(this)");
 
                 output.push_str(&format!(r#"
                     class {task_class} {{
@@ -883,6 +1056,9 @@ impl AsyncTaskIntrinsic {
                         #exited = false;
                         #errored = null;
 
+                        // waitable set the task is currently blocked on (if any)
+                        #waitingOn = null;
+
                         cancelled = false;
                         cancelRequested = false;
                         alwaysTaskReturn = false;
@@ -955,6 +1131,8 @@ impl AsyncTaskIntrinsic {
 
 
                            if (opts.errHandling) {{ this.#errHandling = opts.errHandling; }}
+
+                           {trace_created}
                         }}
 
                         taskState() {{ return this.#state; }}
@@ -1236,7 +1414,7 @@ impl AsyncTaskIntrinsic {
                             }});
 
                             const ready = readyFn();
-                            if (ready && {global_async_determinism} === 'random') {{
+                            if (ready && {global_async_determinism} !== 'deterministic') {{
                                 const coinFlip = {coin_flip_fn}();
                                 if (coinFlip) {{ return true }}
                             }}
@@ -1283,6 +1461,7 @@ impl AsyncTaskIntrinsic {
                             }}
                             if (this.borrowedHandles.length > 0) {{ throw new Error('task still has borrow handles'); }}
                             this.cancelled = true;
+                            {trace_cancelled}
                             this.onResolve(args?.error ?? new Error('task cancelled'));
                             this.#state = {task_class}.State.RESOLVED;
                         }}
@@ -1339,6 +1518,10 @@ impl AsyncTaskIntrinsic {
                         }}
 
                         isRejected() {{ return this.#rejected; }}
+                        isExited() {{ return this.#exited; }}
+
+                        waitingOn() {{ return this.#waitingOn; }}
+                        setWaitingOn(wset) {{ this.#waitingOn = wset; }}
 
                         isErrored() {{ return this.#errored; }}
                         setErrored(err) {{ this.#errored = err; }}
@@ -1383,6 +1566,7 @@ impl AsyncTaskIntrinsic {
                             }}
 
                             this.#state = {task_class}.State.RESOLVED;
+                            {trace_returned}
 
                             switch (results.length) {{
                                 case 0:
@@ -1442,6 +1626,7 @@ impl AsyncTaskIntrinsic {
                             }}
 
                             this.#exited = true;
+                            {trace_exited}
                             {clear_current_task_fn}(this.#componentIdx, this.id());
                         }}
 
@@ -1512,6 +1697,10 @@ impl AsyncTaskIntrinsic {
                 let get_or_create_async_state_fn =
                     Intrinsic::Component(ComponentIntrinsic::GetOrCreateAsyncState).name();
                 let lookup_memories_for_component = Intrinsic::LookupMemoriesForComponent.name();
+                let trace_created = render_args.async_trace("subtaskCreated(this)");
+                let trace_started = render_args.async_trace("subtaskStarted(this)");
+                let trace_resolved = render_args
+                    .async_trace("subtaskResolved(this, this.isReturned() ? 'return' : 'cancel')");
 
                 output.push_str(&format!(r#"
                     class {subtask_class} {{
@@ -1580,6 +1769,7 @@ impl AsyncTaskIntrinsic {
 
                             if (!args.waitable) {{ throw new Error("missing/invalid waitable"); }}
                             this.#waitable = args.waitable;
+                            this.#waitable.setOwner(this);
 
                             if (args.callMetadata) {{ this.#callMetadata = args.callMetadata; }}
 
@@ -1587,6 +1777,8 @@ impl AsyncTaskIntrinsic {
                             this.target = args.target;
                             this.isAsync = args.isAsync;
                             this.isManualAsync = args.isManualAsync;
+
+                            {trace_created}
                         }}
 
                         id() {{ return this.#id; }}
@@ -1669,6 +1861,7 @@ impl AsyncTaskIntrinsic {
                             if (this.#onProgressFn) {{ this.#onProgressFn(); }}
 
                             this.#state = {subtask_class}.State.STARTED;
+                            {trace_started}
 
                             let result;
 
@@ -1726,6 +1919,7 @@ impl AsyncTaskIntrinsic {
                                 }}
                                 this.#state = {subtask_class}.State.RETURNED;
                             }}
+                            {trace_resolved}
 
                             this.setResult(subtaskValue);
 
@@ -2091,6 +2285,47 @@ impl AsyncTaskIntrinsic {
                 ));
             }
 
+            Self::DumpTasks => {
+                let dump_tasks_fn = self.name();
+                let async_state_map =
+                    Intrinsic::Component(ComponentIntrinsic::GlobalAsyncStateMap).name();
+                let global_task_map = Self::GlobalAsyncCurrentTaskMap.name();
+
+                output.push_str(&format!(r#"
+                    function {dump_tasks_fn}() {{
+                        const suspended = new Set();
+                        for (const cstate of {async_state_map}.values()) {{
+                            for (const {{ task }} of cstate.suspendedTasks()) {{ suspended.add(task); }}
+                        }}
+
+                        const tasks = new Set(suspended);
+                        for (const taskMetas of {global_task_map}.values()) {{
+                            for (const {{ task }} of taskMetas) {{
+                                if (task && !task.isExited() && !task.isRejected()) {{ tasks.add(task); }}
+                            }}
+                        }}
+
+                        return [...tasks]
+                            .sort((a, b) => (a.id() < b.id() ? -1 : 1))
+                            .map(task => {{
+                                const wset = task.waitingOn();
+                                return {{
+                                    taskID: Number(task.id()),
+                                    componentIdx: task.componentIdx(),
+                                    entryFnName: task.entryFnName(),
+                                    state: task.taskState(),
+                                    isAsync: task.isAsync(),
+                                    suspended: suspended.has(task),
+                                    waitingOn: wset ? {{
+                                        waitableSet: wset.target,
+                                        waitables: wset.waitables().map(w => w.describe()),
+                                    }} : null,
+                                }};
+                            }});
+                    }}
+                "#));
+            }
+
             // NOTE: the function that is output by this intrinsic also receives the
             // function that *should* be called (i.e. a trampoline to CallWasm, etc)
             //
@@ -2693,6 +2928,291 @@ impl AsyncTaskIntrinsic {
                 let var_name = self.name();
                 output.push_str(&format!("let {var_name} = [];\n"));
             }
+
+            Self::ThreadClass => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let thread_class = self.name();
+                let get_or_create_async_state_fn =
+                    Intrinsic::Component(ComponentIntrinsic::GetOrCreateAsyncState).name();
+                let current_task_get_fn = Self::GetCurrentTask.name();
+                let get_global_current_task_meta_fn = Intrinsic::GetGlobalCurrentTaskMetaFn.name();
+                let set_global_current_task_meta_fn = Intrinsic::SetGlobalCurrentTaskMetaFn.name();
+                let promise_with_resolvers_fn = Intrinsic::PromiseWithResolversPonyfill.name();
+
+                output.push_str(&format!(
+                    r#"
+                    class {thread_class} {{
+                        static State = {{
+                            SUSPENDED: 'suspended',
+                            READY: 'ready',
+                            RUNNING: 'running',
+                            EXITED: 'exited',
+                        }};
+
+                        static #implicitByTask = new WeakMap();
+                        static #runningByTask = new WeakMap();
+
+                        #componentIdx;
+                        #task;
+                        #state;
+                        #idx;
+                        #startFn = null;
+                        #startArg = null;
+                        #cancellable = false;
+                        #resumeFn = null;
+
+                        constructor(args) {{
+                            if (args?.componentIdx === undefined) {{ throw new TypeError('missing component idx during thread creation'); }}
+                            if (!args.task) {{ throw new TypeError('missing task during thread creation'); }}
+                            this.#componentIdx = args.componentIdx;
+                            this.#task = args.task;
+                            this.#state = args.state ?? {thread_class}.State.SUSPENDED;
+                            if (args.startFn) {{
+                                this.#startFn = args.startFn;
+                                this.#startArg = args.startArg;
+                            }}
+                            this.#idx = {get_or_create_async_state_fn}(this.#componentIdx).handles.insert(this);
+                        }}
+
+                        // Retrieve the thread that is currently running for the current task of a component,
+                        // creating the implicit thread of the task if necessary
+                        static current(componentIdx) {{
+                            const currentTaskMeta = {get_global_current_task_meta_fn}(componentIdx);
+                            if (!currentTaskMeta) {{
+                                throw new Error(`missing current task for component idx [${{componentIdx}}]`);
+                            }}
+                            const task = {current_task_get_fn}(componentIdx, currentTaskMeta.taskID)?.task;
+                            if (!task) {{ throw new Error('invalid/missing async task'); }}
+
+                            const running = {thread_class}.#runningByTask.get(task);
+                            if (running) {{ return running; }}
+
+                            let implicit = {thread_class}.#implicitByTask.get(task);
+                            if (!implicit) {{
+                                implicit = new {thread_class}({{
+                                    componentIdx,
+                                    task,
+                                    state: {thread_class}.State.RUNNING,
+                                }});
+                                {thread_class}.#implicitByTask.set(task, implicit);
+                                task.registerOnResolveHandler(() => implicit.#exit());
+                            }}
+                            return implicit;
+                        }}
+
+                        static get(componentIdx, idx) {{
+                            const thread = {get_or_create_async_state_fn}(componentIdx).handles.get(idx);
+                            if (!(thread instanceof {thread_class})) {{
+                                throw new Error(`invalid thread index [${{idx}}] for component idx [${{componentIdx}}]`);
+                            }}
+                            return thread;
+                        }}
+
+                        idx() {{ return this.#idx; }}
+                        task() {{ return this.#task; }}
+                        state() {{ return this.#state; }}
+
+                        isSuspended() {{ return this.#state === {thread_class}.State.SUSPENDED; }}
+                        isReady() {{ return this.#state === {thread_class}.State.READY; }}
+
+                        // Suspend this (running) thread, producing a promise that resolves
+                        // when the thread is resumed, with 1 if it was cancelled and 0 otherwise
+                        suspend(args) {{
+                            const {{ cancellable }} = args;
+                            {debug_log_fn}('[{thread_class}#suspend()] args', {{
+                                idx: this.#idx,
+                                cancellable,
+                                taskID: this.#task.id(),
+                                componentIdx: this.#componentIdx,
+                            }});
+                            if (this.#state !== {thread_class}.State.RUNNING) {{
+                                throw new Error(`thread [${{this.#idx}}] is not running`);
+                            }}
+                            if (this.#task.deliverPendingCancel({{ cancellable }})) {{ return Promise.resolve(1); }}
+
+                            const {{ promise, resolve }} = {promise_with_resolvers_fn}();
+                            this.#state = {thread_class}.State.SUSPENDED;
+                            this.#cancellable = cancellable;
+                            this.#resumeFn = resolve;
+                            if ({thread_class}.#runningByTask.get(this.#task) === this) {{
+                                {thread_class}.#runningByTask.delete(this.#task);
+                            }}
+                            return promise;
+                        }}
+
+                        // Suspend this (running) thread, resuming it once other ready threads have had a chance to run
+                        yield(args) {{
+                            const promise = this.suspend(args);
+                            if (this.isSuspended()) {{
+                                setTimeout(() => {{ if (this.isSuspended()) {{ this.resume(); }} }}, 0);
+                            }}
+                            return promise;
+                        }}
+
+                        // Mark this (suspended) thread as ready, so that it runs as soon as
+                        // the thread that is currently running gives up control
+                        resume() {{
+                            {debug_log_fn}('[{thread_class}#resume()]', {{
+                                idx: this.#idx,
+                                state: this.#state,
+                                taskID: this.#task.id(),
+                                componentIdx: this.#componentIdx,
+                            }});
+                            if (!this.isSuspended()) {{ throw new Error(`thread [${{this.#idx}}] is not suspended`); }}
+                            this.#state = {thread_class}.State.READY;
+                            queueMicrotask(() => this.#run());
+                        }}
+
+                        #run() {{
+                            if (this.#state !== {thread_class}.State.READY) {{ return; }}
+                            this.#state = {thread_class}.State.RUNNING;
+                            {thread_class}.#runningByTask.set(this.#task, this);
+                            {set_global_current_task_meta_fn}({{
+                                taskID: this.#task.id(),
+                                componentIdx: this.#componentIdx,
+                            }});
+
+                            if (this.#startFn !== null) {{
+                                const startFn = this.#startFn;
+                                this.#startFn = null;
+                                WebAssembly.promising(startFn)(this.#startArg).then(
+                                    () => this.#exit(),
+                                    (err) => this.#exit(err),
+                                );
+                                return;
+                            }}
+
+                            const resumeFn = this.#resumeFn;
+                            this.#resumeFn = null;
+                            const cancelled = this.#cancellable && this.#task.deliverPendingCancel({{ cancellable: true }});
+                            resumeFn(cancelled ? 1 : 0);
+                        }}
+
+                        #exit(err) {{
+                            {debug_log_fn}('[{thread_class}#exit()]', {{
+                                idx: this.#idx,
+                                taskID: this.#task.id(),
+                                componentIdx: this.#componentIdx,
+                                err,
+                            }});
+                            if (this.#state === {thread_class}.State.EXITED) {{ return; }}
+                            this.#state = {thread_class}.State.EXITED;
+                            if ({thread_class}.#runningByTask.get(this.#task) === this) {{
+                                {thread_class}.#runningByTask.delete(this.#task);
+                            }}
+                            {get_or_create_async_state_fn}(this.#componentIdx).handles.remove(this.#idx);
+
+                            if (err !== undefined) {{
+                                this.#task.setErrored(err);
+                                this.#task.reject(err);
+                            }}
+                        }}
+                    }}
+                "#
+                ));
+            }
+
+            Self::ThreadIndex => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let thread_index_fn = self.name();
+                let thread_class = Self::ThreadClass.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      function {thread_index_fn}(ctx) {{
+                          {debug_log_fn}('[{thread_index_fn}()] args', {{ ctx }});
+                          return {thread_class}.current(ctx.componentIdx).idx();
+                      }}
+                    "#
+                );
+            }
+
+            Self::ThreadNewIndirect => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let thread_new_indirect_fn = self.name();
+                let thread_class = Self::ThreadClass.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      function {thread_new_indirect_fn}(ctx, fnIdx, arg) {{
+                          {debug_log_fn}('[{thread_new_indirect_fn}()] args', {{ ctx, fnIdx, arg }});
+                          const {{ componentIdx, getTableFn }} = ctx;
+                          const table = getTableFn();
+                          if (!table) {{ throw new Error('missing table for thread start function'); }}
+
+                          const startFn = table.get(fnIdx);
+                          if (!startFn) {{ throw new Error(`invalid thread start function index [${{fnIdx}}]`); }}
+
+                          const task = {thread_class}.current(componentIdx).task();
+                          const thread = new {thread_class}({{ componentIdx, task, startFn, startArg: arg }});
+                          return thread.idx();
+                      }}
+                    "#
+                );
+            }
+
+            Self::ThreadSuspend => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let thread_suspend_fn = self.name();
+                let thread_class = Self::ThreadClass.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      async function {thread_suspend_fn}(ctx) {{
+                          {debug_log_fn}('[{thread_suspend_fn}()] args', {{ ctx }});
+                          const {{ componentIdx, isCancellable }} = ctx;
+                          return await {thread_class}.current(componentIdx).suspend({{ cancellable: isCancellable }});
+                      }}
+                    "#
+                );
+            }
+
+            Self::ThreadSwitchTo => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let thread_switch_to_fn = self.name();
+                let thread_class = Self::ThreadClass.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      async function {thread_switch_to_fn}(ctx, threadIdx) {{
+                          {debug_log_fn}('[{thread_switch_to_fn}()] args', {{ ctx, threadIdx }});
+                          const {{ componentIdx, isCancellable, isYield, allowReady }} = ctx;
+
+                          const current = {thread_class}.current(componentIdx);
+                          const target = {thread_class}.get(componentIdx, threadIdx);
+                          if (target === current) {{ throw new Error(`cannot switch to the current thread [${{threadIdx}}]`); }}
+
+                          if (current.task().deliverPendingCancel({{ cancellable: isCancellable }})) {{ return 1; }}
+
+                          if (target.isSuspended()) {{
+                              target.resume();
+                          }} else if (!(allowReady && target.isReady())) {{
+                              throw new Error(`thread [${{threadIdx}}] is not suspended`);
+                          }}
+
+                          if (isYield) {{
+                              return await current.yield({{ cancellable: isCancellable }});
+                          }}
+                          return await current.suspend({{ cancellable: isCancellable }});
+                      }}
+                    "#
+                );
+            }
+
+            Self::ThreadUnsuspend => {
+                let debug_log_fn = Intrinsic::DebugLog.name();
+                let thread_unsuspend_fn = self.name();
+                let thread_class = Self::ThreadClass.name();
+                uwriteln!(
+                    output,
+                    r#"
+                      function {thread_unsuspend_fn}(ctx, threadIdx) {{
+                          {debug_log_fn}('[{thread_unsuspend_fn}()] args', {{ ctx, threadIdx }});
+                          {thread_class}.get(ctx.componentIdx, threadIdx).resume();
+                      }}
+                    "#
+                );
+            }
         }
     }
 }
@@ -170,9 +170,82 @@ pub enum ErrCtxIntrinsic {
 
     /// Reserve a new error context at the global scope, given a debug message to use
     ReserveGlobalRep,
+
+    /// Class that represents an `error-context` in the hands of the host
+    ///
+    /// Error contexts are surfaced to JS as instances of this class, which extends `Error`,
+    /// so that they can be thrown, carry a stack trace and (when created from a host `Error`)
+    /// a `cause`.
+    ///
+    /// ```ts
+    /// class ErrorContext extends Error {
+    ///     debugMessage(): string;
+    /// }
+    /// ```
+    HostClass,
+
+    /// Bookkeeping for error contexts that are held by the host
+    ///
+    /// Every host value that stands for an error context holds a single reference to the
+    /// component-global error-context, which is released when the value is garbage collected.
+    ///
+    /// You can consider the type of the value referenced by this intrinsic to be:
+    ///
+    /// ```ts
+    /// type ErrorContextRep = number;
+    /// type ErrorContextHostRefs = {
+    ///     reps: WeakMap<ErrorContext, ErrorContextRep>,
+    ///     registry: FinalizationRegistry<ErrorContextRep>,
+    /// };
+    /// ```
+    HostRefs,
+
+    /// Retrieve the host value for a component-global error-context
+    ///
+    /// The same `ErrorContext` is returned for a given error-context for as long as the host holds it.
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// ```ts
+    /// function errCtxHostValue(globalRep: number): ErrorContext;
+    /// ```
+    HostValue,
+
+    /// Retrieve the component-global error-context for a host value
+    ///
+    /// Host created `Error`s reserve a new error-context, which is surfaced to the host as an
+    /// `ErrorContext` with the original error as its `cause`.
+    ///
+    /// # Intrinsic implementation function
+    ///
+    /// ```ts
+    /// function errCtxHostRep(val: ErrorContext | Error): number;
+    /// ```
+    HostRep,
 }
 
 impl ErrCtxIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::ComponentGlobalTable,
+            Self::GlobalErrCtxTableMap,
+            Self::ComponentLocalTable,
+            Self::ErrorContextNew,
+            Self::ErrorContextDrop,
+            Self::ErrorContextTransfer,
+            Self::ErrorContextDebugMessage,
+            Self::GetLocalTable,
+            Self::GlobalRefCountAdd,
+            Self::CreateLocalHandle,
+            Self::ReserveGlobalRep,
+            Self::HostClass,
+            Self::HostRefs,
+            Self::HostValue,
+            Self::HostRep,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -191,6 +264,10 @@ impl ErrCtxIntrinsic {
             "errCtxGetLocalTable",
             "errCtxCreateLocalHandle",
             "errCtxReserveGlobalRep",
+            "ErrorContext",
+            "errCtxHostRefs",
+            "errCtxHostValue",
+            "errCtxHostRep",
         ]
     }
 
@@ -208,6 +285,10 @@ impl ErrCtxIntrinsic {
             Self::GetLocalTable => "errCtxGetLocalTable",
             Self::CreateLocalHandle => "errCtxCreateLocalHandle",
             Self::ReserveGlobalRep => "errCtxReserveGlobalRep",
+            Self::HostClass => "ErrorContext",
+            Self::HostRefs => "errCtxHostRefs",
+            Self::HostValue => "errCtxHostValue",
+            Self::HostRep => "errCtxHostRep",
         }
     }
 
@@ -502,6 +583,101 @@ impl ErrCtxIntrinsic {
                 "#
                 ));
             }
+
+            Self::HostClass => {
+                let class_name = Self::HostClass.name();
+                output.push_str(&format!(
+                    r#"
+                    class {class_name} extends Error {{
+                        constructor(debugMessage, options) {{
+                            super(debugMessage, options);
+                            this.name = '{class_name}';
+                        }}
+
+                        debugMessage() {{
+                            return this.message;
+                        }}
+                    }}
+                "#
+                ));
+            }
+
+            // The host reference is dropped once the host value is no longer reachable, at which
+            // point the error-context may be removed if no component holds it either
+            Self::HostRefs => {
+                let host_refs = Self::HostRefs.name();
+                let global_tbl = Self::ComponentGlobalTable.name();
+                output.push_str(&format!(
+                    r#"
+                    const {host_refs} = {{
+                        reps: new WeakMap(),
+                        registry: new FinalizationRegistry((globalRep) => {{
+                            const globalTable = {global_tbl}.get();
+                            const errCtx = globalTable.get(globalRep);
+                            if (!errCtx) {{ return; }}
+                            errCtx.refCount -= 1;
+                            if (errCtx.refCount <= 0) {{
+                                globalTable.remove(globalRep);
+                            }}
+                        }}),
+                    }};
+                "#
+                ));
+            }
+
+            Self::HostValue => {
+                let host_value_fn = Self::HostValue.name();
+                let host_refs = Self::HostRefs.name();
+                let host_class = Self::HostClass.name();
+                let global_tbl = Self::ComponentGlobalTable.name();
+                output.push_str(&format!(
+                    r#"
+                    function {host_value_fn}(globalRep) {{
+                        const globalTable = {global_tbl}.get();
+                        const errCtx = globalTable.get(globalRep);
+                        if (!errCtx) {{
+                            throw new Error(`missing global error-context [${{globalRep}}] during lift`);
+                        }}
+
+                        let hostValue = errCtx.hostValue?.deref();
+                        if (hostValue === undefined) {{
+                            hostValue = new {host_class}(
+                                errCtx.debugMessage,
+                                errCtx.cause === undefined ? undefined : {{ cause: errCtx.cause }},
+                            );
+                            errCtx.hostValue = new WeakRef(hostValue);
+                            errCtx.refCount += 1;
+                            {host_refs}.reps.set(hostValue, globalRep);
+                            {host_refs}.registry.register(hostValue, globalRep);
+                        }}
+                        return hostValue;
+                    }}
+                "#
+                ));
+            }
+
+            Self::HostRep => {
+                let host_rep_fn = Self::HostRep.name();
+                let host_refs = Self::HostRefs.name();
+                let reserve_global_rep_fn = Self::ReserveGlobalRep.name();
+                output.push_str(&format!(
+                    r#"
+                    function {host_rep_fn}(val) {{
+                        const existingRep = {host_refs}.reps.get(val);
+                        if (existingRep !== undefined) {{ return existingRep; }}
+
+                        if (!(val instanceof Error)) {{
+                            throw new TypeError('error-context values must be ErrorContext or Error objects');
+                        }}
+
+                        // The new error-context is only referenced once it has been lowered into a component
+                        const {{ globalRep, errCtx }} = {reserve_global_rep_fn}(String(val.message), 0);
+                        errCtx.cause = val;
+                        return globalRep;
+                    }}
+                "#
+                ));
+            }
         }
     }
 }
@@ -81,6 +81,16 @@ pub enum HostIntrinsic {
 }
 
 impl HostIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::PrepareCall,
+            Self::AsyncStartCall,
+            Self::SyncStartCall,
+            Self::StoreEventInComponentMemory,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -121,6 +121,20 @@ pub enum WaitableIntrinsic {
 }
 
 impl WaitableIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::WaitableSetClass,
+            Self::WaitableClass,
+            Self::WaitableSetNew,
+            Self::WaitableSetWait,
+            Self::WaitableSetPoll,
+            Self::WaitableSetDrop,
+            Self::RemoveWaitableSet,
+            Self::WaitableJoin,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -146,12 +160,14 @@ impl WaitableIntrinsic {
     }
 
     /// Render an intrinsic to a string
-    pub fn render(&self, output: &mut Source, _render_args: &RenderIntrinsicsArgs<'_>) {
+    pub fn render(&self, output: &mut Source, render_args: &RenderIntrinsicsArgs<'_>) {
         match self {
             Self::WaitableSetClass => {
                 let debug_log_fn = Intrinsic::DebugLog.name();
                 let waitable_set_class = Self::WaitableSetClass.name();
                 let async_event_code_enum = Intrinsic::AsyncEventCodeEnum.name();
+                let global_async_determinism = Intrinsic::GlobalAsyncDeterminism.name();
+                let async_random = Intrinsic::AsyncRandom.name();
 
                 output.push_str(&format!(
                     r#"
@@ -177,13 +193,14 @@ impl WaitableIntrinsic {
                         incrementNumWaiting(n) {{ this.#waiting += n ?? 1; }}
                         decrementNumWaiting(n) {{ this.#waiting -= n ?? 1; }}
 
+                        waitables() {{ return [...this.#waitables]; }}
                         targets() {{ return this.#waitables.map(w => w.target); }}
 
                         setTarget(tgt) {{ this.target = tgt; }}
 
                         shuffleWaitables() {{
                             this.#waitables = this.#waitables
-                                .map(value => ({{ value, sort: Math.random() }}))
+                                .map(value => ({{ value, sort: {async_random}.next() }}))
                                 .sort((a, b) => a.sort - b.sort)
                                 .map(({{ value }}) => value);
                         }}
@@ -215,16 +232,21 @@ impl WaitableIntrinsic {
                                 componentIdx: this.#componentIdx,
                                 waitableSet: this,
                             }});
-                            for (const waitable of this.#waitables) {{
-                                if (!waitable.hasPendingEvent()) {{ continue; }}
-                                const event = waitable.getPendingEvent();
-                                {debug_log_fn}('[{waitable_set_class}#getPendingEvent()] found pending event', {{
-                                    waitable,
-                                    event,
-                                }});
-                                return event;
+                            const pending = this.#waitables.filter(w => w.hasPendingEvent());
+                            if (pending.length === 0) {{ throw new Error('no waitables had a pending event'); }}
+
+                            // Any waitable with a pending event may be delivered first, which is always
+                            // the earliest joined waitable when determinism is required
+                            let waitable = pending[0];
+                            if ({global_async_determinism} !== 'deterministic') {{
+                                waitable = pending[Math.floor({async_random}.next() * pending.length)];
                             }}
-                            throw new Error('no waitables had a pending event');
+                            const event = waitable.getPendingEvent();
+                            {debug_log_fn}('[{waitable_set_class}#getPendingEvent()] found pending event', {{
+                                waitable,
+                                event,
+                            }});
+                            return event;
                         }}
 
                         async waitUntil(opts) {{
@@ -235,15 +257,22 @@ impl WaitableIntrinsic {
                             let event;
 
                             this.incrementNumWaiting();
-
-                            const keepGoing = await task.suspendUntil({{
-                                readyFn: () => {{
-                                    const hasPendingEvent = this.hasPendingEvent();
-                                    const ready = readyFn();
-                                    return ready && hasPendingEvent;
-                                }},
-                                cancellable,
-                            }});
+                            task.setWaitingOn(this);
+
+                            let keepGoing;
+                            try {{
+                                keepGoing = await task.suspendUntil({{
+                                    readyFn: () => {{
+                                        const hasPendingEvent = this.hasPendingEvent();
+                                        const ready = readyFn();
+                                        return ready && hasPendingEvent;
+                                    }},
+                                    cancellable,
+                                }});
+                            }} finally {{
+                                task.setWaitingOn(null);
+                                this.decrementNumWaiting();
+                            }}
 
                             if (keepGoing) {{
                                 event = this.getPendingEvent();
@@ -255,8 +284,6 @@ impl WaitableIntrinsic {
                                 }};
                             }}
 
-                            this.decrementNumWaiting();
-
                             return event;
                         }}
 
@@ -287,6 +314,9 @@ impl WaitableIntrinsic {
 
                         #idx = null; // to component-global waitables
 
+                        // the stream/future end or subtask this waitable belongs to (if any)
+                        #owner = null;
+
                         target;
 
                         constructor(args) {{
@@ -307,6 +337,35 @@ impl WaitableIntrinsic {
 
                         setTarget(tgt) {{ this.target = tgt; }}
 
+                        owner() {{ return this.#owner; }}
+                        setOwner(owner) {{ this.#owner = owner; }}
+
+                        // Produce a plain (JSON-friendly) description of this waitable and what it belongs to
+                        describe() {{
+                            const desc = {{
+                                idx: this.#idx,
+                                target: this.target,
+                                hasPendingEvent: this.hasPendingEvent(),
+                            }};
+                            const owner = this.#owner;
+                            if (owner?.getChildTask) {{
+                                const childTask = owner.getChildTask();
+                                desc.subtask = {{
+                                    id: Number(owner.id()),
+                                    fnName: owner.fnName,
+                                    childTaskID: childTask ? Number(childTask.id()) : null,
+                                }};
+                            }} else if (owner?.isHostDriven) {{
+                                desc.end = {{
+                                    kind: owner.streamTableIdx ? 'stream' : 'future',
+                                    target: owner.target,
+                                    otherEnd: owner.peer()?.target ?? null,
+                                    hostDriven: owner.isHostDriven(),
+                                }};
+                            }}
+                            return desc;
+                        }}
+
                         #resetPromise() {{
                             const {{ promise, resolve, reject }} = {promise_with_resolvers_fn}()
                             this.#promise = promise;
@@ -419,6 +478,7 @@ impl WaitableIntrinsic {
                         const wset = new {waitable_set_class}(componentIdx);
                         const rep = state.handles.insert(wset);
                         if (typeof rep !== 'number') {{ throw new Error(`invalid/missing waitable set rep [${{rep}}]`); }}
+                        wset.setTarget(`component [${{componentIdx}}] waitable set [${{rep}}]`);
 
                         {debug_log_fn}('[{waitable_set_new_fn}()] created waitable set', {{ componentIdx, rep }});
                         return rep;
@@ -615,6 +675,9 @@ impl WaitableIntrinsic {
                 let waitable_join_fn = Self::WaitableJoin.name();
                 let get_or_create_async_state_fn =
                     Intrinsic::Component(ComponentIntrinsic::GetOrCreateAsyncState).name();
+                let trace_joined = render_args.async_trace(
+                    "waitableJoined(componentIdx, waitableRep, waitableSetRep, waitable)",
+                );
                 output.push_str(&format!(r#"
                     function {waitable_join_fn}(componentIdx, waitableRep, waitableSetRep) {{
                         {debug_log_fn}('[{waitable_join_fn}()] args', {{
@@ -648,6 +711,7 @@ impl WaitableIntrinsic {
                         }}
 
                         waitable.join(waitableSet);
+                        {trace_joined}
                     }}
                 "#));
             }
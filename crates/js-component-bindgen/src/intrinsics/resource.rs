@@ -67,6 +67,23 @@ pub enum ResourceIntrinsic {
 }
 
 impl ResourceIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::ResourceTableFlag,
+            Self::ResourceTableCreateBorrow,
+            Self::ResourceTableCreateOwn,
+            Self::ResourceTableGet,
+            Self::ResourceTableEnsureBorrowDrop,
+            Self::ResourceTableRemove,
+            Self::ResourceCallBorrows,
+            Self::ResourceTransferBorrow,
+            Self::ResourceTransferBorrowValidLifting,
+            Self::ResourceTransferOwn,
+            Self::CurResourceBorrows,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -97,7 +114,7 @@ impl ResourceIntrinsic {
             Self::ResourceTableCreateBorrow => "rscTableCreateBorrow",
             Self::ResourceTableCreateOwn => "rscTableCreateOwn",
             Self::ResourceTableGet => "rscTableGet",
-            Self::ResourceTableEnsureBorrowDrop => "rscTableTryGet",
+            Self::ResourceTableEnsureBorrowDrop => "rscTableEnsureBorrowDrop",
             Self::ResourceTableRemove => "rscTableRemove",
             Self::ResourceTransferBorrow => "resourceTransferBorrow",
             Self::ResourceTransferBorrowValidLifting => "resourceTransferBorrowValidLifting",
@@ -111,7 +128,7 @@ impl ResourceIntrinsic {
         match self {
             Self::CurResourceBorrows => output.push_str(
                 "
-                let curResourceBorrows = [];
+                const curResourceBorrows = [];
             ",
             ),
 
@@ -295,3 +312,58 @@ impl ResourceIntrinsic {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::{TranspileOpts, transpile};
+
+    #[test]
+    fn intrinsics_define_their_names() {
+        let opts = TranspileOpts::builder().name("component".into()).build();
+        let mut intrinsics = BTreeSet::new();
+        let args = RenderIntrinsicsArgs::builder()
+            .intrinsics(&mut intrinsics)
+            .transpile_opts(&opts)
+            .build();
+        for intrinsic in ResourceIntrinsic::all() {
+            let mut output = Source::default();
+            intrinsic.render(&mut output, &args);
+            let name = intrinsic.name();
+            assert!(
+                [
+                    format!("function {name}("),
+                    format!("const {name} ="),
+                    format!("let {name} ="),
+                ]
+                .iter()
+                .any(|decl| output.contains(decl)),
+                "intrinsic [{name}] does not define a binding with its name"
+            );
+        }
+    }
+
+    #[test]
+    fn borrows_are_cleared_in_place() {
+        let component = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../packages/jco-transpile/test/fixtures/components/runtime/resource-borrow-simple.component.wasm"
+        ))
+        .unwrap();
+        let opts = TranspileOpts::builder().name("borrows".into()).build();
+        let (_, src) = transpile(&component, opts)
+            .unwrap()
+            .files
+            .into_iter()
+            .find(|(name, _)| name == "borrows.js")
+            .unwrap();
+        let src = String::from_utf8(src).unwrap();
+
+        // The binding may be imported from a shared runtime, so it is never reassigned
+        assert!(src.contains("const curResourceBorrows = [];"));
+        assert!(src.contains("curResourceBorrows.length = 0;"));
+        assert_eq!(src.matches("curResourceBorrows = [];").count(), 1);
+    }
+}
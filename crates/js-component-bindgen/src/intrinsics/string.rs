@@ -31,6 +31,21 @@ pub enum StringIntrinsic {
 }
 
 impl StringIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::Utf16Decoder,
+            Self::Utf16Encode,
+            Self::Utf16EncodeAsync,
+            Self::GlobalTextDecoderUtf8,
+            Self::GlobalTextEncoderUtf8,
+            Self::Utf8Encode,
+            Self::Utf8EncodeAsync,
+            Self::ValidateGuestChar,
+            Self::ValidateHostChar,
+        ]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
@@ -9,6 +9,11 @@ pub enum WebIdlIntrinsic {
 }
 
 impl WebIdlIntrinsic {
+    /// All intrinsics of this kind
+    pub fn all() -> &'static [Self] {
+        &[Self::GlobalThisIdlProxy]
+    }
+
     /// Retrieve dependencies for this intrinsic
     pub fn deps() -> &'static [&'static Intrinsic] {
         &[]
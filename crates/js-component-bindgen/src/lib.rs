@@ -1,16 +1,24 @@
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
-use anyhow::{Context as _, Result, anyhow, bail, ensure};
+use anyhow::{Result, ensure};
 use ts_bindgen::ts_bindgen;
-use wasmtime_environ::component::{CanonicalOptions, ComponentTypesBuilder, StaticModuleIndex};
+use wasmtime_environ::component::{
+    CanonicalOptions, ComponentTranslation, ComponentTypesBuilder, CoreDef, ExportItem,
+    GlobalInitializer, InstantiateModule, RuntimeInstanceIndex, StaticModuleIndex, Trampoline,
+};
 use wasmtime_environ::wasmparser::WasmFeatures;
 use wasmtime_environ::{PrimaryMap, ScopeVec, Tunables};
 use wit_bindgen_core::wit_parser::Function;
 use wit_component::DecodedWasm;
 use wit_parser::{Package, Resolve, Stability, Type, TypeDefKind, TypeId, WorldId};
 
+mod asyncify;
 mod core;
+mod core_js;
+mod error;
 mod files;
+mod inspect;
 mod transpile_bindgen;
 mod ts_bindgen;
 
@@ -22,10 +30,14 @@ pub mod source;
 pub mod intrinsics;
 use intrinsics::Intrinsic;
 
-use transpile_bindgen::transpile_bindgen;
+pub use error::{TranspileError, TranspileResult};
+pub use inspect::{FunctionDirection, FunctionInfo, inspect};
+pub use intrinsics::AsyncDeterminismProfile;
 pub use transpile_bindgen::{
-    AsyncMode, BindingsMode, ExportKind, InstantiationMode, TranspileOpts,
+    AsyncMode, BindingsMode, ExportKind, ImportJournalMode, InstantiationMode, ModuleFormat,
+    ResultMode, TranspileOpts, TypeRepresentations,
 };
+use transpile_bindgen::{core_file_name, transpile_bindgen};
 
 /// Calls [`write!`] with the passed arguments and unwraps the result.
 ///
@@ -59,21 +71,47 @@ pub struct Transpiled {
     pub exports: Vec<(String, transpile_bindgen::ExportKind)>,
 }
 
+/// Import, export and function information for a component, as produced by [`inspect`]
 pub struct ComponentInfo {
+    /// Import specifiers, after applying [`TranspileOpts::map`]
     pub imports: Vec<String>,
+    /// Export names (including aliases) along with their kinds
     pub exports: Vec<(String, transpile_bindgen::ExportKind)>,
+    /// Signatures of all imported and exported functions
+    pub functions: Vec<FunctionInfo>,
+}
+
+/// Enable options that are implied by others
+fn apply_implied_opts(opts: &mut TranspileOpts) {
+    // CommonJS modules cannot use top-level await, so initialization must be
+    // awaited through the `$init` export instead
+    if opts.module_format == ModuleFormat::CommonJs {
+        opts.tla_compat = true;
+    }
+    // Core modules compiled to JS can access any number of memories, so there
+    // is no need to polyfill multi-memory
+    if opts.compile_core_to_js {
+        opts.multi_memory = true;
+    }
 }
 
 pub fn generate_types(
     name: &str,
     resolve: Resolve,
     world_id: WorldId,
-    opts: TranspileOpts,
-) -> Result<Vec<(String, Vec<u8>)>> {
+    mut opts: TranspileOpts,
+) -> TranspileResult<Vec<(String, Vec<u8>)>> {
+    apply_implied_opts(&mut opts);
     let mut files = files::Files::default();
 
-    ts_bindgen(name, &resolve, world_id, &opts, &mut files)
-        .context("failed to generate Typescript bindings")?;
+    ts_bindgen(
+        name,
+        &resolve,
+        world_id,
+        &CoreModuleItems::default(),
+        &opts,
+        &mut files,
+    )?;
 
     let mut files_out: Vec<(String, Vec<u8>)> = Vec::new();
     for (name, source) in files.iter() {
@@ -85,9 +123,11 @@ pub fn generate_types(
 /// Generate the JS transpilation bindgen for a given Wasm component binary
 /// Outputs the file map and import and export metadata for the Transpilation
 #[cfg(feature = "transpile-bindgen")]
-pub fn transpile(component: &[u8], opts: TranspileOpts) -> Result<Transpiled> {
+pub fn transpile(component: &[u8], mut opts: TranspileOpts) -> TranspileResult<Transpiled> {
     use wasmtime_environ::component::{Component, Translator};
 
+    apply_implied_opts(&mut opts);
+
     let name = opts.name.clone();
     let mut files = files::Files::default();
 
@@ -97,13 +137,49 @@ pub fn transpile(component: &[u8], opts: TranspileOpts) -> Result<Transpiled> {
     // package which has a single document and `world` within it which describes
     // the state of the component. This is then further used afterwards for
     // bindings Transpilation as-if a `*.wit` file was input.
-    let decoded = wit_component::decode(component)
-        .context("failed to extract interface information from component")?;
+    let (resolve, world_id, core_modules) = decode_component(component)?;
 
-    let (resolve, world_id) = match decoded {
-        DecodedWasm::WitPackage(_, _) => bail!("unexpected wit package as input"),
-        DecodedWasm::Component(resolve, world_id) => (resolve, world_id),
-    };
+    transpile_bindgen::validate_map(&opts.map)?;
+
+    // Source phase imports are an ES module feature, and load core Wasm
+    // rather than the JS emitted for asm.js output
+    if opts.source_phase_imports && (opts.module_format != ModuleFormat::Esm || opts.asmjs) {
+        return Err(TranspileError::UnsupportedFeature {
+            feature: "source phase imports require ES module output with core Wasm modules".into(),
+            item: None,
+        });
+    }
+
+    // Core modules compiled to JS are neither Wasm nor asm.js, and the
+    // compiled functions cannot be suspended
+    if opts.compile_core_to_js {
+        let unsupported = if opts.source_phase_imports {
+            Some("compiling core modules to JS is incompatible with source phase imports")
+        } else if opts.asmjs {
+            Some("compiling core modules to JS is incompatible with asm.js core modules")
+        } else if matches!(
+            opts.async_mode,
+            Some(AsyncMode::JavaScriptPromiseIntegration { .. })
+        ) {
+            Some("compiling core modules to JS is incompatible with JSPI async mode")
+        } else {
+            None
+        };
+        if let Some(feature) = unsupported {
+            return Err(TranspileError::UnsupportedFeature {
+                feature: feature.into(),
+                item: None,
+            });
+        }
+    }
+
+    // Instrumented core modules are rewritten after being converted to asm.js
+    if opts.asyncify() && opts.asmjs {
+        return Err(TranspileError::UnsupportedFeature {
+            feature: "asyncify async mode is incompatible with asm.js core modules".into(),
+            item: None,
+        });
+    }
 
     // Components are complicated, there's no real way around that. To
     // handle all the work of parsing a component and figuring out how to
@@ -133,6 +209,7 @@ pub fn transpile(component: &[u8], opts: TranspileOpts) -> Result<Transpiled> {
         | WasmFeatures::CM_ASYNC
         | WasmFeatures::CM_MORE_ASYNC_BUILTINS
         | WasmFeatures::CM_ASYNC_STACKFUL
+        | WasmFeatures::CM_THREADING
         | WasmFeatures::CM_ERROR_CONTEXT
         | WasmFeatures::CM_FIXED_LENGTH_LISTS
         | WasmFeatures::CM_MAP
@@ -153,30 +230,69 @@ pub fn transpile(component: &[u8], opts: TranspileOpts) -> Result<Transpiled> {
 
     let (component, modules) = Translator::new(&tunables, &mut validator, &mut types, &scope)
         .translate(component)
-        .map_err(|e| anyhow!(e).context("failed to translate component"))?;
+        .map_err(|e| TranspileError::InvalidComponent {
+            message: format!("failed to translate component: {e:#}"),
+            item: None,
+        })?;
 
     let modules: PrimaryMap<StaticModuleIndex, core::Translation<'_>> = modules
         .into_iter()
-        .map(|(_i, module)| core::Translation::new(module, opts.multi_memory))
-        .collect::<Result<_>>()?;
+        .map(|(i, module)| {
+            core::Translation::new(module, opts.multi_memory).map_err(|e| {
+                TranspileError::UnsupportedFeature {
+                    feature: format!("{e:#}"),
+                    item: Some(format!("core module {}", i.as_u32())),
+                }
+            })
+        })
+        .collect::<TranspileResult<_>>()?;
 
     let wasmtime_component = Component::default();
     let types = types.finish(&wasmtime_component);
 
+    let suspending_imports = match &opts.async_mode {
+        Some(AsyncMode::Asyncify { imports, .. }) => {
+            asyncify_suspending_imports(&component, &modules, &imports.iter().cloned().collect())?
+        }
+        _ => HashMap::new(),
+    };
+
     // Insert all core wasm modules into the generated `Files` which will
     // end up getting used in the `generate_instantiate` method.
     for (i, module) in modules.iter() {
-        files.push(&core_file_name(&name, i.as_u32()), module.wasm());
+        let file_name = core_file_name(&name, i.as_u32(), &opts);
+        let wasm = if opts.asyncify() {
+            let suspending = suspending_imports.get(&i);
+            asyncify::instrument(module.wasm(), |module, name| {
+                suspending.is_some_and(|s| s.contains(&(module.to_string(), name.to_string())))
+            })
+            .map_err(|e| TranspileError::UnsupportedFeature {
+                feature: format!("failed to instrument core module for asyncify: {e:#}"),
+                item: Some(format!("core module {}", i.as_u32())),
+            })?
+        } else {
+            Cow::Borrowed(module.wasm())
+        };
+        if opts.compile_core_to_js {
+            let js = core_js::compile(&wasm, opts.module_format).map_err(|e| {
+                TranspileError::UnsupportedFeature {
+                    feature: format!("failed to compile core module to JS: {e:#}"),
+                    item: Some(format!("core module {}", i.as_u32())),
+                }
+            })?;
+            files.push(&file_name, js.as_bytes());
+        } else {
+            files.push(&file_name, &wasm);
+        }
     }
 
     if !opts.no_typescript {
-        ts_bindgen(&name, &resolve, world_id, &opts, &mut files)
-            .context("failed to generate Typescript bindings")?;
+        ts_bindgen(&name, &resolve, world_id, &core_modules, &opts, &mut files)?;
     }
 
     let (imports, exports) = transpile_bindgen(
         &name, &component, &modules, &types.0, &resolve, world_id, opts, &mut files,
-    );
+    )?;
 
     let mut files_out: Vec<(String, Vec<u8>)> = Vec::new();
     for (name, source) in files.iter() {
@@ -189,13 +305,265 @@ pub fn transpile(component: &[u8], opts: TranspileOpts) -> Result<Transpiled> {
     })
 }
 
-fn core_file_name(name: &str, idx: u32) -> String {
-    let i_str = if idx == 0 {
-        String::from("")
-    } else {
-        (idx + 1).to_string()
+/// Determine the imports of each core module that may suspend in asyncify async
+/// mode, by import module and name
+///
+/// These are the lowered imports matching `async_imports`, along with exports
+/// of other core instances that may call them. All instances of a core module
+/// share the same instrumentation, so the imports that may suspend in any of
+/// its instances are combined.
+fn asyncify_suspending_imports(
+    component: &ComponentTranslation,
+    modules: &PrimaryMap<StaticModuleIndex, core::Translation<'_>>,
+    async_imports: &HashSet<String>,
+) -> TranspileResult<HashMap<StaticModuleIndex, HashSet<(String, String)>>> {
+    let mut async_lowerings = HashSet::new();
+    // Module of each core instance, along with its exports that may suspend
+    let mut instances =
+        PrimaryMap::<RuntimeInstanceIndex, Option<(StaticModuleIndex, HashSet<String>)>>::new();
+    let mut suspending = HashMap::<StaticModuleIndex, HashSet<(String, String)>>::new();
+
+    for init in &component.component.initializers {
+        match init {
+            GlobalInitializer::LowerImport { index, import } => {
+                let (import_index, path) = &component.component.imports[*import];
+                let (import_name, _) = &component.component.import_types[*import_index];
+                let may_suspend = match path.as_slice() {
+                    [] => matches_any_pattern(None, import_name, async_imports),
+                    [func_name] => matches_any_pattern(Some(import_name), func_name, async_imports),
+                    _ => false,
+                };
+                if may_suspend {
+                    async_lowerings.insert(*index);
+                }
+            }
+            GlobalInitializer::InstantiateModule(InstantiateModule::Static(idx, args), _) => {
+                let module = &modules[*idx];
+                let mut imports = HashSet::new();
+                for (import_module, import_name, arg) in module.imports(args) {
+                    let core::AugmentedImport::CoreDef(def) = arg else {
+                        continue;
+                    };
+                    let may_suspend = match def {
+                        CoreDef::Trampoline(trampoline) => matches!(
+                            &component.trampolines[*trampoline],
+                            Trampoline::LowerImport { index, .. } if async_lowerings.contains(index)
+                        ),
+                        // Exports of instances of imported modules are not known
+                        CoreDef::Export(export) => match &instances[export.instance] {
+                            Some((module, exports)) => match &export.item {
+                                ExportItem::Name(name) => exports.contains(name),
+                                ExportItem::Index(idx) => modules[*module]
+                                    .exports()
+                                    .iter()
+                                    .find(|(_, export_idx)| *export_idx == idx)
+                                    .is_none_or(|(name, _)| exports.contains(name)),
+                            },
+                            None => true,
+                        },
+                        _ => false,
+                    };
+                    if may_suspend {
+                        imports.insert((import_module.to_string(), import_name.to_string()));
+                    }
+                }
+
+                let exports = asyncify::suspending_exports(module.wasm(), |module, name| {
+                    imports.contains(&(module.to_string(), name.to_string()))
+                })
+                .map_err(|e| TranspileError::UnsupportedFeature {
+                    feature: format!("failed to instrument core module for asyncify: {e:#}"),
+                    item: Some(format!("core module {}", idx.as_u32())),
+                })?;
+                instances.push(Some((*idx, exports)));
+                suspending.entry(*idx).or_default().extend(imports);
+            }
+            GlobalInitializer::InstantiateModule(InstantiateModule::Import(..), _) => {
+                instances.push(None);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(suspending)
+}
+
+/// Names of the core modules imported and exported by a component, which are
+/// not part of its WIT world
+#[derive(Debug, Default)]
+pub(crate) struct CoreModuleItems {
+    pub imports: Vec<String>,
+    pub exports: Vec<String>,
+}
+
+/// Discover the WIT world of a component binary, along with the core modules
+/// it imports and exports.
+///
+/// WIT can't describe exported core modules, so they are removed from the
+/// component before its world is decoded.
+fn decode_component(component: &[u8]) -> TranspileResult<(Resolve, WorldId, CoreModuleItems)> {
+    let (component, core_modules) = split_core_modules(component)?;
+    match wit_component::decode(&component).map_err(invalid_component)? {
+        DecodedWasm::WitPackage(_, _) => Err(TranspileError::InvalidComponent {
+            message: "unexpected wit package as input".into(),
+            item: None,
+        }),
+        DecodedWasm::Component(resolve, world_id) => Ok((resolve, world_id, core_modules)),
+    }
+}
+
+/// Error for a component that its world could not be extracted from
+fn invalid_component(e: impl Into<anyhow::Error>) -> TranspileError {
+    TranspileError::InvalidComponent {
+        message: format!(
+            "failed to extract interface information from component: {:#}",
+            e.into()
+        ),
+        item: None,
+    }
+}
+
+/// Find the core modules imported and exported by a component, returning the
+/// component without its core module exports.
+///
+/// Removing an export shifts the index of any core module that follows it, so
+/// components that define or use core modules after exporting one are rejected
+/// rather than re-indexed.
+fn split_core_modules(component: &[u8]) -> TranspileResult<(Cow<'_, [u8]>, CoreModuleItems)> {
+    use wasm_encoder::reencode::{ReencodeComponent, RoundtripReencoder};
+    use wasmparser::{ComponentExternalKind, ComponentTypeRef, Parser, Payload};
+
+    let mut core_modules = CoreModuleItems::default();
+    let mut stripped = wasm_encoder::Component::new();
+    // Number of items in the core module index space, and the index of the
+    // first export removed from it
+    let mut module_count = 0;
+    let mut first_stripped = None;
+    // Nesting level of modules and components, which are copied over as part
+    // of the section that contains them
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(component) {
+        let payload = payload.map_err(invalid_component)?;
+        if depth > 0 {
+            match payload {
+                Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+                Payload::End(_) => depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
+        let (defined, max_used) = core_module_uses(&payload).map_err(invalid_component)?;
+        if let Some(first_stripped) = first_stripped
+            && (defined > 0 || max_used.is_some_and(|idx| idx >= first_stripped))
+        {
+            return Err(TranspileError::unsupported(
+                "core modules defined or used after a core module export",
+                core_modules.exports[0].clone(),
+            ));
+        }
+        module_count += defined;
+
+        match &payload {
+            Payload::ComponentImportSection(section) => {
+                for import in section.clone() {
+                    let import = import.map_err(invalid_component)?;
+                    if let ComponentTypeRef::Module(_) = import.ty {
+                        core_modules.imports.push(import.name.name.to_string());
+                    }
+                }
+            }
+            Payload::ComponentExportSection(section) => {
+                let mut exports = wasm_encoder::ComponentExportSection::new();
+                for export in section.clone() {
+                    let export = export.map_err(invalid_component)?;
+                    if export.kind == ComponentExternalKind::Module {
+                        core_modules.exports.push(export.name.name.to_string());
+                        first_stripped.get_or_insert(module_count);
+                        module_count += 1;
+                    } else {
+                        RoundtripReencoder
+                            .parse_component_export(&mut exports, export)
+                            .map_err(invalid_component)?;
+                    }
+                }
+                stripped.section(&exports);
+                continue;
+            }
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+            _ => {}
+        }
+        if let Some((id, range)) = payload.as_section() {
+            stripped.section(&wasm_encoder::RawSection {
+                id,
+                data: &component[range],
+            });
+        }
+    }
+
+    if core_modules.exports.is_empty() {
+        return Ok((Cow::Borrowed(component), core_modules));
+    }
+    Ok((Cow::Owned(stripped.finish()), core_modules))
+}
+
+/// Count the core modules a top-level section of a component adds to the
+/// core module index space, other than by exports, along with the highest
+/// core module index it refers to
+fn core_module_uses(payload: &wasmparser::Payload) -> Result<(u32, Option<u32>)> {
+    use wasmparser::{
+        ComponentAlias, ComponentExternalKind, ComponentInstance, ComponentOuterAliasKind,
+        ComponentTypeRef, Instance, Payload,
     };
-    format!("{name}.core{i_str}.wasm")
+
+    let mut defined = 0;
+    let mut max_used = None;
+    let mut use_module = |idx: u32| max_used = max_used.max(Some(idx));
+    match payload {
+        Payload::ModuleSection { .. } => defined += 1,
+        Payload::ComponentImportSection(section) => {
+            for import in section.clone() {
+                if let ComponentTypeRef::Module(_) = import?.ty {
+                    defined += 1;
+                }
+            }
+        }
+        Payload::ComponentAliasSection(section) => {
+            for alias in section.clone() {
+                match alias? {
+                    ComponentAlias::InstanceExport {
+                        kind: ComponentExternalKind::Module,
+                        ..
+                    }
+                    | ComponentAlias::Outer {
+                        kind: ComponentOuterAliasKind::CoreModule,
+                        ..
+                    } => defined += 1,
+                    _ => {}
+                }
+            }
+        }
+        Payload::InstanceSection(section) => {
+            for instance in section.clone() {
+                if let Instance::Instantiate { module_index, .. } = instance? {
+                    use_module(module_index);
+                }
+            }
+        }
+        Payload::ComponentInstanceSection(section) => {
+            for instance in section.clone() {
+                if let ComponentInstance::Instantiate { args, .. } = instance? {
+                    for arg in args.iter() {
+                        if arg.kind == ComponentExternalKind::Module {
+                            use_module(arg.index);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok((defined, max_used))
 }
 
 pub fn dealias(resolve: &Resolve, mut id: TypeId) -> TypeId {
@@ -273,13 +641,10 @@ pub(crate) fn is_async_fn(func: &Function, canon_opts: &CanonicalOptions) -> boo
     func.kind.is_async()
 }
 
-/// Identifier for a function used
-enum FunctionIdentifier<'a> {
-    Fn(&'a Function),
-    CanonFnName(&'a str),
-}
-
-/// Check whether a function has been marked or async binding generation
+/// Check whether a function matches any of the given function patterns
+///
+/// Patterns are used to mark functions for async binding generation (async porcelain) and
+/// for passing `result<T, E>` values as explicit `{ tag, val }` objects.
 ///
 /// When dealing with imports, functions that are designated to require async porcelain
 /// are usually asynchronous host functions -- they will have code generated
@@ -294,35 +659,19 @@ enum FunctionIdentifier<'a> {
 /// to synchronous WebAssembly modules
 ///
 /// This function is *not* for detecting WASI P3 asynchronous behavior -- see [`is_guest_async_lifted_fn`].
-pub(crate) fn requires_async_porcelain(
-    func: FunctionIdentifier<'_>,
-    id: &str,
-    async_funcs: &HashSet<String>,
+///
+/// `interface` is the full id of the interface the function belongs to (e.g. `wasi:io/poll@0.2.0`),
+/// or `None` for functions imported/exported by the world directly. See
+/// [`transpile_bindgen::matches_async_pattern`] for the patterns that may be used in `patterns`.
+pub(crate) fn matches_any_pattern(
+    interface: Option<&str>,
+    func_name: &str,
+    patterns: &HashSet<String>,
 ) -> bool {
-    let name = match func {
-        FunctionIdentifier::Fn(func) => func.name.as_str(),
-        FunctionIdentifier::CanonFnName(name) => name,
-    }
-    .trim_start_matches("[async]");
-
-    if async_funcs.contains(name) {
-        return true;
-    }
-
-    let qualified_name = format!("{id}#{name}");
-    if async_funcs.contains(&qualified_name) {
-        return true;
-    }
-
-    if let Some(pos) = id.find('@') {
-        let namespace = &id[..pos];
-        let namespaced_name = format!("{namespace}#{name}");
-
-        if async_funcs.contains(&namespaced_name) {
-            return true;
-        }
-    }
-    false
+    let func_name = func_name.trim_start_matches("[async]");
+    patterns
+        .iter()
+        .any(|pattern| transpile_bindgen::matches_async_pattern(pattern, interface, func_name))
 }
 
 /// Objects that can control the printing/setup of intrinsics (normally in some final codegen output)
@@ -1,8 +1,19 @@
+use std::collections::HashMap;
 use std::fmt::{self, Write};
 use std::ops::Deref;
 
 pub use wit_parser;
 
+use crate::uwrite;
+
+/// Delimiters of the inline markers used to track source map regions
+///
+/// Markers are written into the generated source by [`Source::begin_mapping`]
+/// and [`Source::end_mapping`], carried along as sources are concatenated,
+/// and removed again by [`extract_source_map`] once the final file is known.
+const MAPPING_MARKER_START: char = '\u{1}';
+const MAPPING_MARKER_END: char = '\u{2}';
+
 #[derive(Default)]
 pub struct Source {
     s: String,
@@ -74,6 +85,172 @@ impl Source {
     pub fn as_mut_string(&mut self) -> &mut String {
         &mut self.s
     }
+
+    /// Begin a region of generated code that originates from `item` (e.g. a
+    /// WIT function or a trampoline), for use in source maps
+    ///
+    /// The marker is written on its own line, which is removed along with the
+    /// marker itself when the source map is extracted.
+    pub fn begin_mapping(&mut self, item: &str) {
+        self.s.push(MAPPING_MARKER_START);
+        self.s.push_str(item);
+        self.s.push(MAPPING_MARKER_END);
+        self.newline();
+    }
+
+    /// End the region started by the last call to [`Source::begin_mapping`]
+    pub fn end_mapping(&mut self) {
+        self.s.push(MAPPING_MARKER_START);
+        self.s.push(MAPPING_MARKER_END);
+        self.newline();
+    }
+}
+
+/// Remove the mapping markers from a generated file, building a source map
+/// (version 3) for it
+///
+/// Each mapped item becomes a virtual source (and name) of its own, so that
+/// debuggers show the WIT item or trampoline kind rather than the generated
+/// function. Returns the source without markers along with the source map JSON.
+pub fn extract_source_map(src: &str, file: &str) -> (String, String) {
+    let mut output = String::with_capacity(src.len());
+    let mut items: Vec<&str> = Vec::new();
+    let mut item_indices: HashMap<&str, usize> = HashMap::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut mappings = String::new();
+    let mut prev_item = 0;
+    let mut first_line = true;
+
+    for line in src.split('\n') {
+        let trimmed = line.trim();
+        let marker_only = trimmed.starts_with(MAPPING_MARKER_START)
+            && trimmed.ends_with(MAPPING_MARKER_END)
+            && trimmed.matches(MAPPING_MARKER_START).count() == 1;
+
+        // Segments of the current line as (generated column, item index)
+        let mut segments: Vec<(usize, Option<usize>)> = Vec::new();
+        let mut push_segment = |col: usize, item: Option<usize>| {
+            if let Some(last) = segments.last_mut()
+                && last.0 == col
+            {
+                last.1 = item;
+            } else {
+                segments.push((col, item));
+            }
+        };
+
+        let mut line_out = String::with_capacity(line.len());
+        let mut col = 0;
+        if let Some(&top) = stack.last()
+            && !marker_only
+        {
+            push_segment(0, Some(top));
+        }
+        let mut rest = line;
+        while let Some(start) = rest.find(MAPPING_MARKER_START) {
+            let before = &rest[..start];
+            line_out.push_str(before);
+            col += before.encode_utf16().count();
+            let after = &rest[start + MAPPING_MARKER_START.len_utf8()..];
+            let end = after.find(MAPPING_MARKER_END).unwrap_or(after.len());
+            let item = &after[..end];
+            if item.is_empty() {
+                stack.pop();
+            } else {
+                let idx = *item_indices.entry(item).or_insert_with(|| {
+                    items.push(item);
+                    items.len() - 1
+                });
+                stack.push(idx);
+            }
+            push_segment(col, stack.last().copied());
+            rest = after
+                .get(end + MAPPING_MARKER_END.len_utf8()..)
+                .unwrap_or_default();
+        }
+        line_out.push_str(rest);
+
+        if marker_only {
+            continue;
+        }
+        if !first_line {
+            output.push('\n');
+            mappings.push(';');
+        }
+        first_line = false;
+        output.push_str(&line_out);
+
+        // Trailing unmapped segments carry no information
+        while segments.last().is_some_and(|(_, item)| item.is_none()) {
+            segments.pop();
+        }
+        let mut prev_col = 0;
+        for (i, (col, item)) in segments.iter().enumerate() {
+            if i > 0 {
+                mappings.push(',');
+            }
+            encode_vlq(&mut mappings, *col as i64 - prev_col as i64);
+            prev_col = *col;
+            if let Some(item) = item {
+                // Every item is a source of its own (starting at line 0,
+                // column 0) as well as a name
+                encode_vlq(&mut mappings, *item as i64 - prev_item as i64);
+                encode_vlq(&mut mappings, 0);
+                encode_vlq(&mut mappings, 0);
+                encode_vlq(&mut mappings, *item as i64 - prev_item as i64);
+                prev_item = *item;
+            }
+        }
+    }
+
+    let items_json = items
+        .iter()
+        .map(|item| json_string(item))
+        .collect::<Vec<_>>()
+        .join(",");
+    let source_map = format!(
+        r#"{{"version":3,"file":{},"sources":[{items_json}],"sourcesContent":[{items_json}],"names":[{items_json}],"mappings":"{mappings}"}}"#,
+        json_string(file),
+    );
+    (output, source_map)
+}
+
+/// Append a base64 VLQ encoded value, as used by source map mappings
+fn encode_vlq(out: &mut String, value: i64) {
+    const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Render a string as a JSON string literal
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => uwrite!(out, "\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
 }
 
 impl Write for Source {
@@ -98,7 +275,7 @@ impl From<Source> for String {
 
 #[cfg(test)]
 mod tests {
-    use super::Source;
+    use super::{Source, extract_source_map};
 
     #[test]
     fn simple_append() {
@@ -145,4 +322,23 @@ mod tests {
         );
         assert_eq!(s.s, "function() {\n  x\n}");
     }
+
+    #[test]
+    fn source_map_regions() {
+        let mut s = Source::default();
+        s.push_str("let x;\n");
+        s.begin_mapping("local:pkg/iface#f");
+        s.push_str("function f() {\n");
+        s.push_str("return x;\n");
+        s.push_str("}\n");
+        s.end_mapping();
+        s.push_str("let y;");
+
+        let (output, source_map) = extract_source_map(&s, "out.js");
+        assert_eq!(output, "let x;\nfunction f() {\n  return x;\n}\nlet y;");
+        assert_eq!(
+            source_map,
+            r#"{"version":3,"file":"out.js","sources":["local:pkg/iface#f"],"sourcesContent":["local:pkg/iface#f"],"names":["local:pkg/iface#f"],"mappings":";AAAAA;AAAAA;AAAAA;"}"#
+        );
+    }
 }
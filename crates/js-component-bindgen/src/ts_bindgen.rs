@@ -11,13 +11,15 @@ use wit_bindgen_core::wit_parser::{
 };
 
 use crate::files::Files;
-use crate::function_bindgen::{as_nullable, js_array_ty, maybe_null};
+use crate::function_bindgen::{TypeRepr, TypeReprMap, as_nullable, js_array_ty, maybe_null};
 use crate::names::{LocalNames, RESERVED_KEYWORDS, is_valid_js_identifier, maybe_quote_id};
 use crate::source::Source;
-use crate::transpile_bindgen::{AsyncMode, InstantiationMode, TranspileOpts, parse_world_key};
+use crate::transpile_bindgen::{
+    AsyncMode, InstantiationMode, ResultMode, TranspileOpts, parse_world_key, resolve_type_reprs,
+};
 use crate::{
-    FunctionIdentifier, dealias, feature_gate_allowed, get_thrown_type, requires_async_porcelain,
-    uwrite, uwriteln,
+    CoreModuleItems, TranspileError, TranspileResult, dealias, feature_gate_allowed,
+    get_thrown_type, matches_any_pattern, uwrite, uwriteln,
 };
 
 /// TypeScript declaration for the `Option<T>` helper type alias used to
@@ -36,6 +38,13 @@ const TS_OPTION_TYPE_ALIAS: &str =
 const TS_RESULT_TYPE_ALIAS: &str =
     "export type Result<T, E> = { tag: 'ok', val: T } | { tag: 'err', val: E };\n";
 
+/// TypeScript declaration for the `ErrorContext` values that represent WIT `error-context`s.
+/// Emitted at the top level for the same reason as [`TS_OPTION_TYPE_ALIAS`].
+const TS_ERROR_CONTEXT_TYPE: &str = "export interface ErrorContext extends Error {
+  debugMessage(): string;
+}
+";
+
 struct TsBindgen {
     /// The source code for the "main" file that's going to be created for the
     /// component we're generating bindings for. This is incrementally added to
@@ -59,6 +68,7 @@ struct TsBindgen {
     /// instantiation mode (see https://github.com/bytecodealliance/jco/issues/1708).
     needs_ty_option: bool,
     needs_ty_result: bool,
+    needs_ty_error_context: bool,
 
     /// Whether to generate types for a guest module.
     ///
@@ -69,6 +79,15 @@ struct TsBindgen {
     async_imports: HashSet<String>,
     async_exports: HashSet<String>,
 
+    /// Functions that return `Result<T, E>` objects rather than throwing
+    /// (see [`ResultMode::Explicit`])
+    explicit_result_imports: HashSet<String>,
+    explicit_result_exports: HashSet<String>,
+
+    /// Alternative representations of WIT types
+    /// (see [`TypeRepresentations`](crate::transpile_bindgen::TypeRepresentations))
+    type_reprs: TypeReprMap,
+
     /// A set of all interface files that are referenced by the generated
     /// definitions. This is used to generate `/// <reference path="..." />`
     /// directives at the top of the file.
@@ -104,25 +123,58 @@ struct TsInterface<'a> {
     is_root: bool,
     is_guest: bool,
     resolve: &'a Resolve,
+    type_reprs: &'a TypeReprMap,
     has_constructor: bool,
     needs_ty_option: bool,
     needs_ty_result: bool,
+    needs_ty_error_context: bool,
     needs_module_end: bool,
     local_names: LocalNames,
     resources: BTreeMap<String, (GeneratedTypeMeta, TsInterface<'a>)>,
     references: BTreeSet<String>,
+    /// First error raised while printing types, reported when the interface is finished
+    error: Option<TranspileError>,
 }
 
+/// Generate the TypeScript declarations for a given world
+///
+/// Errors raised during generation are surfaced as [`TranspileError::TypeGeneration`],
+/// unless a more specific [`TranspileError`] was raised.
 pub fn ts_bindgen(
     name: &str,
     resolve: &Resolve,
     id: WorldId,
+    core_modules: &CoreModuleItems,
+    opts: &TranspileOpts,
+    files: &mut Files,
+) -> TranspileResult<()> {
+    generate_world_types(name, resolve, id, core_modules, opts, files).map_err(|e| {
+        e.downcast::<TranspileError>()
+            .unwrap_or_else(|e| TranspileError::TypeGeneration {
+                message: format!("{e:#}"),
+                item: None,
+            })
+    })
+}
+
+fn generate_world_types(
+    name: &str,
+    resolve: &Resolve,
+    id: WorldId,
+    core_modules: &CoreModuleItems,
     opts: &TranspileOpts,
     files: &mut Files,
 ) -> Result<()> {
     let (async_imports, async_exports) = match opts.async_mode.clone() {
         None | Some(AsyncMode::Sync) => (Default::default(), Default::default()),
-        Some(AsyncMode::JavaScriptPromiseIntegration { imports, exports }) => {
+        Some(
+            AsyncMode::JavaScriptPromiseIntegration { imports, exports }
+            | AsyncMode::Asyncify { imports, exports },
+        ) => (imports.into_iter().collect(), exports.into_iter().collect()),
+    };
+    let (explicit_result_imports, explicit_result_exports) = match opts.result_mode.clone() {
+        None | Some(ResultMode::Throw) => (Default::default(), Default::default()),
+        Some(ResultMode::Explicit { imports, exports }) => {
             (imports.into_iter().collect(), exports.into_iter().collect())
         }
     };
@@ -135,9 +187,13 @@ pub fn ts_bindgen(
         export_object: Source::default(),
         needs_ty_option: false,
         needs_ty_result: false,
+        needs_ty_error_context: false,
         is_guest: opts.guest,
         async_imports,
         async_exports,
+        explicit_result_imports,
+        explicit_result_exports,
+        type_reprs: resolve_type_reprs(resolve, &opts.type_representations)?,
         references: Default::default(),
     };
 
@@ -204,7 +260,7 @@ pub fn ts_bindgen(
                                 *id,
                                 files,
                                 opts.instantiation_mode.is_some(),
-                            );
+                            )?;
                         }
 
                         // namespaced ns:pkg/iface
@@ -238,50 +294,37 @@ pub fn ts_bindgen(
                         continue;
                     }
 
-                    let mut generator = TsInterface::new(resolve, true, opts.guest);
-                    generator.docs(&ty.docs);
-
-                    match &ty.kind {
-                        TypeDefKind::Record(record) => {
-                            generator.type_record(*id, name, record, &ty.docs)
-                        }
-                        TypeDefKind::Flags(flags) => {
-                            generator.type_flags(*id, name, flags, &ty.docs)
-                        }
-                        TypeDefKind::Tuple(tuple) => {
-                            generator.type_tuple(*id, name, tuple, &ty.docs)
-                        }
-                        TypeDefKind::Enum(enum_) => generator.type_enum(*id, name, enum_, &ty.docs),
-                        TypeDefKind::Variant(variant) => {
-                            generator.type_variant(*id, name, variant, &ty.docs)
-                        }
-                        TypeDefKind::Option(t) => generator.type_option(*id, name, t, &ty.docs),
-                        TypeDefKind::Result(r) => generator.type_result(*id, name, r, &ty.docs),
-                        TypeDefKind::List(t) => generator.type_list(*id, name, t, &ty.docs),
-                        TypeDefKind::FixedLengthList(t, len) => {
-                            generator.type_fixed_size_list(*id, name, t, len, &ty.docs)
-                        }
-                        TypeDefKind::Type(t) => generator.type_alias(*id, name, t, None, &ty.docs),
-                        TypeDefKind::Future(t) => generator.type_future(name, t, &ty.docs),
-                        TypeDefKind::Stream(t) => generator.type_stream(name, t, &ty.docs),
-                        TypeDefKind::Unknown => unreachable!("(async impl) generate for unknown"),
-                        TypeDefKind::Resource => {
-                            generator.type_resource(*id, ty, GeneratedTypeMeta { is_export: false })
-                        }
-                        TypeDefKind::Handle(_) => todo!("type generation for handle"),
-                        TypeDefKind::Map(key, value) => {
-                            generator.type_map(*id, name, key, value, &ty.docs)
-                        }
+                    bindgen.world_type(
+                        resolve,
+                        name,
+                        *id,
+                        GeneratedTypeMeta { is_export: false },
+                    )?;
+
+                    // World-level class variants are defined by the bindings
+                    if opts.instantiation_mode.is_some()
+                        && matches!(
+                            bindgen.type_reprs.get(id),
+                            Some(TypeRepr::ClassVariant { .. })
+                        )
+                    {
+                        let class_name = name.to_upper_camel_case();
+                        uwriteln!(bindgen.export_object, "{class_name}: typeof {class_name},");
                     }
-                    let (src, references) = generator.finish();
-                    bindgen.src.push_str(&src);
-                    bindgen.references.extend(references);
                 }
             }
         }
         // kebab import funcs (always default imports)
         for (name, func) in funcs {
-            bindgen.import_funcs(resolve, &name, func, files);
+            bindgen.import_funcs(resolve, &name, func, files)?;
+        }
+        // core modules are default imports as well
+        for name in core_modules.imports.iter() {
+            uwriteln!(
+                bindgen.import_object,
+                "{}: {{ default: WebAssembly.Module }},",
+                maybe_quote_id(name)
+            );
         }
         // namespace imports are grouped by namespace / kebab name
         // kebab name imports are direct
@@ -292,7 +335,7 @@ pub fn ts_bindgen(
                 import_interfaces,
                 files,
                 opts.instantiation_mode.is_some(),
-            );
+            )?;
         }
     }
 
@@ -341,24 +384,69 @@ pub fn ts_bindgen(
                 }
 
                 let instantiation = opts.instantiation_mode.is_some();
-                bindgen.export_interface(resolve, export_name, *id, files, instantiation);
+                bindgen.export_interface(resolve, export_name, *id, files, instantiation)?;
                 // Also export the interface name as a type alias
                 let alt_export_name = iface_name.to_lower_camel_case();
                 if alt_export_name != export_name {
-                    bindgen.export_interface(resolve, &alt_export_name, *id, files, instantiation);
+                    bindgen.export_interface(
+                        resolve,
+                        &alt_export_name,
+                        *id,
+                        files,
+                        instantiation,
+                    )?;
+                }
+            }
+            WorldItem::Type { id, .. } => {
+                let ty = &resolve.types[*id];
+                let type_name = ty.name.as_ref().unwrap();
+
+                if !feature_gate_allowed(resolve, package, &ty.stability, type_name)
+                    .context("failed to check feature gate for exported type")?
+                {
+                    debug!(
+                        "skipping exported type [{type_name}] feature gate due to feature gate visibility"
+                    );
+                    continue;
+                }
+
+                bindgen.world_type(
+                    resolve,
+                    type_name,
+                    *id,
+                    GeneratedTypeMeta { is_export: true },
+                )?;
+
+                // Exported resources and class variants are classes, and so are
+                // values of the export object as well
+                if opts.instantiation_mode.is_some()
+                    && (matches!(
+                        resolve.types[dealias(resolve, *id)].kind,
+                        TypeDefKind::Resource
+                    ) || matches!(
+                        bindgen.type_reprs.get(id),
+                        Some(TypeRepr::ClassVariant { .. })
+                    ))
+                {
+                    let class_name = type_name.to_upper_camel_case();
+                    uwriteln!(bindgen.export_object, "{class_name}: typeof {class_name},");
                 }
             }
-            WorldItem::Type { .. } => unimplemented!("type exports"),
         }
     }
     if !funcs.is_empty() {
-        bindgen.export_funcs(
-            resolve,
-            id,
-            &funcs,
-            files,
-            opts.instantiation_mode.is_none(),
-        );
+        bindgen.export_funcs(resolve, &funcs, files, opts.instantiation_mode.is_none())?;
+    }
+    for name in core_modules.exports.iter() {
+        let name = name.to_lower_camel_case();
+        if opts.instantiation_mode.is_some() {
+            uwriteln!(bindgen.export_object, "{name}: WebAssembly.Module,");
+        } else {
+            uwriteln!(
+                bindgen.export_object,
+                "export const {name}: WebAssembly.Module;"
+            );
+        }
     }
 
     let camel = world.name.to_upper_camel_case();
@@ -393,6 +481,9 @@ pub fn ts_bindgen(
     if bindgen.needs_ty_result {
         bindgen.src.push_str(TS_RESULT_TYPE_ALIAS);
     }
+    if bindgen.needs_ty_error_context {
+        bindgen.src.push_str(TS_ERROR_CONTEXT_TYPE);
+    }
 
     if opts.tla_compat && opts.instantiation_mode.is_none() {
         uwriteln!(
@@ -431,6 +522,15 @@ pub fn ts_bindgen(
             );
         }
     } else {
+        // Core modules compiled to JS are functions instantiating the module
+        let (core_module, core_instance) = if opts.compile_core_to_js {
+            (
+                "((imports: Record<string, any>) => Record<string, any>)",
+                "{ exports: Record<string, any> }",
+            )
+        } else {
+            ("WebAssembly.Module", "WebAssembly.Instance")
+        };
         match opts.instantiation_mode {
             Some(InstantiationMode::Async) => {
                 uwriteln!(
@@ -456,14 +556,14 @@ pub fn ts_bindgen(
                  * `WebAssembly.Module` constructor on the web, for example.
                  */
                 export function instantiate(
-                    getCoreModule: (path: string) => WebAssembly.Module,
+                    getCoreModule: (path: string) => {core_module},
                     imports: ImportObject,
-                    instantiateCore?: (module: WebAssembly.Module, imports: Record<string, any>) => WebAssembly.Instance
+                    instantiateCore?: (module: {core_module}, imports: Record<string, any>) => {core_instance}
                 ): {camel};
                 export function instantiate(
-                    getCoreModule: (path: string) => WebAssembly.Module | Promise<WebAssembly.Module>,
+                    getCoreModule: (path: string) => {core_module} | Promise<{core_module}>,
                     imports: ImportObject,
-                    instantiateCore?: (module: WebAssembly.Module, imports: Record<string, any>) => WebAssembly.Instance | Promise<WebAssembly.Instance>
+                    instantiateCore?: (module: {core_module}, imports: Record<string, any>) => {core_instance} | Promise<{core_instance}>
                 ): {camel} | Promise<{camel}>;
                 ",
                 );
@@ -493,9 +593,9 @@ pub fn ts_bindgen(
                      * `WebAssembly.Module` constructor on the web, for example.
                      */
                     export function instantiate(
-                        getCoreModule: (path: string) => WebAssembly.Module,
+                        getCoreModule: (path: string) => {core_module},
                         imports: ImportObject,
-                        instantiateCore?: (module: WebAssembly.Module, imports: Record<string, any>) => WebAssembly.Instance
+                        instantiateCore?: (module: {core_module}, imports: Record<string, any>) => {core_instance}
                     ): {camel};
                     ",
                 )
@@ -509,7 +609,7 @@ pub fn ts_bindgen(
         uwriteln!(bindgen.src, "}}");
     }
 
-    let filename = format!("{name}.d.ts");
+    let filename = format!("{name}.{}", opts.module_format.dts_extension());
     files.push(
         &filename,
         generate_references(&bindgen.references).as_bytes(),
@@ -520,6 +620,47 @@ pub fn ts_bindgen(
 }
 
 impl TsBindgen {
+    /// Generates the definition of a type that is imported or exported by the world itself
+    fn world_type(
+        &mut self,
+        resolve: &Resolve,
+        name: &str,
+        id: TypeId,
+        meta: GeneratedTypeMeta,
+    ) -> Result<()> {
+        let ty = &resolve.types[id];
+        let mut generator = TsInterface::new(resolve, &self.type_reprs, true, self.is_guest);
+        generator.docs(&ty.docs);
+
+        match &ty.kind {
+            TypeDefKind::Record(record) => generator.type_record(id, name, record, &ty.docs),
+            TypeDefKind::Flags(flags) => generator.type_flags(id, name, flags, &ty.docs),
+            TypeDefKind::Tuple(tuple) => generator.type_tuple(id, name, tuple, &ty.docs),
+            TypeDefKind::Enum(enum_) => generator.type_enum(id, name, enum_, &ty.docs),
+            TypeDefKind::Variant(variant) => generator.type_variant(id, name, variant, &ty.docs),
+            TypeDefKind::Option(t) => generator.type_option(id, name, t, &ty.docs),
+            TypeDefKind::Result(r) => generator.type_result(id, name, r, &ty.docs),
+            TypeDefKind::List(t) => generator.type_list(id, name, t, &ty.docs),
+            TypeDefKind::FixedLengthList(t, len) => {
+                generator.type_fixed_size_list(id, name, t, len, &ty.docs)
+            }
+            TypeDefKind::Type(t) => generator.type_alias(id, name, t, None, &ty.docs),
+            TypeDefKind::Future(t) => generator.type_future(name, t, &ty.docs),
+            TypeDefKind::Stream(t) => generator.type_stream(name, t, &ty.docs),
+            TypeDefKind::Unknown => unreachable!("(async impl) generate for unknown"),
+            TypeDefKind::Resource => generator.type_resource(id, ty, meta),
+            TypeDefKind::Handle(Handle::Own(resource) | Handle::Borrow(resource)) => {
+                generator.type_alias(id, name, &Type::Id(*resource), None, &ty.docs)
+            }
+            TypeDefKind::Map(key, value) => generator.type_map(id, name, key, value, &ty.docs),
+        }
+        self.needs_ty_error_context |= generator.needs_ty_error_context;
+        let (src, references) = generator.finish()?;
+        self.src.push_str(&src);
+        self.references.extend(references);
+        Ok(())
+    }
+
     fn world_import_interface(
         &mut self,
         resolve: &Resolve,
@@ -527,10 +668,10 @@ impl TsBindgen {
         id: InterfaceId,
         files: &mut Files,
         instantiation: bool,
-    ) {
+    ) -> Result<()> {
         if instantiation {
             // in case an imported type is used as an exported type
-            let local_name = self.import_interface(name, resolve, id, files);
+            let local_name = self.import_interface(name, resolve, id, files)?;
             uwriteln!(
                 self.import_object,
                 "{}: typeof {local_name},",
@@ -546,7 +687,7 @@ impl TsBindgen {
                 id,
                 files,
                 GeneratedTypeMeta { is_export: false },
-            );
+            )?;
             // Alias under the import's own name: a named interface may be
             // imported under one or more plain labels via the component model
             // `implements` feature, in which case each label gets its own
@@ -558,6 +699,7 @@ impl TsBindgen {
                 id_name
             );
         }
+        Ok(())
     }
 
     fn world_import_interfaces(
@@ -567,22 +709,22 @@ impl TsBindgen {
         ifaces: Vec<(String, &InterfaceId)>,
         files: &mut Files,
         instantiation: bool,
-    ) {
+    ) -> Result<()> {
         if instantiation {
             if ifaces.len() == 1 {
                 let (iface_name, id) = ifaces.first().unwrap();
                 if iface_name == "*" {
                     uwrite!(self.import_object, "{}: ", maybe_quote_id(import_name));
                     let name = resolve.interfaces[**id].name.as_ref().unwrap();
-                    let local_name = self.import_interface(name, resolve, **id, files);
+                    let local_name = self.import_interface(name, resolve, **id, files)?;
                     uwriteln!(self.import_object, "typeof {local_name},",);
-                    return;
+                    return Ok(());
                 }
             }
             uwriteln!(self.import_object, "{}: {{", maybe_quote_id(import_name));
             for (iface_name, &id) in ifaces {
                 let name = resolve.interfaces[id].name.as_ref().unwrap();
-                let local_name = self.import_interface(name, resolve, id, files);
+                let local_name = self.import_interface(name, resolve, id, files)?;
                 uwriteln!(
                     self.import_object,
                     "{}: typeof {local_name},",
@@ -601,7 +743,7 @@ impl TsBindgen {
                     id,
                     files,
                     GeneratedTypeMeta { is_export: false },
-                );
+                )?;
                 uwriteln!(
                     self.src,
                     "export type * as {} from '{import_path}'; // import {}",
@@ -610,6 +752,7 @@ impl TsBindgen {
                 );
             }
         }
+        Ok(())
     }
 
     fn import_funcs(
@@ -618,20 +761,26 @@ impl TsBindgen {
         import_name: &str,
         func: &Function,
         _files: &mut Files,
-    ) {
+    ) -> Result<()> {
         uwriteln!(self.import_object, "{}: {{", maybe_quote_id(import_name));
-        let mut generator = TsInterface::new(resolve, false, self.is_guest);
+        let explicit_result = matches_any_pattern(None, &func.name, &self.explicit_result_imports);
+        let mut generator = TsInterface::new(resolve, &self.type_reprs, false, self.is_guest);
         generator.ts_func(
             func,
             true,
             false,
             false,
+            explicit_result,
             &GeneratedTypeMeta { is_export: false },
         );
-        let (src, references) = generator.finish();
+        self.needs_ty_option |= generator.needs_ty_option;
+        self.needs_ty_result |= generator.needs_ty_result;
+        self.needs_ty_error_context |= generator.needs_ty_error_context;
+        let (src, references) = generator.finish()?;
         self.import_object.push_str(&src);
         self.references.extend(references);
         uwriteln!(self.import_object, "}},");
+        Ok(())
     }
 
     fn export_interface(
@@ -641,9 +790,9 @@ impl TsBindgen {
         id: InterfaceId,
         files: &mut Files,
         instantiation: bool,
-    ) {
+    ) -> Result<()> {
         if instantiation {
-            let local_name = self.import_interface(export_name, resolve, id, files);
+            let local_name = self.import_interface(export_name, resolve, id, files)?;
             uwriteln!(
                 self.export_object,
                 "{}: typeof {local_name},",
@@ -661,31 +810,29 @@ impl TsBindgen {
                 id,
                 files,
                 GeneratedTypeMeta { is_export: true },
-            );
+            )?;
             uwriteln!(
                 self.export_object,
                 "export * as {export_name} from '{file_name}'; // export {id_name}"
             );
         }
+        Ok(())
     }
 
     fn export_funcs(
         &mut self,
         resolve: &Resolve,
-        world: WorldId,
         funcs: &[(String, &Function)],
         _files: &mut Files,
         declaration: bool,
-    ) {
-        let mut generator = TsInterface::new(resolve, false, self.is_guest);
-        let id_name = &resolve.worlds[world].name;
+    ) -> Result<()> {
+        let mut generator = TsInterface::new(resolve, &self.type_reprs, false, self.is_guest);
 
         for (_, func) in funcs {
-            let requires_async_porcelain = requires_async_porcelain(
-                FunctionIdentifier::Fn(func),
-                id_name,
-                &self.async_exports,
-            );
+            let requires_async_porcelain =
+                matches_any_pattern(None, &func.name, &self.async_exports);
+            let explicit_result =
+                matches_any_pattern(None, &func.name, &self.explicit_result_exports);
 
             // Figure out whether we need ancillary types
             for ty in func.parameter_and_result_types() {
@@ -712,6 +859,7 @@ impl TsBindgen {
                 false,
                 declaration,
                 requires_async_porcelain,
+                explicit_result,
                 &GeneratedTypeMeta { is_export: true },
             );
         }
@@ -726,10 +874,16 @@ impl TsBindgen {
             || generator.resources.iter().any(|(_, r)| r.1.needs_ty_option);
         self.needs_ty_result |= generator.needs_ty_result
             || generator.resources.iter().any(|(_, r)| r.1.needs_ty_result);
+        self.needs_ty_error_context |= generator.needs_ty_error_context
+            || generator
+                .resources
+                .iter()
+                .any(|(_, r)| r.1.needs_ty_error_context);
 
-        let (src, references) = generator.finish();
+        let (src, references) = generator.finish()?;
         self.export_object.push_str(&src);
         self.references.extend(references);
+        Ok(())
     }
 
     /// Adds an import for the given interface to the generated source code,
@@ -740,7 +894,7 @@ impl TsBindgen {
         resolve: &Resolve,
         id: InterfaceId,
         files: &mut Files,
-    ) -> String {
+    ) -> Result<String> {
         let id_name = resolve.id_of(id).unwrap_or_else(|| name.to_string());
         let goal_name = interface_goal_name(&id_name);
         let file_name = self.generate_interface(
@@ -749,7 +903,7 @@ impl TsBindgen {
             id,
             files,
             GeneratedTypeMeta { is_export: false },
-        );
+        )?;
 
         let (local_name, local_exists) = self.local_names.get_or_create(&file_name, &goal_name);
         let local_name = local_name.to_upper_camel_case();
@@ -760,7 +914,7 @@ impl TsBindgen {
             );
         }
 
-        local_name
+        Ok(local_name)
     }
 
     /// Generates a definition file for the given interface, if it doesn't already
@@ -775,7 +929,7 @@ impl TsBindgen {
         interface_id: InterfaceId,
         files: &mut Files,
         type_meta: GeneratedTypeMeta,
-    ) -> String {
+    ) -> Result<String> {
         let id_name = resolve
             .id_of(interface_id)
             .unwrap_or_else(|| name.to_string());
@@ -786,70 +940,72 @@ impl TsBindgen {
         let (_name, iface_exists) = self.interface_names.get_or_create(&file_name, &goal_name);
 
         if !iface_exists {
-            let mut generator = TsInterface::new(resolve, false, self.is_guest);
+            let mut generator = TsInterface::new(resolve, &self.type_reprs, false, self.is_guest);
             generator.begin(&id_name); // Write module declaration
 
             // Generate function definitions
-            let async_funcs = if resolve.exports_interface(interface_id) {
-                &self.async_exports
+            let (async_funcs, explicit_result_funcs) = if resolve.exports_interface(interface_id) {
+                (&self.async_exports, &self.explicit_result_exports)
             } else {
-                &self.async_imports
+                (&self.async_imports, &self.explicit_result_imports)
             };
 
             let iface = &resolve.interfaces[interface_id];
-            let package_id = iface.package.expect("missing package on interface");
+            let package_id = iface
+                .package
+                .with_context(|| format!("missing package on interface [{id_name}]"))?;
             let package = resolve
                 .packages
                 .get(package_id)
-                .expect("unexpectedly missing package");
+                .context("unexpectedly missing package")?;
 
             for (_, func) in iface.functions.iter() {
                 // Ensure that the function  the world item for stability guarantees and exclude if they do not match
                 if !feature_gate_allowed(resolve, package, &func.stability, &func.name)
-                    .expect("failed to check feature gate for function")
+                    .context("failed to check feature gate for function")?
                 {
                     continue;
                 }
 
-                let func_name = func.name.clone();
-
-                let canonicalized_fn_name = match resolve.canonicalized_id_of(interface_id) {
-                    Some(canon_iface_name) => {
-                        format!("{}#{func_name}", canon_iface_name.trim_start_matches("$"))
-                    }
-                    // NOTE: we can't get canonicalized interface names for anonymous interfaces
-                    // so we use the function name bare
-                    None => func_name,
-                };
-
-                let requires_async_porcelain = requires_async_porcelain(
-                    FunctionIdentifier::CanonFnName(&canonicalized_fn_name),
-                    &id_name,
-                    async_funcs,
+                let requires_async_porcelain =
+                    matches_any_pattern(Some(&id_name), &func.name, async_funcs);
+                let explicit_result =
+                    matches_any_pattern(Some(&id_name), &func.name, explicit_result_funcs);
+
+                generator.ts_func(
+                    func,
+                    false,
+                    true,
+                    requires_async_porcelain,
+                    explicit_result,
+                    &type_meta,
                 );
-
-                generator.ts_func(func, false, true, requires_async_porcelain, &type_meta);
             }
 
-            generator.types(interface_id);
+            generator.types(interface_id)?;
             generator.post_types();
 
-            let (src, references) = generator.finish();
+            let (src, references) = generator.finish()?;
             files.push(&file_name, generate_references(&references).as_bytes());
             files.push(&file_name, src.as_bytes());
         }
 
         if self.is_guest {
             self.references.insert(format!("./{file_name}"));
-            id_name
+            Ok(id_name)
         } else {
-            format!("./{file_stem}.js")
+            Ok(format!("./{file_stem}.js"))
         }
     }
 }
 
 impl<'a> TsInterface<'a> {
-    fn new(resolve: &'a Resolve, is_root: bool, is_guest: bool) -> Self {
+    fn new(
+        resolve: &'a Resolve,
+        type_reprs: &'a TypeReprMap,
+        is_root: bool,
+        is_guest: bool,
+    ) -> Self {
         TsInterface {
             is_root,
             is_guest,
@@ -857,11 +1013,14 @@ impl<'a> TsInterface<'a> {
             resources: BTreeMap::new(),
             local_names: LocalNames::default(),
             resolve,
+            type_reprs,
             has_constructor: false,
             needs_ty_option: false,
             needs_ty_result: false,
+            needs_ty_error_context: false,
             needs_module_end: false,
             references: Default::default(),
+            error: None,
         }
     }
 
@@ -874,8 +1033,14 @@ impl<'a> TsInterface<'a> {
         }
     }
 
-    fn finish(mut self) -> (Source, BTreeSet<String>) {
+    fn finish(mut self) -> TranspileResult<(Source, BTreeSet<String>)> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
         for (resource, (meta, source)) in self.resources {
+            if let Some(err) = source.error {
+                return Err(err);
+            }
             let generates_disposable_resource =
                 (self.is_guest && meta.is_import()) || (!self.is_guest && meta.is_export());
             let (impl_phrase, extra_members) = if generates_disposable_resource {
@@ -911,7 +1076,7 @@ impl<'a> TsInterface<'a> {
         if self.needs_module_end {
             uwriteln!(self.src, "}}");
         }
-        (self.src, self.references)
+        Ok((self.src, self.references))
     }
 
     fn docs_raw(&mut self, docs: &str) {
@@ -928,7 +1093,7 @@ impl<'a> TsInterface<'a> {
         }
     }
 
-    fn types(&mut self, iface_id: InterfaceId) {
+    fn types(&mut self, iface_id: InterfaceId) -> Result<()> {
         let iface = &self.resolve.interfaces[iface_id];
 
         for (name, id) in iface.types.iter() {
@@ -956,10 +1121,14 @@ impl<'a> TsInterface<'a> {
                         is_export: self.resolve.exports_interface(iface_id),
                     },
                 ),
-                TypeDefKind::Handle(_) => todo!("types for handle"),
+                // Handles are represented by the class of the resource they refer to
+                TypeDefKind::Handle(Handle::Own(resource) | Handle::Borrow(resource)) => {
+                    self.type_alias(*id, name, &Type::Id(*resource), Some(iface_id), &ty.docs)
+                }
                 TypeDefKind::Map(key, value) => self.type_map(*id, name, key, value, &ty.docs),
             }
         }
+        Ok(())
     }
 
     fn print_ty(&mut self, ty: &Type) {
@@ -976,8 +1145,10 @@ impl<'a> TsInterface<'a> {
             Type::U64 | Type::S64 => self.src.push_str("bigint"),
             Type::Char => self.src.push_str("string"),
             Type::String => self.src.push_str("string"),
-            // TODO: `error-context`s should probably be represented by a native always-provided type
-            Type::ErrorContext => self.src.push_str("number"),
+            Type::ErrorContext => {
+                self.needs_ty_error_context = true;
+                self.src.push_str("ErrorContext");
+            }
             Type::Id(id) => {
                 let ty = &self.resolve.types[*id];
                 if let Some(name) = &ty.name {
@@ -986,9 +1157,9 @@ impl<'a> TsInterface<'a> {
                 match &ty.kind {
                     TypeDefKind::Type(t) => self.print_ty(t),
                     TypeDefKind::Tuple(t) => self.print_tuple(t),
-                    TypeDefKind::Record(_) => panic!("anonymous record"),
-                    TypeDefKind::Flags(_) => panic!("anonymous flags"),
-                    TypeDefKind::Enum(_) => panic!("anonymous enum"),
+                    TypeDefKind::Record(_) => self.print_anonymous_ty("record"),
+                    TypeDefKind::Flags(_) => self.print_anonymous_ty("flags"),
+                    TypeDefKind::Enum(_) => self.print_anonymous_ty("enum"),
                     TypeDefKind::Option(t) => {
                         if maybe_null(self.resolve, t) {
                             self.needs_ty_option = true;
@@ -1008,7 +1179,7 @@ impl<'a> TsInterface<'a> {
                         self.print_optional_ty(r.err.as_ref());
                         self.src.push_str(">");
                     }
-                    TypeDefKind::Variant(_) => panic!("[print_ty()] anonymous variant"),
+                    TypeDefKind::Variant(_) => self.print_anonymous_ty("variant"),
                     TypeDefKind::List(v) => self.print_list_ty(v),
                     TypeDefKind::FixedLengthList(v, len) => self.print_fixed_size_list(v, len),
                     TypeDefKind::Future(maybe_ty) => {
@@ -1022,17 +1193,11 @@ impl<'a> TsInterface<'a> {
                         self.src.push_str(">");
                     }
                     TypeDefKind::Unknown => unreachable!("unexpectedly unknown type def"),
-                    TypeDefKind::Resource => todo!("[print_ty()] resource"),
-                    TypeDefKind::Handle(h) => {
-                        let ty = match h {
-                            Handle::Own(r) => r,
-                            Handle::Borrow(r) => r,
-                        };
-                        let ty = &self.resolve.types[*ty];
-                        if let Some(name) = &ty.name {
-                            return self.src.push_str(&name.to_upper_camel_case());
-                        }
-                        panic!("anonymous resource handle");
+                    // Resources are always named in WIT, so an anonymous one has no
+                    // class we could refer to
+                    TypeDefKind::Resource => self.src.push_str("unknown"),
+                    TypeDefKind::Handle(Handle::Own(resource) | Handle::Borrow(resource)) => {
+                        self.print_ty(&Type::Id(*resource))
                     }
                     TypeDefKind::Map(key, value) => self.print_map_ty(key, value),
                 }
@@ -1040,6 +1205,17 @@ impl<'a> TsInterface<'a> {
         }
     }
 
+    /// Record that an anonymous type of the given kind (which has no name to refer to)
+    /// was encountered, printing a placeholder in its place
+    fn print_anonymous_ty(&mut self, kind: &str) {
+        self.error
+            .get_or_insert_with(|| TranspileError::TypeGeneration {
+                message: format!("anonymous {kind} types are not supported"),
+                item: None,
+            });
+        self.src.push_str("unknown");
+    }
+
     fn print_optional_ty(&mut self, ty: Option<&Type>) {
         match ty {
             Some(ty) => self.print_ty(ty),
@@ -1108,7 +1284,7 @@ impl<'a> TsInterface<'a> {
                 .or_insert_with(|| {
                     (
                         meta.clone(),
-                        TsInterface::new(self.resolve, false, self.is_guest),
+                        TsInterface::new(self.resolve, self.type_reprs, false, self.is_guest),
                     )
                 })
                 .1
@@ -1123,6 +1299,7 @@ impl<'a> TsInterface<'a> {
         default: bool,
         declaration: bool,
         requires_async_porcelain: bool,
+        explicit_result: bool,
         meta: &GeneratedTypeMeta,
     ) {
         let iface = self.iface_for(func, meta);
@@ -1217,7 +1394,10 @@ impl<'a> TsInterface<'a> {
             iface.src.push_str("Promise<");
         }
 
-        if let Some((ok_ty, _)) = get_thrown_type(iface.resolve, func.result) {
+        // Unless results are explicit, errors are thrown and only the ok type is returned
+        if let Some((ok_ty, _)) =
+            get_thrown_type(iface.resolve, func.result).filter(|_| !explicit_result)
+        {
             iface.print_optional_ty(ok_ty);
         } else {
             match func.result {
@@ -1246,6 +1426,14 @@ impl<'a> TsInterface<'a> {
         if needs_ty_result {
             self.src.push_str(TS_RESULT_TYPE_ALIAS);
         }
+        if self.needs_ty_error_context
+            || self
+                .resources
+                .iter()
+                .any(|(_, r)| r.1.needs_ty_error_context)
+        {
+            self.src.push_str(TS_ERROR_CONTEXT_TYPE);
+        }
     }
 
     fn type_record(&mut self, _id: TypeId, name: &str, record: &Record, docs: &Docs) {
@@ -1277,8 +1465,21 @@ impl<'a> TsInterface<'a> {
         self.src.push_str(";\n");
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
         self.docs(docs);
+        if self.type_reprs.get(&id) == Some(&TypeRepr::BitmaskFlags) {
+            self.src.push_str(&format!(
+                "export const enum {} {{\n",
+                name.to_upper_camel_case()
+            ));
+            for (i, flag) in flags.flags.iter().enumerate() {
+                self.docs(&flag.docs);
+                let name = flag.name.to_upper_camel_case();
+                self.src.push_str(&format!("{name} = {},\n", 1u32 << i));
+            }
+            self.src.push_str("}\n");
+            return;
+        }
         self.src.push_str(&format!(
             "export interface {} {{\n",
             name.to_upper_camel_case()
@@ -1291,8 +1492,12 @@ impl<'a> TsInterface<'a> {
         self.src.push_str("}\n");
     }
 
-    fn type_variant(&mut self, _id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+    fn type_variant(&mut self, id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
         self.docs(docs);
+        if let Some(TypeRepr::ClassVariant { .. }) = self.type_reprs.get(&id) {
+            self.type_class_variant(name, variant);
+            return;
+        }
         self.src
             .push_str(&format!("export type {} = ", name.to_upper_camel_case()));
         for (i, case) in variant.cases.iter().enumerate() {
@@ -1321,6 +1526,44 @@ impl<'a> TsInterface<'a> {
         }
     }
 
+    /// Variants represented as classes are an abstract base class, with a
+    /// subclass per case that is discriminated with `instanceof`
+    fn type_class_variant(&mut self, name: &str, variant: &Variant) {
+        let name = name.to_upper_camel_case();
+        let tags = variant
+            .cases
+            .iter()
+            .map(|case| format!("'{}'", case.name))
+            .collect::<Vec<_>>();
+        let tags = if tags.is_empty() {
+            "never".to_string()
+        } else {
+            tags.join(" | ")
+        };
+        uwriteln!(self.src, "export abstract class {name} {{");
+        uwriteln!(self.src, "readonly tag: {tags};");
+        self.src.push_str("}\n");
+        uwriteln!(self.src, "export namespace {name} {{");
+        for case in variant.cases.iter() {
+            self.docs(&case.docs);
+            let case_class = case.name.to_upper_camel_case();
+            uwriteln!(self.src, "export class {case_class} extends {name} {{");
+            if let Some(ty) = case.ty {
+                self.src.push_str("constructor(val: ");
+                self.print_ty(&ty);
+                self.src.push_str(");\n");
+            }
+            uwriteln!(self.src, "readonly tag: '{}';", case.name);
+            if let Some(ty) = case.ty {
+                self.src.push_str("val: ");
+                self.print_ty(&ty);
+                self.src.push_str(";\n");
+            }
+            self.src.push_str("}\n");
+        }
+        self.src.push_str("}\n");
+    }
+
     fn type_option(&mut self, _id: TypeId, name: &str, payload: &Type, docs: &Docs) {
         self.docs(docs);
         let name = name.to_upper_camel_case();
@@ -1348,7 +1591,22 @@ impl<'a> TsInterface<'a> {
         self.src.push_str(">;\n");
     }
 
-    fn type_enum(&mut self, _id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        if self.type_reprs.get(&id) == Some(&TypeRepr::NumericEnum) {
+            self.docs(docs);
+            self.src.push_str(&format!(
+                "export const enum {} {{\n",
+                name.to_upper_camel_case()
+            ));
+            for (i, case) in enum_.cases.iter().enumerate() {
+                self.docs(&case.docs);
+                let name = case.name.to_upper_camel_case();
+                self.src.push_str(&format!("{name} = {i},\n"));
+            }
+            self.src.push_str("}\n");
+            return;
+        }
+
         // The complete documentation for this enum, including documentation for variants.
         let mut complete_docs = String::new();
 
@@ -1385,7 +1643,7 @@ impl<'a> TsInterface<'a> {
 
     fn type_alias(
         &mut self,
-        id: TypeId,
+        _id: TypeId,
         name: &str,
         ty: &Type,
         parent_id: Option<InterfaceId>,
@@ -1415,7 +1673,10 @@ impl<'a> TsInterface<'a> {
         let type_name = name.to_upper_camel_case();
         match owner_not_parent {
             Some(owned_interface_id) => {
-                let orig_id = dealias(self.resolve, id);
+                let Type::Id(orig_id) = ty else {
+                    unreachable!("only type definitions have an owner")
+                };
+                let orig_id = dealias(self.resolve, *orig_id);
                 let orig_name = self.resolve.types[orig_id]
                     .name
                     .as_ref()
@@ -1489,9 +1750,12 @@ impl<'a> TsInterface<'a> {
 
     fn type_resource(&mut self, _id: TypeId, ty: &TypeDef, meta: GeneratedTypeMeta) {
         let resource = ty.name.clone().unwrap();
-        self.resources
-            .entry(resource)
-            .or_insert_with(|| (meta, TsInterface::new(self.resolve, false, self.is_guest)));
+        self.resources.entry(resource).or_insert_with(|| {
+            (
+                meta,
+                TsInterface::new(self.resolve, self.type_reprs, false, self.is_guest),
+            )
+        });
     }
 }
 
@@ -1530,3 +1794,219 @@ impl ResolveExt for Resolve {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_types;
+    use crate::transpile_bindgen::TypeRepresentations;
+
+    const WIT: &str = r#"
+        package test:world-types;
+
+        interface types {
+            resource blob {
+                constructor(data: list<u8>);
+            }
+        }
+
+        world world-types {
+            use types.{blob};
+
+            record point {
+                x: u32,
+                y: u32,
+            }
+            resource counter {
+                get: func() -> u32;
+            }
+            type owned-counter = own<counter>;
+            type local-blob = blob;
+        }
+    "#;
+
+    /// WIT has no syntax for exporting types from a world, so types that are imported by
+    /// the world are moved over to its exports instead
+    fn world_types(instantiation_mode: Option<InstantiationMode>) -> String {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str("test.wit", WIT).unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+        for name in ["point", "counter", "owned-counter", "local-blob"] {
+            let key = WorldKey::Name(name.to_string());
+            let item = resolve.worlds[world].imports.shift_remove(&key).unwrap();
+            resolve.worlds[world].exports.insert(key, item);
+        }
+
+        let opts = TranspileOpts::builder()
+            .name("world-types".to_string())
+            .maybe_instantiation_mode(instantiation_mode)
+            .build();
+        let files = generate_types("world-types", resolve, world, opts).unwrap();
+        let (_, src) = files
+            .into_iter()
+            .find(|(name, _)| name == "world-types.d.ts")
+            .unwrap();
+        String::from_utf8(src).unwrap()
+    }
+
+    #[test]
+    fn exported_world_types() {
+        let src = world_types(None);
+        assert!(src.contains("export interface Point {"));
+        assert!(src.contains("export class Counter implements Disposable {"));
+        assert!(src.contains("export type OwnedCounter = Counter;"));
+        assert!(src.contains("export type LocalBlob = Blob;"));
+    }
+
+    #[test]
+    fn exported_world_types_instantiation() {
+        let src = world_types(Some(InstantiationMode::Async));
+        assert!(src.contains("export class Counter implements Disposable {"));
+        assert!(src.contains("Counter: typeof Counter,"));
+        assert!(!src.contains("Point: typeof Point,"));
+    }
+
+    #[test]
+    fn explicit_results() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:results;
+
+                interface store {
+                    get: func(key: string) -> result<u32, string>;
+                    get-or-throw: func(key: string) -> result<u32, string>;
+                }
+
+                world results {
+                    import store;
+                    import lookup: func() -> result<u32>;
+                    export checked: func() -> result<u32, string>;
+                    export unchecked: func() -> result<u32, string>;
+                }
+            "#,
+            )
+            .unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+
+        let opts = TranspileOpts::builder()
+            .name("results".to_string())
+            .instantiation_mode(InstantiationMode::Async)
+            .result_mode(ResultMode::Explicit {
+                imports: vec!["test:results/store#get".into(), "lookup".into()],
+                exports: vec!["checked".into()],
+            })
+            .build();
+        let files: BTreeMap<_, _> = generate_types("results", resolve, world, opts)
+            .unwrap()
+            .into_iter()
+            .map(|(name, src)| (name, String::from_utf8(src).unwrap()))
+            .collect();
+
+        let world_src = &files["results.d.ts"];
+        assert!(world_src.contains("'default'(): Result<number, void>,"));
+        assert!(world_src.contains("checked(): Result<number, string>,"));
+        assert!(world_src.contains("unchecked(): number,"));
+        assert!(world_src.contains("export type Result<T, E> ="));
+
+        let store_src = &files["interfaces/test-results-store.d.ts"];
+        assert!(store_src.contains("export function get(key: string): Result<number, string>;"));
+        assert!(store_src.contains("export function getOrThrow(key: string): number;"));
+    }
+
+    #[test]
+    fn type_representations() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:reprs;
+
+                interface types {
+                    enum color { red, green, blue }
+                    enum size { small, large }
+                    flags perms { read, write, exec }
+                    variant shape { circle(u32), none }
+                }
+
+                world reprs {
+                    use types.{color, perms, shape};
+                    variant step { up(u32), stop }
+                    export paint: func(c: color, p: perms, s: shape, st: step);
+                }
+            "#,
+            )
+            .unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+
+        let opts = TranspileOpts::builder()
+            .name("reprs".to_string())
+            .instantiation_mode(InstantiationMode::Async)
+            .type_representations(TypeRepresentations {
+                numeric_enums: vec!["test:reprs/types#color".into()],
+                bitmask_flags: vec!["*".into()],
+                class_variants: vec!["shape".into(), "step".into()],
+            })
+            .build();
+        let files: BTreeMap<_, _> = generate_types("reprs", resolve, world, opts)
+            .unwrap()
+            .into_iter()
+            .map(|(name, src)| (name, String::from_utf8(src).unwrap()))
+            .collect();
+
+        let types_src = &files["interfaces/test-reprs-types.d.ts"];
+        assert!(types_src.contains("export const enum Color {\n  Red = 0,\n  Green = 1,"));
+        assert!(types_src.contains("export type Size = 'small' | 'large';"));
+        assert!(types_src.contains("Write = 2,\n  Exec = 4,"));
+        assert!(types_src.contains("export abstract class Shape {"));
+        assert!(types_src.contains("export class Circle extends Shape {"));
+        assert!(types_src.contains("constructor(val: number);"));
+
+        let world_src = &files["reprs.d.ts"];
+        assert!(world_src.contains("readonly tag: 'up' | 'stop';"));
+        assert!(world_src.contains("Step: typeof Step,"));
+    }
+
+    #[test]
+    fn anonymous_record_is_an_error() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package test:anon;
+
+                interface types {
+                    record point { x: u32, y: u32 }
+                    origin: func() -> point;
+                }
+
+                world anon {
+                    import types;
+                }
+            "#,
+            )
+            .unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+
+        // WIT has no syntax for anonymous records, so strip the name from a copy
+        let iface = resolve.packages[pkg].interfaces["types"];
+        let mut anon = resolve.types[resolve.interfaces[iface].types["point"]].clone();
+        anon.name = None;
+        anon.owner = TypeOwner::None;
+        let anon = resolve.types.alloc(anon);
+        resolve.interfaces[iface]
+            .functions
+            .get_mut("origin")
+            .unwrap()
+            .result = Some(Type::Id(anon));
+
+        let opts = TranspileOpts::builder().name("anon".to_string()).build();
+        let err = generate_types("anon", resolve, world, opts).unwrap_err();
+        assert!(matches!(err, TranspileError::TypeGeneration { .. }));
+        assert_eq!(err.message(), "anonymous record types are not supported");
+    }
+}
@@ -0,0 +1,6 @@
+//! Build script for test components that need extra linker configuration
+
+fn main() {
+    // `thread.new-indirect` requires the function table to be exported
+    println!("cargo::rustc-link-arg-bin=thread_builtins=--export-table");
+}
@@ -0,0 +1,85 @@
+//! Exercises the cooperative threading builtins (`thread.index`, `thread.new-indirect`,
+//! `thread.suspend`, `thread.yield-then-resume` and `thread.suspend-then-promote`).
+//!
+//! The builtins are not yet exposed by `wit-bindgen`, so they are imported directly.
+//! `thread.yield-then-resume` and `thread.suspend-then-promote` are encoded with the same
+//! opcodes as `thread.yield-to-suspended` and `thread.suspend-to`, which they are decoded as
+//! when transpiling. `thread.resume-later` and `thread.suspend-then-resume` are not used, as
+//! their opcodes are decoded as other builtins (`thread.suspend-to-suspended` and
+//! `thread.unsuspend`), which are left untested.
+//! Note that this component must be linked with an exported `__indirect_function_table`
+//! (see `build.rs`).
+
+use std::cell::RefCell;
+
+mod bindings {
+    use super::Component;
+    wit_bindgen::generate!({
+        world: "thread-builtins",
+    });
+    export!(Component);
+}
+
+#[link(wasm_import_module = "$root")]
+unsafe extern "C" {
+    #[link_name = "[thread-index]"]
+    fn thread_index() -> u32;
+    #[link_name = "[thread-new-indirect-v0]"]
+    fn thread_new_indirect(start_fn_idx: u32, arg: u32) -> u32;
+    #[link_name = "[thread-suspend]"]
+    fn thread_suspend() -> u32;
+    #[link_name = "[thread-yield-then-resume]"]
+    fn thread_yield_then_resume(thread_idx: u32) -> u32;
+    #[link_name = "[thread-suspend-then-promote]"]
+    fn thread_suspend_then_promote(thread_idx: u32) -> u32;
+}
+
+thread_local! {
+    static LOG: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+    static THREADS: RefCell<(u32, u32)> = const { RefCell::new((0, 0)) };
+}
+
+fn log(v: u32) {
+    LOG.with(|l| l.borrow_mut().push(v));
+}
+
+extern "C" fn worker(arg: u32) {
+    let (main_idx, worker_idx) = THREADS.with(|t| *t.borrow());
+    log(arg);
+    log((unsafe { thread_index() } == worker_idx && worker_idx != main_idx) as u32);
+
+    // Wait for the main thread to switch back to this thread
+    unsafe { thread_suspend() };
+    log(arg + 1);
+
+    // Hand control back to the main thread, and exit once it has run
+    unsafe { thread_yield_then_resume(main_idx) };
+}
+
+struct Component;
+
+impl bindings::Guest for Component {
+    async fn run_threads() -> Vec<u32> {
+        LOG.with(|l| l.borrow_mut().clear());
+
+        let main_idx = unsafe { thread_index() };
+        log(1);
+
+        let start_fn = worker as extern "C" fn(u32);
+        let worker_idx = unsafe { thread_new_indirect(start_fn as usize as u32, 7) };
+        THREADS.with(|t| *t.borrow_mut() = (main_idx, worker_idx));
+
+        // Run the worker until it suspends itself, then continue
+        unsafe { thread_yield_then_resume(worker_idx) };
+        log(2);
+
+        // Resume the suspended worker, which will resume this thread in turn
+        unsafe { thread_suspend_then_promote(worker_idx) };
+        log(3);
+
+        LOG.with(|l| l.borrow().clone())
+    }
+}
+
+// Stub only to ensure this works as a binary
+fn main() {}
@@ -6,7 +6,12 @@ import { platform, argv0 } from 'node:process';
 import * as nodeUtils from 'node:util';
 
 import type { TranspilationOptions } from './transpile.js';
-import type { AsyncMode as WITAsyncMode } from '../vendor/js-component-bindgen-component.js';
+import type {
+    AsyncDeterminism as WITAsyncDeterminism,
+    AsyncMode as WITAsyncMode,
+    ResultMode as WITResultMode,
+    TypeRepresentations as WITTypeRepresentations,
+} from '../vendor/js-component-bindgen-component.js';
 
 /** Detect a windows environment */
 export const isWindows = platform === 'win32';
@@ -213,7 +218,7 @@ export function extractWITAsyncModeFromOpts(opts: AsyncOptionsLike): WITAsyncMod
         return opts.asyncMode;
     }
     return {
-        tag: 'jspi',
+        tag: opts.asyncMode === 'asyncify' ? 'asyncify' : 'jspi',
         val: {
             imports: opts.asyncImports || [],
             exports: opts.asyncExports || [],
@@ -221,6 +226,62 @@ export function extractWITAsyncModeFromOpts(opts: AsyncOptionsLike): WITAsyncMod
     };
 }
 
+/** Extract a WIT variant for async determinism from a given set of transpilation options */
+export function extractWITAsyncDeterminismFromOpts(
+    opts: Pick<TranspilationOptions, 'asyncDeterminism'>,
+): WITAsyncDeterminism | undefined {
+    const determinism = opts.asyncDeterminism;
+    if (determinism === undefined) {
+        return undefined;
+    }
+    if (determinism === 'random' || determinism === 'deterministic') {
+        return { tag: determinism };
+    }
+    if (typeof determinism === 'object' && Number.isInteger(determinism.seed)) {
+        return { tag: 'seeded', val: determinism.seed >>> 0 };
+    }
+    throw new TypeError(`invalid async determinism [${JSON.stringify(determinism)}]`);
+}
+
+interface ResultOptionsLike {
+    resultImports?: string[];
+    resultExports?: string[];
+}
+
+/** Extract a WIT enum for result mode from a given set of result options */
+export function extractWITResultModeFromOpts(opts: ResultOptionsLike): WITResultMode | undefined {
+    if (!opts.resultImports?.length && !opts.resultExports?.length) {
+        return undefined;
+    }
+    return {
+        tag: 'explicit',
+        val: {
+            imports: opts.resultImports || [],
+            exports: opts.resultExports || [],
+        },
+    };
+}
+
+interface TypeRepresentationOptionsLike {
+    numericEnums?: string[];
+    bitmaskFlags?: string[];
+    classVariants?: string[];
+}
+
+/** Extract the WIT type representations from a given set of type representation options */
+export function extractWITTypeRepresentationsFromOpts(
+    opts: TypeRepresentationOptionsLike,
+): WITTypeRepresentations | undefined {
+    if (!opts.numericEnums?.length && !opts.bitmaskFlags?.length && !opts.classVariants?.length) {
+        return undefined;
+    }
+    return {
+        numericEnums: opts.numericEnums || [],
+        bitmaskFlags: opts.bitmaskFlags || [],
+        classVariants: opts.classVariants || [],
+    };
+}
+
 /** Options for `writeFiles()` utility function */
 interface WriteFileOpts {
     /** Change (prepend) the base directory before writing each file */
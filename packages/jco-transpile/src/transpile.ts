@@ -8,21 +8,30 @@ import { $init as $initBindgenComponent, generate } from '../vendor/js-component
 import type {
     AsyncMode as WITAsyncMode,
     InstantiationMode as WITInstantiationMode,
+    TranspileError as WITTranspileError,
 } from '../vendor/js-component-bindgen-component.js';
 
 import { $init as $initWasmToolsComponent, tools } from '../vendor/wasm-tools.js';
 const { componentEmbed, componentNew } = tools;
 
 import { runOptimizeComponent, type OptimizeOptions } from './opt.js';
-import { isWindows } from './common.js';
+import {
+    extractWITAsyncDeterminismFromOpts,
+    extractWITResultModeFromOpts,
+    extractWITTypeRepresentationsFromOpts,
+    isWindows,
+} from './common.js';
 import { ASYNC_WASI_IMPORTS, ASYNC_WASI_EXPORTS } from './constants.js';
 import { generateASMJS } from './asm.js';
 
 /** Instantiation mode for a transpiled component */
 export type InstantiationMode = 'async' | 'sync';
 
-/** Async mode for the component (i.e. whether to use JSPI) */
-export type AsyncMode = 'sync' | 'jspi';
+/** Async mode for the component (i.e. whether to use JSPI, or asyncify where JSPI is unavailable) */
+export type AsyncMode = 'sync' | 'jspi' | 'asyncify';
+
+/** Module format of the generated JS */
+export type ModuleFormat = 'esm' | 'commonjs';
 
 /** Options for transpilation */
 export interface TranspilationOptions {
@@ -59,7 +68,10 @@ export interface TranspilationOptions {
     /**
      * Imports that should be treated as asynchronous *host side* functions
      *
-     * This option works in conjunction with `asyncMode` set to `'jspi'`
+     * Entries may use `*` wildcards (e.g. `wasi:filesystem/*#*`), or name a whole
+     * interface (e.g. `wasi:io/poll`)
+     *
+     * This option works in conjunction with `asyncMode` set to `'jspi'` or `'asyncify'`
      */
     asyncImports?: string[];
 
@@ -67,10 +79,50 @@ export interface TranspilationOptions {
      * Exports that should be treated as asynchronous Wasm functions to the Host that
      * calls them.
      *
-     * This option works in conjunction with `asyncMode` set to `'jspi'`
+     * This option works in conjunction with `asyncMode` set to `'jspi'` or `'asyncify'`
      */
     asyncExports?: string[];
 
+    /**
+     * How scheduling decisions of async tasks are made
+     *
+     * By default (`'random'`) decisions like which of several ready tasks continues
+     * first are made randomly. With `'deterministic'` the same decisions are always
+     * made, while `{ seed }` makes random decisions with a pseudo-random generator, so
+     * that a given seed reproduces the same interleaving of tasks.
+     *
+     * The seed can also be changed at runtime with `_util.seedAsyncScheduler(seed)`.
+     */
+    asyncDeterminism?: 'random' | 'deterministic' | { seed: number };
+
+    /**
+     * Record calls to host imports into a journal, or replay them from one
+     *
+     * When recording, the lifted arguments and results of every call to a host import
+     * (including the eventual results of async imports) are available as JSON-compatible
+     * entries from `_util.importJournal()`. When replaying, entries provided with
+     * `_util.loadImportJournal(entries)` are returned in place of calling the host.
+     */
+    importJournal?: 'record' | 'replay';
+
+    /**
+     * Record async task, subtask, waitable, backpressure and stream events
+     *
+     * Recorded events are available in the Chrome trace event format from
+     * `_util.asyncTraceEvents()`, which can be saved as JSON and loaded into
+     * tools like Perfetto, and can be discarded with `_util.clearAsyncTrace()`.
+     */
+    asyncTrace?: boolean;
+
+    /**
+     * Milliseconds for which every async task must stay blocked (with nothing pending
+     * on the host) before the blocked tasks fail with a deadlock error
+     *
+     * The error lists each blocked task, its waitables and the stream/future ends
+     * they wait on. Defaults to 1000, and a value of zero disables deadlock detection.
+     */
+    asyncDeadlockTimeout?: number;
+
     /**
      * WASI imports that should be marked as asynchronous
      *
@@ -85,10 +137,60 @@ export interface TranspilationOptions {
      */
     asyncWasiExports?: string[];
 
+    /**
+     * Imports that return `{ tag: 'ok' | 'err', val }` objects for `result<T, E>`
+     * values, rather than throwing to produce an error
+     *
+     * Entries use the same patterns as `asyncImports`
+     */
+    resultImports?: string[];
+
+    /**
+     * Exports that return `{ tag: 'ok' | 'err', val }` objects for `result<T, E>`
+     * values, rather than throwing a `ComponentError`
+     *
+     * Entries use the same patterns as `asyncExports`
+     */
+    resultExports?: string[];
+
+    /**
+     * Enums that are represented as numbers (the index of the case), rather than
+     * strings, and typed as a TypeScript `const enum`
+     *
+     * Entries are `<interface>#<type>` patterns (e.g. `my:pkg/types#color`), where
+     * a bare type name matches types of that name in any interface, and `*` matches all enums
+     */
+    numericEnums?: string[];
+
+    /**
+     * Flags that are represented as a numeric bitmask (bit `i` being the `i`th flag),
+     * rather than a record of booleans
+     *
+     * Entries use the same patterns as `numericEnums`
+     */
+    bitmaskFlags?: string[];
+
+    /**
+     * Variants that are represented as instances of a class per case, to be
+     * discriminated with `instanceof`, rather than `{ tag, val }` objects
+     *
+     * Classes of variants from imported interfaces must be provided by the host
+     * alongside the interface functions, while all other classes are exported by
+     * the bindings.
+     *
+     * Entries use the same patterns as `numericEnums`
+     */
+    classVariants?: string[];
+
     /** Whether the valid lifting optimization should be performed */
     validLiftingOptimization?: boolean;
 
-    /** Enable/disable tracing for debug purposes */
+    /**
+     * Enable/disable tracing of function entry and exits
+     *
+     * Events are logged to the console, unless hooks are registered at runtime with
+     * `_util.setTracer({ enter, exit, error })`.
+     */
     tracing?: boolean;
 
     /** Enable/disable NodeJS compat */
@@ -128,6 +230,43 @@ export interface TranspilationOptions {
     /** Whether bindgen may emit Wasm exception references (`exnref`) */
     bindgenEnableWasmExnref?: boolean;
 
+    /** Whether to emit a source map (`{name}.js.map`) for the generated bindings */
+    sourceMap?: boolean;
+
+    /**
+     * Whether to import intrinsics from a shared runtime module (`jco-runtime-{version}.js`),
+     * emitted alongside the component, rather than inlining them
+     *
+     * Components transpiled with the same version of jco and compatible options share the
+     * same runtime file, and thus a single async scheduler and set of global tables.
+     */
+    sharedRuntime?: boolean;
+
+    /**
+     * Module format of the generated JS (default: `'esm'`)
+     *
+     * CommonJS output uses `require()`/`module.exports` and implies `tlaCompat`.
+     * It is emitted as `.cjs` files (with `.d.cts` types), so that it loads as CommonJS
+     * even inside packages with `"type": "module"`.
+     */
+    moduleFormat?: ModuleFormat;
+
+    /**
+     * Whether to load core Wasm modules through source phase imports
+     * (`import source module0 from './component.core.wasm'`), rather than fetching or inlining them
+     *
+     * This allows bundlers to statically discover the Wasm assets. Only supported for ES module output.
+     */
+    sourcePhaseImports?: boolean;
+
+    /**
+     * Whether to compile core Wasm modules to JS modules (`component.core.js`), so that
+     * the component can run on JS engines without WebAssembly support
+     *
+     * Exports using `i64` are legalized as for `js` output. Async components are not supported.
+     */
+    compileCoreToJs?: boolean;
+
     /** Whether to enable WebIDL imports */
     experimentalIdlImports?: boolean;
 
@@ -147,7 +286,7 @@ export interface TranspilationOptions {
 interface TranspilationResult {
     files: Record<string, Uint8Array>;
     imports: string[];
-    exports: [string, 'function' | 'instance'][];
+    exports: [string, 'function' | 'instance' | 'module'][];
 }
 
 const SUPPORTED_P3_VERSIONS = ['0.3.0-rc-2026-03-15', '0.3.0'];
@@ -278,11 +417,11 @@ export async function transpileBytes(
     }
 
     // Determine the async mode that should be used
-    // (i.e. determining whether JSPI should be used)
+    // (i.e. determining whether JSPI or asyncify should be used)
     let asyncMode: WITAsyncMode | undefined = undefined;
-    if (opts.asyncMode === 'jspi') {
+    if (opts.asyncMode === 'jspi' || opts.asyncMode === 'asyncify') {
         asyncMode = {
-            tag: 'jspi',
+            tag: opts.asyncMode,
             val: {
                 imports: opts.asyncImports || [],
                 exports: opts.asyncExports || [],
@@ -290,12 +429,23 @@ export async function transpileBytes(
         };
     }
 
+    // Source maps describe the generated bindings, they would be invalidated by further processing
+    if (opts.sourceMap && (opts.minify || opts.js)) {
+        throw new Error('source maps are not supported together with minification or JS (asm.js) output');
+    }
+
     // Build the options for calling into the js-component-bindgen's `generate()` export
     const generateOpts = {
         name: opts.name ?? 'component',
         map: Object.entries(opts.map ?? {}),
         instantiation,
         asyncMode,
+        asyncDeterminism: extractWITAsyncDeterminismFromOpts(opts),
+        importJournal: opts.importJournal,
+        asyncTrace: opts.asyncTrace ?? false,
+        asyncDeadlockTimeout: opts.asyncDeadlockTimeout,
+        resultMode: extractWITResultModeFromOpts(opts),
+        typeRepresentations: extractWITTypeRepresentationsFromOpts(opts),
         importBindings: opts.importBindings ? { tag: opts.importBindings } : undefined,
         validLiftingOptimization: opts.validLiftingOptimization ?? false,
         tracing: opts.tracing ?? false,
@@ -309,10 +459,20 @@ export async function transpileBytes(
         strict: opts.strict === true,
         idlImports: opts.experimentalIdlImports === true,
         asmjs: opts.js === true,
+        sourceMap: opts.sourceMap === true,
+        sharedRuntime: opts.sharedRuntime === true,
+        moduleFormat: opts.moduleFormat ? { tag: opts.moduleFormat } : undefined,
+        sourcePhaseImports: opts.sourcePhaseImports === true,
+        compileCoreToJs: opts.compileCoreToJs === true,
     };
 
     // Generate the component
-    const generated = generate(component, generateOpts);
+    let generated: ReturnType<typeof generate>;
+    try {
+        generated = generate(component, generateOpts);
+    } catch (err) {
+        throw formatTranspileError(err);
+    }
     const imports = generated.imports;
     const exports = generated.exports;
 
@@ -323,12 +483,13 @@ export async function transpileBytes(
     }
     const files: [string, Uint8Array][] = generated.files.map(([name, source]) => [`${outDir}${name}`, source]);
 
-    // Find JS files
+    // Find the generated JS module (other JS files, like the shared runtime, may be present)
+    const jsFileName = `${outDir}${generateOpts.name}.${opts.moduleFormat === 'commonjs' ? 'cjs' : 'js'}`;
     const jsFile = files.find(([name]) => {
         if (typeof name !== 'string') {
             throw new Error('unexpected name value');
         }
-        return name.endsWith('.js');
+        return name === jsFileName;
     });
     if (!jsFile) {
         throw new Error('failed to find generated JS module');
@@ -369,3 +530,21 @@ export async function transpileBytes(
 
     return { files: Object.fromEntries(files), imports, exports };
 }
+
+/**
+ * Convert a structured transpile error thrown by js-component-bindgen into a readable `Error`
+ *
+ * The original structured error is preserved as the `cause` of the returned error.
+ *
+ * @param {unknown} err - error thrown from `generate()`
+ * @returns {unknown} a formatted `Error`, or `err` itself if it is not a transpile error
+ */
+function formatTranspileError(err: unknown): unknown {
+    const payload = (err as { payload?: WITTranspileError })?.payload;
+    if (!payload || typeof payload !== 'object' || !('tag' in payload)) {
+        return err;
+    }
+    const { message, item } = payload.val;
+    const at = item ? ` (at [${item}])` : '';
+    return new Error(`transpile failed (${payload.tag}): ${message}${at}`, { cause: payload });
+}
@@ -9,7 +9,14 @@ import {
 import type { InstantiationMode as WITInstantiationMode, WitPath } from '../vendor/js-component-bindgen-component.js';
 
 import { TranspilationOptions } from './transpile.js';
-import { extractWITAsyncModeFromOpts, type FileBytes, isWindows } from './common.js';
+import {
+    extractWITAsyncModeFromOpts,
+    extractWITResultModeFromOpts,
+    extractWITTypeRepresentationsFromOpts,
+    type FileBytes,
+    isWindows,
+    readFile,
+} from './common.js';
 
 import { ASYNC_WASI_IMPORTS, ASYNC_WASI_EXPORTS } from './constants.js';
 
@@ -36,6 +43,21 @@ interface TypegenOptions {
     /** @see `TranspilationOptions#asyncExports` */
     asyncExports?: TranspilationOptions['asyncExports'];
 
+    /** @see `TranspilationOptions#resultImports` */
+    resultImports?: TranspilationOptions['resultImports'];
+
+    /** @see `TranspilationOptions#resultExports` */
+    resultExports?: TranspilationOptions['resultExports'];
+
+    /** @see `TranspilationOptions#numericEnums` */
+    numericEnums?: TranspilationOptions['numericEnums'];
+
+    /** @see `TranspilationOptions#bitmaskFlags` */
+    bitmaskFlags?: TranspilationOptions['bitmaskFlags'];
+
+    /** @see `TranspilationOptions#classVariants` */
+    classVariants?: TranspilationOptions['classVariants'];
+
     /** Output directory */
     outDir?: string;
 
@@ -61,7 +83,8 @@ interface TypegenOptions {
 /**
  * Generate host types for a given WIT world
  *
- * @param witPath - path to the file/directory containing the WIT world
+ * @param witPath - path to the file/directory containing the WIT world, or to a `.wasm`
+ *   binary-encoded WIT package or component
  * @param opts - options for controlling type generation
  * @returns A `Promise` that resolves to written file data
  */
@@ -152,15 +175,25 @@ export async function runTypesComponent(witPath: string, opts: TypegenOptions) {
 
     const absWitPath = resolve(witPath);
 
+    // Binary WIT packages and components are read up front and decoded by the bindgen component
+    let wit: WitPath;
+    if (extname(absWitPath) === '.wasm') {
+        wit = { tag: 'binary', val: (await readFile(absWitPath)) as Uint8Array } as WitPath;
+    } else {
+        wit = { tag: 'path', val: (isWindows ? '//?/' : '') + absWitPath } as WitPath;
+    }
+
     const generateOpts = {
         ...opts,
-        wit: { tag: 'path', val: (isWindows ? '//?/' : '') + absWitPath } as WitPath,
+        wit,
         instantiation,
         tlaCompat: opts.tlaCompat ?? false,
         world: opts.worldName,
         features,
         guest: opts.guest ?? false,
         asyncMode,
+        resultMode: extractWITResultModeFromOpts(opts),
+        typeRepresentations: extractWITTypeRepresentationsFromOpts(opts),
     };
     const types = generateTypes(name, generateOpts).map(([name, file]) => [`${outDir}${name}`, file]);
 
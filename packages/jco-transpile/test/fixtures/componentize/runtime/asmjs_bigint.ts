@@ -10,6 +10,9 @@ async function run() {
 
     strictEqual(wasm.add(1n, 2n), 3n);
     strictEqual(wasm.add(1n << 33n, -(1n << 42n)), -4389456576512n);
+    // The low word of the result has its top bit set, and must not be sign extended
+    strictEqual(wasm.add(0x7fffffffn, 1n), 0x80000000n);
+    strictEqual(wasm.add(1n << 32n, 0xffffffffn), 0x1ffffffffn);
 }
 
 await run();
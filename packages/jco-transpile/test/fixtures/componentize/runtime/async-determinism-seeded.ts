@@ -0,0 +1,47 @@
+// Flags: --tla-compat --async-determinism seed=42
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/async-determinism-seeded/async-determinism-seeded.js';
+
+/** Run the component, returning the position (in join order) of each waitable, in delivery order */
+function deliveryOrder(): number[] {
+    const waitables = [...wasm.run()];
+    const joined = waitables.slice(0, 4);
+    return waitables.slice(4).map((waitable) => joined.indexOf(waitable));
+}
+
+/** Run the component a few times, returning the delivery order of every run */
+function deliveryOrders(): number[][] {
+    return [deliveryOrder(), deliveryOrder(), deliveryOrder()];
+}
+
+async function run() {
+    await wasm.$init;
+
+    // Events are delivered in an order picked by the seeded scheduler...
+    const seeded = deliveryOrders();
+    for (const order of seeded) {
+        assert.deepStrictEqual([...order].sort(), [0, 1, 2, 3]);
+    }
+    assert.ok(seeded.some((order) => order.join() !== '0,1,2,3'));
+
+    // ...which is reproduced by reseeding with the seed given at transpile time
+    wasm._util.seedAsyncScheduler(42);
+    assert.deepStrictEqual(deliveryOrders(), seeded);
+    wasm._util.seedAsyncScheduler(42);
+    assert.deepStrictEqual(deliveryOrders(), seeded);
+
+    // Other seeds reproduce their own orders
+    wasm._util.seedAsyncScheduler(7);
+    const reseeded = deliveryOrders();
+    assert.notDeepStrictEqual(reseeded, seeded);
+    wasm._util.seedAsyncScheduler(7);
+    assert.deepStrictEqual(deliveryOrders(), reseeded);
+
+    assert.throws(() => wasm._util.seedAsyncScheduler(1.5), TypeError);
+}
+
+// TLA cycle avoidance
+setTimeout(run);
@@ -0,0 +1,39 @@
+// Flags: --tla-compat --map test:asyncify/host=../asyncify.js --async-mode asyncify --async-imports test:asyncify/host#lookup --async-exports run
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/asyncify/asyncify.js';
+
+const logged: number[] = [];
+
+// Async import, suspending the core module until the promise settles
+export function lookup(x: number): Promise<number> {
+    return new Promise((resolve) => setTimeout(() => resolve(x * 2 + 1), 1));
+}
+
+// Sync import
+export function log(x: number) {
+    logged.push(x);
+}
+
+async function run() {
+    await wasm.$init;
+
+    // Locals and values on the stack are restored after each suspension, so the
+    // result is the sum of `lookup(i) + 100 * i + 10` for each `i` below `n`
+    const result = wasm.run(3);
+    assert.ok(result instanceof Promise);
+    assert.strictEqual(await result, 339);
+    assert.deepStrictEqual(logged, [0, 1, 2]);
+
+    assert.strictEqual(await wasm.run(0), 0);
+    assert.strictEqual(await wasm.run(5), 1075);
+
+    // Exports that are not async are called synchronously
+    assert.strictEqual(wasm.add(4, 5), 9);
+    assert.strictEqual(logged.at(-1), 4);
+}
+
+// TLA cycle avoidance
+setTimeout(run);
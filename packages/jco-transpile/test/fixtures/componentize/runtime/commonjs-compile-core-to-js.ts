@@ -0,0 +1,25 @@
+// Flags: --module-format commonjs --compile-core-to-js
+
+import * as assert from 'assert';
+import { createRequire } from 'node:module';
+
+/*
+
+Uses the component from the `commonjs` runtime test, with all core modules
+compiled to JS, which must be required from the same `.cjs` files they are written to.
+
+*/
+
+async function run() {
+    // Ensure nothing in the generated bindings relies on WebAssembly
+    delete (globalThis as any).WebAssembly;
+
+    const require = createRequire(import.meta.url);
+    const wasm = require('../js-test-components/commonjs-compile-core-to-js/commonjs-compile-core-to-js.cjs');
+    await wasm.$init;
+
+    assert.strictEqual(wasm.add(2, 3), 5);
+    assert.strictEqual(wasm.greet('world'), 'hello world');
+}
+
+await run();
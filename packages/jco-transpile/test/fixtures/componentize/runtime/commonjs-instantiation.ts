@@ -0,0 +1,18 @@
+// Flags: --module-format commonjs --instantiation
+
+import * as assert from 'assert';
+import { createRequire } from 'node:module';
+
+import * as helpers from './helpers.js';
+
+const require = createRequire(import.meta.url);
+const { instantiate } = require('../js-test-components/commonjs-instantiation/commonjs-instantiation.cjs');
+
+async function run() {
+    const wasm = await instantiate(helpers.loadWasm, {});
+
+    assert.strictEqual(wasm.add(40, 2), 42);
+    assert.strictEqual(wasm.greet('instantiation'), 'hello instantiation');
+}
+
+await run();
@@ -0,0 +1,20 @@
+// Flags: --module-format commonjs
+
+import * as assert from 'assert';
+import { createRequire } from 'node:module';
+
+// The bindings are emitted as `.cjs`, so they load as CommonJS inside this `"type": "module"` package
+const require = createRequire(import.meta.url);
+const wasm = require('../js-test-components/commonjs/commonjs.cjs');
+
+async function run() {
+    // CommonJS has no top-level await, so the bindings must be initialized first
+    assert.ok(wasm.$init instanceof Promise);
+    await wasm.$init;
+
+    assert.strictEqual(wasm.add(2, 3), 5);
+    assert.strictEqual(wasm.greet('world'), 'hello world');
+    assert.strictEqual(typeof wasm._util, 'object');
+}
+
+await run();
@@ -0,0 +1,71 @@
+// Flags: --compile-core-to-js --instantiation
+
+import * as helpers from './helpers.js';
+import { strictEqual } from 'node:assert';
+
+/*
+
+Uses the component from the `numbers` runtime test, with all core modules
+compiled to JS, so that the component runs without WebAssembly support.
+
+*/
+
+async function run() {
+    // Ensure nothing in the generated bindings relies on WebAssembly
+    delete (globalThis as any).WebAssembly;
+
+    // @ts-expect-error
+    const { instantiate } = await import('../js-test-components/compile-core-to-js/compile-core-to-js.js');
+
+    let scalar = 0;
+    const imports = {
+        roundtripU8: (x) => x,
+        roundtripS8: (x) => x,
+        roundtripU16: (x) => x,
+        roundtripS16: (x) => x,
+        roundtripU32: (x) => x,
+        roundtripS32: (x) => x,
+        roundtripU64: (x) => x,
+        roundtripS64: (x) => x,
+        roundtripF32: (x) => x,
+        roundtripF64: (x) => x,
+        roundtripChar: (x) => x,
+        setScalar(x) {
+            scalar = x;
+        },
+        getScalar() {
+            return scalar;
+        },
+    };
+
+    // Core modules are loaded from the compiled JS modules by default
+    const wasm = await instantiate(undefined, {
+        ...helpers.wasi,
+        'test:numbers/test': imports,
+    });
+
+    wasm.testImports();
+
+    strictEqual(wasm.test.roundtripU8((1 << 8) - 1), (1 << 8) - 1);
+    strictEqual(wasm.test.roundtripS8(-(1 << 7)), -(1 << 7));
+    strictEqual(wasm.test.roundtripU16((1 << 16) - 1), (1 << 16) - 1);
+    strictEqual(wasm.test.roundtripS16(-(1 << 15)), -(1 << 15));
+    strictEqual(wasm.test.roundtripU32(~0 >>> 0), ~0 >>> 0);
+    strictEqual(wasm.test.roundtripS32(1 << 31), 1 << 31);
+
+    // 64-bit integers are split into two 32-bit halves across the compiled core modules
+    strictEqual(wasm.test.roundtripU64((1n << 64n) - 1n), (1n << 64n) - 1n);
+    strictEqual(wasm.test.roundtripU64(0x80000000n), 0x80000000n);
+    strictEqual(wasm.test.roundtripS64((1n << 63n) - 1n), (1n << 63n) - 1n);
+    strictEqual(wasm.test.roundtripS64(-(1n << 63n)), -(1n << 63n));
+
+    strictEqual(wasm.test.roundtripF32(Infinity), Infinity);
+    strictEqual(wasm.test.roundtripF64(-Infinity), -Infinity);
+    strictEqual(Number.isNaN(wasm.test.roundtripF64(NaN)), true);
+    strictEqual(wasm.test.roundtripChar('🚩'), '🚩');
+
+    wasm.test.setScalar(4);
+    strictEqual(wasm.test.getScalar(), 4);
+}
+
+await run();
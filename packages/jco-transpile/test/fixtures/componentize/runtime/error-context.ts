@@ -0,0 +1,53 @@
+// Flags: --tla-compat --map report=../error-context.js
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/error-context/error-context.js';
+
+const reported: Error[] = [];
+
+export default function report(e: Error) {
+    reported.push(e);
+}
+
+async function run() {
+    await wasm.$init;
+
+    // Error contexts created by the component are surfaced as errors
+    const err = wasm.make('something went wrong');
+    assert.ok(err instanceof Error);
+    assert.strictEqual(err.name, 'ErrorContext');
+    assert.strictEqual(err.message, 'something went wrong');
+    assert.strictEqual(err.debugMessage(), 'something went wrong');
+    assert.strictEqual(typeof err.stack, 'string');
+    assert.strictEqual(wasm.message(err), 'something went wrong');
+
+    // The class error contexts are surfaced as is exported for hosts
+    assert.ok(err instanceof wasm._util.ErrorContext);
+    const created = new wasm._util.ErrorContext('created by host');
+    assert.strictEqual(wasm.message(created), 'created by host');
+    assert.strictEqual(wasm.echo(created).debugMessage(), 'created by host');
+
+    // The same error context is surfaced as the same value
+    assert.strictEqual(wasm.echo(err), err);
+
+    // Host errors are lowered into new error contexts, with the original error as their cause
+    const hostErr = new Error('host failure');
+    assert.strictEqual(wasm.message(hostErr), 'host failure');
+    const hostCtx = wasm.echo(hostErr);
+    assert.strictEqual(hostCtx.name, 'ErrorContext');
+    assert.strictEqual(hostCtx.debugMessage(), 'host failure');
+    assert.strictEqual(hostCtx.cause, hostErr);
+
+    // Error contexts can be passed to imports
+    wasm.forward(hostCtx);
+    assert.strictEqual(reported.length, 1);
+    assert.strictEqual(reported[0], hostCtx);
+
+    wasm.drop(err);
+    assert.throws(() => wasm.echo('not an error'), TypeError);
+}
+
+// Async cycle handling
+setTimeout(run);
@@ -0,0 +1,37 @@
+// Flags: --tla-compat --map local:results/store=../explicit-results.js --result-imports local:results/store#get --result-exports checked
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/explicit-results/explicit-results.js';
+
+// Imported with explicit results
+export function get(x: number) {
+    return x % 2 === 0 ? { tag: 'ok', val: x / 2 } : { tag: 'err', val: `odd: ${x}` };
+}
+
+// Imported with thrown errors
+export function getOrThrow(x: number) {
+    if (x % 2 !== 0) {
+        throw `odd: ${x}`;
+    }
+    return x / 2;
+}
+
+async function run() {
+    await wasm.$init;
+
+    // Exports with explicit results return result objects rather than throwing
+    assert.deepStrictEqual(wasm.checked(4), { tag: 'ok', val: 2 });
+    assert.deepStrictEqual(wasm.checked(3), { tag: 'err', val: 'odd: 3' });
+
+    // Other exports keep throwing on errors
+    assert.strictEqual(wasm.unchecked(4), 2);
+    assert.throws(
+        () => wasm.unchecked(3),
+        (err: any) => err.name !== 'TypeError' && err.payload === 'odd: 3',
+    );
+}
+
+// TLA cycle avoidance
+setTimeout(run);
@@ -0,0 +1,32 @@
+// Flags: --tla-compat
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/multi-memory/multi-memory.js';
+
+async function run() {
+    await wasm.$init;
+
+    // Active data segments of the second memory are written from JS
+    assert.strictEqual(wasm.activeData(), 0x04030201);
+
+    // Loads and stores of every width on the second memory
+    assert.strictEqual(wasm.load8S(), -1n);
+    assert.strictEqual(wasm.load32U(), 0xfcfdfeffn);
+    assert.strictEqual(wasm.store16(0x12348001n), -32767n);
+
+    // Bulk memory instructions, including copies between both memories
+    assert.strictEqual(wasm.fillCopy(), 0xabababab);
+    assert.strictEqual(wasm.copyFromPrimary(), 42);
+    assert.strictEqual(wasm.init(), 0x6f6c6c65);
+    assert.strictEqual(wasm.grow(), 102);
+    assert.strictEqual(wasm.grow(), 203);
+
+    // Tables and element segments are preserved
+    assert.strictEqual(wasm.indirect(0), 10);
+    assert.strictEqual(wasm.indirect(1), 20);
+}
+
+// Async cycle handling
+setTimeout(run);
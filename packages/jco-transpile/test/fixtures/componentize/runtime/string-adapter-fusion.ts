@@ -0,0 +1,50 @@
+// Flags: --tla-compat --map test=../string-adapter-fusion.js
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/string-adapter-fusion/string-adapter-fusion.js';
+
+let expected: any = null;
+
+export function echo(s: string) {
+    assert.strictEqual(s, expected);
+    return s;
+}
+
+async function run() {
+    await wasm.$init;
+
+    function test(s: string) {
+        expected = s;
+        const ret = wasm.result.echo(s);
+        expected = null;
+        assert.strictEqual(ret, s);
+    }
+
+    // Empty & ASCII-only strings
+    test('');
+    test('x');
+    test('hello world');
+
+    // Strings that fit in latin1, but need two bytes per character in utf8
+    test('é');
+    test('déjà vu, über ÿ');
+
+    // Strings that need utf16, including surrogate pairs
+    test('世界');
+    test('🚀 launch');
+
+    // Strings that start out as latin1 before needing utf16
+    test('café 世界');
+    test('latin1 first, 🚀 after');
+
+    // Longer strings of each kind
+    test('a'.repeat(1000));
+    test('é'.repeat(1000));
+    test('é'.repeat(1000) + '世');
+    test('世'.repeat(1000));
+}
+
+// Async cycle handling
+setTimeout(run);
@@ -0,0 +1,136 @@
+// Flags: --tla-compat --tracing --map test:tracing/host=../tracing.js
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/tracing/tracing.js';
+
+export function double(x: number): number {
+    return x * 2;
+}
+
+const hostError = new Error('host failure');
+
+export function fail(message: string) {
+    assert.strictEqual(message, hostError.message);
+    throw hostError;
+}
+
+const RUN = { iface: null, name: 'run', kind: 'export', params: ['x'] };
+const TRIP = { iface: null, name: 'trip', kind: 'export', params: [] };
+const DOUBLE = { iface: 'test:tracing/host', name: 'double', kind: 'import', params: ['x'] };
+const FAIL = { iface: 'test:tracing/host', name: 'fail', kind: 'import', params: ['message'] };
+
+/** Run a function, returning whatever it logged with `console.error` */
+function captureErrorLogs(f: () => void): string[] {
+    const logs: string[] = [];
+    const consoleError = console.error;
+    console.error = (...args: any[]) => logs.push(args.map(String).join(' '));
+    try {
+        f();
+    } finally {
+        console.error = consoleError;
+    }
+    return logs;
+}
+
+/** Check that an enter event and the exit (or error) event that ends its span are consistent */
+function assertSpan(enter: any, end: any) {
+    assert.strictEqual(end.fn, enter.fn);
+    assert.strictEqual(end.taskID, enter.taskID);
+    assert.strictEqual(end.componentIdx, enter.componentIdx);
+    assert.strictEqual(typeof enter.time, 'number');
+    assert.ok(end.time >= enter.time);
+    assert.strictEqual(end.duration, end.time - enter.time);
+}
+
+async function run() {
+    await wasm.$init;
+
+    const events: [string, any][] = [];
+    wasm._util.setTracer({
+        enter: (event: any) => events.push(['enter', event]),
+        exit: (event: any) => events.push(['exit', event]),
+        error: (event: any) => events.push(['error', event]),
+    });
+
+    // An export calling an import produces nested spans
+    assert.strictEqual(wasm.run(20), 41);
+    assert.deepStrictEqual(
+        events.map(([kind, { fn }]) => [kind, fn]),
+        [
+            ['enter', RUN],
+            ['enter', DOUBLE],
+            ['exit', DOUBLE],
+            ['exit', RUN],
+        ],
+    );
+    const [[, runEnter], [, doubleEnter], [, doubleExit], [, runExit]] = events;
+    assert.deepStrictEqual(runEnter.args, [20]);
+    assert.deepStrictEqual(doubleEnter.args, [20]);
+    assert.strictEqual(doubleExit.result, 40);
+    assert.strictEqual(runExit.result, 41);
+    assert.strictEqual(typeof runEnter.taskID, 'bigint');
+    assert.ok(doubleEnter.time >= runEnter.time);
+    assertSpan(runEnter, runExit);
+    assertSpan(doubleEnter, doubleExit);
+
+    // Errors thrown by imports end the spans of both the import and the export that called it
+    events.length = 0;
+    assert.throws(() => wasm.trip(), hostError);
+    assert.deepStrictEqual(
+        events.map(([kind, { fn }]) => [kind, fn]),
+        [
+            ['enter', TRIP],
+            ['enter', FAIL],
+            ['error', FAIL],
+            ['error', TRIP],
+        ],
+    );
+    const [[, tripEnter], [, failEnter], [, failError], [, tripError]] = events;
+    assert.deepStrictEqual(tripEnter.args, []);
+    assert.deepStrictEqual(failEnter.args, ['host failure']);
+    assert.strictEqual(failError.error, hostError);
+    assert.strictEqual(tripError.error, hostError);
+    assertSpan(tripEnter, tripError);
+    assertSpan(failEnter, failError);
+
+    // Errors thrown by hooks are logged, and do not affect the traced calls
+    const hookError = new Error('hook failure');
+    const throwHookError = () => {
+        throw hookError;
+    };
+    wasm._util.setTracer({ enter: throwHookError, exit: throwHookError, error: throwHookError });
+    let logs = captureErrorLogs(() => assert.strictEqual(wasm.run(1), 3));
+    assert.strictEqual(logs.length, 4);
+    assert.ok(logs.every((log) => log.startsWith('tracer hook failed') && log.includes('hook failure')));
+    logs = captureErrorLogs(() => assert.throws(() => wasm.trip(), hostError));
+    assert.strictEqual(logs.length, 4);
+
+    // Hooks are optional
+    events.length = 0;
+    wasm._util.setTracer({ exit: (event: any) => events.push(['exit', event]) });
+    assert.strictEqual(wasm.run(2), 5);
+    assert.deepStrictEqual(
+        events.map(([kind, { fn }]) => [kind, fn]),
+        [
+            ['exit', DOUBLE],
+            ['exit', RUN],
+        ],
+    );
+
+    // Without hooks, events are logged
+    wasm._util.setTracer(null);
+    logs = captureErrorLogs(() => assert.strictEqual(wasm.run(3), 7));
+    assert.deepStrictEqual(logs, [
+        '[iface="<no iface>", function="run"] call x=3',
+        '[iface="test:tracing/host", function="double"] call x=3',
+        '[iface="test:tracing/host", function="double"] return result=6',
+        '[iface="<no iface>", function="run"] return result=7',
+    ]);
+
+    assert.throws(() => wasm._util.setTracer(() => {}), TypeError);
+}
+
+// TLA cycle avoidance
+setTimeout(run);
@@ -0,0 +1,43 @@
+;; Waits on a waitable set holding a stream end whose other end is only held by the
+;; (blocked) task itself, so the wait can never complete
+(component
+  (type $s (stream u32))
+
+  (core module $libc
+    (memory (export "memory") 1)
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $stream_new (canon stream.new $s))
+  (core func $waitable_set_new (canon waitable-set.new))
+  (core func $waitable_join (canon waitable.join))
+
+  (core module $m
+    (import "" "stream.new" (func $stream_new (result i64)))
+    (import "" "waitable-set.new" (func $waitable_set_new (result i32)))
+    (import "" "waitable.join" (func $waitable_join (param i32 i32)))
+
+    (func (export "run") (result i32)
+      (local $set i32)
+      (local.set $set (call $waitable_set_new))
+      ;; join the readable end, keeping the writable end in this component
+      (call $waitable_join
+        (i32.wrap_i64 (call $stream_new))
+        (local.get $set))
+      ;; WAIT on the set
+      (i32.or (i32.const 2) (i32.shl (local.get $set) (i32.const 4))))
+
+    (func (export "cb") (param i32 i32 i32) (result i32)
+      unreachable)
+  )
+  (core instance $m (instantiate $m
+    (with "" (instance
+      (export "stream.new" (func $stream_new))
+      (export "waitable-set.new" (func $waitable_set_new))
+      (export "waitable.join" (func $waitable_join))
+    ))
+  ))
+
+  (func (export "run") async
+    (canon lift (core func $m "run") async (callback (func $m "cb"))))
+)
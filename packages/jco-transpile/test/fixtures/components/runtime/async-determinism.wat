@@ -0,0 +1,137 @@
+;; Joins the readable ends of several streams to a waitable set (out of handle order), completes
+;; a read on every one of them at once, then polls the set until every event has been delivered
+;;
+;; Returns the joined waitables in join order, followed by the waitables in delivery order
+(component
+  (type $s (stream u32))
+
+  (core module $libc
+    (memory (export "memory") 1)
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $stream_new (canon stream.new $s))
+  (core func $stream_read (canon stream.read $s async (memory $libc "memory")))
+  (core func $stream_write (canon stream.write $s async (memory $libc "memory")))
+  (core func $stream_drop_readable (canon stream.drop-readable $s))
+  (core func $stream_drop_writable (canon stream.drop-writable $s))
+  (core func $waitable_set_new (canon waitable-set.new))
+  (core func $waitable_set_poll (canon waitable-set.poll (memory $libc "memory")))
+  (core func $waitable_set_drop (canon waitable-set.drop))
+  (core func $waitable_join (canon waitable.join))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "" "stream.new" (func $stream_new (result i64)))
+    (import "" "stream.read" (func $stream_read (param i32 i32 i32) (result i32)))
+    (import "" "stream.write" (func $stream_write (param i32 i32 i32) (result i32)))
+    (import "" "stream.drop-readable" (func $stream_drop_readable (param i32)))
+    (import "" "stream.drop-writable" (func $stream_drop_writable (param i32)))
+    (import "" "waitable-set.new" (func $waitable_set_new (result i32)))
+    (import "" "waitable-set.poll" (func $waitable_set_poll (param i32 i32) (result i32)))
+    (import "" "waitable-set.drop" (func $waitable_set_drop (param i32)))
+    (import "" "waitable.join" (func $waitable_join (param i32 i32)))
+
+    ;; Memory layout:
+    ;;   0: stream element buffer
+    ;;   16: polled event payload (waitable, code-specific payload)
+    ;;   64: results (4 joined waitables, then 4 delivered waitables)
+    ;;   128: returned list (pointer, length)
+    ;;   256: readable ends
+    ;;   288: writable ends
+    ;;   320: order in which the readable ends are joined
+    (data (i32.const 320) "\02\00\00\00\00\00\00\00\03\00\00\00\01\00\00\00")
+
+    (func (export "run") (result i32)
+      (local $i i32)
+      (local $ends i64)
+      (local $set i32)
+      (local $readable i32)
+      (local.set $set (call $waitable_set_new))
+
+      ;; Create the streams
+      (local.set $i (i32.const 0))
+      (loop $create
+        (local.set $ends (call $stream_new))
+        (i32.store offset=256 (i32.shl (local.get $i) (i32.const 2))
+          (i32.wrap_i64 (local.get $ends)))
+        (i32.store offset=288 (i32.shl (local.get $i) (i32.const 2))
+          (i32.wrap_i64 (i64.shr_u (local.get $ends) (i64.const 32))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br_if $create (i32.lt_u (local.get $i) (i32.const 4))))
+
+      ;; Join the readable ends in join order, recording them as they are joined
+      (local.set $i (i32.const 0))
+      (loop $join
+        (local.set $readable
+          (i32.load offset=256
+            (i32.shl (i32.load offset=320 (i32.shl (local.get $i) (i32.const 2))) (i32.const 2))))
+        (i32.store offset=64 (i32.shl (local.get $i) (i32.const 2)) (local.get $readable))
+        (call $waitable_join (local.get $readable) (local.get $set))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br_if $join (i32.lt_u (local.get $i) (i32.const 4))))
+
+      ;; Start a read on every readable end, none of which can complete yet
+      (local.set $i (i32.const 0))
+      (loop $read
+        (if (i32.ne
+              (call $stream_read
+                (i32.load offset=256 (i32.shl (local.get $i) (i32.const 2)))
+                (i32.const 0)
+                (i32.const 1))
+              (i32.const -1))
+          (then unreachable))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br_if $read (i32.lt_u (local.get $i) (i32.const 4))))
+
+      ;; Write to every stream, completing all of the reads at once
+      (local.set $i (i32.const 0))
+      (loop $write
+        (drop
+          (call $stream_write
+            (i32.load offset=288 (i32.shl (local.get $i) (i32.const 2)))
+            (i32.const 0)
+            (i32.const 1)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br_if $write (i32.lt_u (local.get $i) (i32.const 4))))
+
+      ;; Poll out every read (STREAM_READ) event, recording the waitable it was delivered for
+      (local.set $i (i32.const 0))
+      (loop $poll
+        (if (i32.ne (call $waitable_set_poll (local.get $set) (i32.const 16)) (i32.const 2))
+          (then unreachable))
+        (i32.store offset=80 (i32.shl (local.get $i) (i32.const 2)) (i32.load (i32.const 16)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br_if $poll (i32.lt_u (local.get $i) (i32.const 4))))
+
+      ;; Clean up
+      (local.set $i (i32.const 0))
+      (loop $drop
+        (call $stream_drop_readable (i32.load offset=256 (i32.shl (local.get $i) (i32.const 2))))
+        (call $stream_drop_writable (i32.load offset=288 (i32.shl (local.get $i) (i32.const 2))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br_if $drop (i32.lt_u (local.get $i) (i32.const 4))))
+      (call $waitable_set_drop (local.get $set))
+
+      (i32.store (i32.const 128) (i32.const 64))
+      (i32.store (i32.const 132) (i32.const 8))
+      (i32.const 128))
+  )
+  (core instance $m (instantiate $m
+    (with "libc" (instance $libc))
+    (with "" (instance
+      (export "stream.new" (func $stream_new))
+      (export "stream.read" (func $stream_read))
+      (export "stream.write" (func $stream_write))
+      (export "stream.drop-readable" (func $stream_drop_readable))
+      (export "stream.drop-writable" (func $stream_drop_writable))
+      (export "waitable-set.new" (func $waitable_set_new))
+      (export "waitable-set.poll" (func $waitable_set_poll))
+      (export "waitable-set.drop" (func $waitable_set_drop))
+      (export "waitable.join" (func $waitable_join))
+    ))
+  ))
+
+  (func (export "run") (result (list u32))
+    (canon lift (core func $m "run") (memory $libc "memory")))
+)
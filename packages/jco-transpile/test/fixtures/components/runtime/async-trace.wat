@@ -0,0 +1,61 @@
+;; Applies backpressure, and joins a stream end to a waitable set, to produce async trace events
+(component
+  (type $s (stream u32))
+
+  (core module $libc
+    (memory (export "memory") 1)
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $backpressure_inc (canon backpressure.inc))
+  (core func $backpressure_dec (canon backpressure.dec))
+  (core func $stream_new (canon stream.new $s))
+  (core func $stream_drop_readable (canon stream.drop-readable $s))
+  (core func $stream_drop_writable (canon stream.drop-writable $s))
+  (core func $waitable_set_new (canon waitable-set.new))
+  (core func $waitable_set_drop (canon waitable-set.drop))
+  (core func $waitable_join (canon waitable.join))
+
+  (core module $m
+    (import "" "backpressure.inc" (func $backpressure_inc))
+    (import "" "backpressure.dec" (func $backpressure_dec))
+    (import "" "stream.new" (func $stream_new (result i64)))
+    (import "" "stream.drop-readable" (func $stream_drop_readable (param i32)))
+    (import "" "stream.drop-writable" (func $stream_drop_writable (param i32)))
+    (import "" "waitable-set.new" (func $waitable_set_new (result i32)))
+    (import "" "waitable-set.drop" (func $waitable_set_drop (param i32)))
+    (import "" "waitable.join" (func $waitable_join (param i32 i32)))
+
+    (func (export "run") (result i32)
+      (local $ends i64)
+      (local $readable i32)
+      (local $writable i32)
+      (local $set i32)
+      (call $backpressure_inc)
+      (local.set $ends (call $stream_new))
+      (local.set $readable (i32.wrap_i64 (local.get $ends)))
+      (local.set $writable (i32.wrap_i64 (i64.shr_u (local.get $ends) (i64.const 32))))
+      (local.set $set (call $waitable_set_new))
+      (call $waitable_join (local.get $writable) (local.get $set))
+      (call $waitable_join (local.get $writable) (i32.const 0))
+      (call $waitable_set_drop (local.get $set))
+      (call $stream_drop_writable (local.get $writable))
+      (call $stream_drop_readable (local.get $readable))
+      (call $backpressure_dec)
+      (local.get $set))
+  )
+  (core instance $m (instantiate $m
+    (with "" (instance
+      (export "backpressure.inc" (func $backpressure_inc))
+      (export "backpressure.dec" (func $backpressure_dec))
+      (export "stream.new" (func $stream_new))
+      (export "stream.drop-readable" (func $stream_drop_readable))
+      (export "stream.drop-writable" (func $stream_drop_writable))
+      (export "waitable-set.new" (func $waitable_set_new))
+      (export "waitable-set.drop" (func $waitable_set_drop))
+      (export "waitable.join" (func $waitable_join))
+    ))
+  ))
+
+  (func (export "run") (result u32) (canon lift (core func $m "run")))
+)
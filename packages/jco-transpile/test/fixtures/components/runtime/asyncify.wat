@@ -0,0 +1,54 @@
+;; Calls an async host import from a loop, keeping values in locals and on the
+;; value stack across each call, so that they must survive the core module being
+;; unwound and rewound while the host `Promise` is pending
+(component
+  (type $host (instance
+    (export "lookup" (func (param "x" u32) (result u32)))
+    (export "log" (func (param "x" u32)))
+  ))
+  (import "test:asyncify/host" (instance $host (type $host)))
+
+  (core func $lookup (canon lower (func $host "lookup")))
+  (core func $log (canon lower (func $host "log")))
+
+  (core module $m
+    (import "" "lookup" (func $lookup (param i32) (result i32)))
+    (import "" "log" (func $log (param i32)))
+
+    ;; Returns the sum of `lookup(i) + 100 * i + 10` for each `i` below `n`
+    (func (export "run") (param $n i32) (result i32)
+      (local $i i32)
+      (local $acc i32)
+      (local $scaled i32)
+      (block $done
+        (loop $next
+          (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+          (local.set $scaled (i32.mul (local.get $i) (i32.const 100)))
+          ;; The constant stays on the value stack while suspended
+          i32.const 10
+          (call $lookup (local.get $i))
+          i32.add
+          (i32.add (local.get $scaled))
+          (local.set $acc (i32.add (local.get $acc)))
+          (call $log (local.get $i))
+          (local.set $i (i32.add (local.get $i) (i32.const 1)))
+          (br $next)))
+      (local.get $acc))
+
+    ;; Never suspends, as only synchronous imports are called
+    (func (export "add") (param i32 i32) (result i32)
+      (call $log (local.get 0))
+      (i32.add (local.get 0) (local.get 1)))
+  )
+  (core instance $m (instantiate $m
+    (with "" (instance
+      (export "lookup" (func $lookup))
+      (export "log" (func $log))
+    ))
+  ))
+
+  (func (export "run") (param "n" u32) (result u32)
+    (canon lift (core func $m "run")))
+  (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+    (canon lift (core func $m "add")))
+)
@@ -0,0 +1,45 @@
+;; Exports a numeric and a string function, used to check that CommonJS output
+;; can be loaded with `require()`, both with and without instantiation mode
+(component
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.xor (i32.sub (local.get 2) (i32.const 1)) (i32.const -1))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "libc" "realloc" (func $realloc (param i32 i32 i32 i32) (result i32)))
+
+    (func (export "add") (param i32 i32) (result i32)
+      (i32.add (local.get 0) (local.get 1)))
+
+    ;; Returns the given string with "hello " prepended, through a return area at 64
+    (func (export "greet") (param $ptr i32) (param $len i32) (result i32)
+      (local $out i32)
+      (local.set $out
+        (call $realloc (i32.const 0) (i32.const 0) (i32.const 1) (i32.add (local.get $len) (i32.const 6))))
+      (i32.store (local.get $out) (i32.const 0x6c6c6568))
+      (i32.store16 offset=4 (local.get $out) (i32.const 0x206f))
+      (memory.copy (i32.add (local.get $out) (i32.const 6)) (local.get $ptr) (local.get $len))
+      (i32.store (i32.const 64) (local.get $out))
+      (i32.store (i32.const 68) (i32.add (local.get $len) (i32.const 6)))
+      (i32.const 64))
+  )
+  (core instance $m (instantiate $m
+    (with "libc" (instance $libc))
+  ))
+
+  (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+    (canon lift (core func $m "add")))
+  (func (export "greet") (param "name" string) (result string)
+    (canon lift (core func $m "greet") (memory $libc "memory") (realloc (func $libc "realloc")) string-encoding=utf8))
+)
@@ -0,0 +1,25 @@
+;; Instantiates a core module imported from the host, and exports core modules
+;; for the host to instantiate
+(component
+  (import "plugin" (core module $plugin
+    (import "host" "log" (func (param i32)))
+    (export "run" (func (param i32) (result i32)))
+  ))
+
+  (core module $host
+    (global $last (mut i32) (i32.const 0))
+    (func (export "log") (param i32) (global.set $last (local.get 0)))
+    (func (export "last") (result i32) (global.get $last))
+  )
+  (core instance $host (instantiate $host))
+  (core instance $plugin (instantiate $plugin (with "host" (instance $host))))
+
+  (func (export "run") (param "x" u32) (result u32) (canon lift (core func $plugin "run")))
+  (func (export "last") (result u32) (canon lift (core func $host "last")))
+
+  (core module $answer
+    (func (export "answer") (result i32) (i32.const 42))
+  )
+  (export "answer" (core module $answer))
+  (export "plugin" (core module $plugin))
+)
@@ -0,0 +1,66 @@
+;; Creates, inspects and passes around error contexts, both between the component
+;; and the host
+(component
+  (import "report" (func $report (param "e" error-context)))
+
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.xor (i32.sub (local.get 2) (i32.const 1)) (i32.const -1))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $new (canon error-context.new (memory $libc "memory") string-encoding=utf8))
+  (core func $debug_message
+    (canon error-context.debug-message (memory $libc "memory") (realloc (func $libc "realloc")) string-encoding=utf8))
+  (core func $drop (canon error-context.drop))
+  (core func $report (canon lower (func $report)))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "" "new" (func $new (param i32 i32) (result i32)))
+    (import "" "debug-message" (func $debug_message (param i32 i32)))
+    (import "" "drop" (func $drop (param i32)))
+    (import "" "report" (func $report (param i32)))
+
+    (func (export "make") (param i32 i32) (result i32)
+      (call $new (local.get 0) (local.get 1)))
+    (func (export "message") (param i32) (result i32)
+      (call $debug_message (local.get 0) (i32.const 8))
+      (i32.const 8))
+    (func (export "echo") (param i32) (result i32)
+      (local.get 0))
+    (func (export "forward") (param i32)
+      (call $report (local.get 0))
+      (call $drop (local.get 0)))
+    (func (export "drop") (param i32)
+      (call $drop (local.get 0)))
+  )
+  (core instance $m (instantiate $m
+    (with "libc" (instance $libc))
+    (with "" (instance
+      (export "new" (func $new))
+      (export "debug-message" (func $debug_message))
+      (export "drop" (func $drop))
+      (export "report" (func $report))
+    ))
+  ))
+
+  (func (export "make") (param "msg" string) (result error-context)
+    (canon lift (core func $m "make") (memory $libc "memory") (realloc (func $libc "realloc")) string-encoding=utf8))
+  (func (export "message") (param "e" error-context) (result string)
+    (canon lift (core func $m "message") (memory $libc "memory") string-encoding=utf8))
+  (func (export "echo") (param "e" error-context) (result error-context)
+    (canon lift (core func $m "echo")))
+  (func (export "forward") (param "e" error-context)
+    (canon lift (core func $m "forward")))
+  (func (export "drop") (param "e" error-context)
+    (canon lift (core func $m "drop")))
+)
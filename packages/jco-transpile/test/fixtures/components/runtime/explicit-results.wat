@@ -0,0 +1,52 @@
+;; Passes `result<u32, string>` values between the host and the component, both
+;; with thrown errors and with explicit `{ tag, val }` result objects
+(component
+  (type $store (instance
+    (export "get" (func (param "x" u32) (result (result u32 (error string)))))
+    (export "get-or-throw" (func (param "x" u32) (result (result u32 (error string)))))
+  ))
+  (import "local:results/store" (instance $store (type $store)))
+
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.xor (i32.sub (local.get 2) (i32.const 1)) (i32.const -1))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $get
+    (canon lower (func $store "get") (memory $libc "memory") (realloc (func $libc "realloc")) string-encoding=utf8))
+  (core func $get_or_throw
+    (canon lower (func $store "get-or-throw") (memory $libc "memory") (realloc (func $libc "realloc")) string-encoding=utf8))
+
+  (core module $m
+    (import "" "get" (func $get (param i32 i32)))
+    (import "" "get-or-throw" (func $get_or_throw (param i32 i32)))
+
+    ;; Results are written to (and lifted from) a fixed return area
+    (func (export "checked") (param i32) (result i32)
+      (call $get (local.get 0) (i32.const 64))
+      (i32.const 64))
+    (func (export "unchecked") (param i32) (result i32)
+      (call $get_or_throw (local.get 0) (i32.const 64))
+      (i32.const 64))
+  )
+  (core instance $m (instantiate $m
+    (with "" (instance
+      (export "get" (func $get))
+      (export "get-or-throw" (func $get_or_throw))
+    ))
+  ))
+
+  (func (export "checked") (param "x" u32) (result (result u32 (error string)))
+    (canon lift (core func $m "checked") (memory $libc "memory") string-encoding=utf8))
+  (func (export "unchecked") (param "x" u32) (result (result u32 (error string)))
+    (canon lift (core func $m "unchecked") (memory $libc "memory") string-encoding=utf8))
+)
@@ -0,0 +1,67 @@
+;; Calls imported resource constructors, methods and functions returning results,
+;; so that the calls can be recorded into an import journal
+(component
+  (type $host (instance
+    (export "counter" (type $counter (sub resource)))
+    (export "[constructor]counter" (func (param "start" u64) (result (own $counter))))
+    (export "[method]counter.next" (func (param "self" (borrow $counter)) (result u64)))
+    (export "lookup" (func (param "x" u32) (result (result u32 (error string)))))
+  ))
+  (import "local:journal/host" (instance $host (type $host)))
+  (alias export $host "counter" (type $counter))
+
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr
+        (i32.and
+          (i32.add (global.get $heap) (i32.sub (local.get 2) (i32.const 1)))
+          (i32.xor (i32.sub (local.get 2) (i32.const 1)) (i32.const -1))))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $new (canon lower (func $host "[constructor]counter")))
+  (core func $next (canon lower (func $host "[method]counter.next")))
+  (core func $drop (canon resource.drop $counter))
+  (core func $lookup
+    (canon lower (func $host "lookup") (memory $libc "memory") (realloc (func $libc "realloc")) string-encoding=utf8))
+
+  (core module $m
+    (import "" "new" (func $new (param i64) (result i32)))
+    (import "" "next" (func $next (param i32) (result i64)))
+    (import "" "drop" (func $drop (param i32)))
+    (import "" "lookup" (func $lookup (param i32 i32)))
+
+    ;; Advances a new counter twice, returning its second value
+    (func (export "tick") (param i64) (result i64)
+      (local $handle i32)
+      (local $ret i64)
+      (local.set $handle (call $new (local.get 0)))
+      (drop (call $next (local.get $handle)))
+      (local.set $ret (call $next (local.get $handle)))
+      (call $drop (local.get $handle))
+      (local.get $ret))
+
+    ;; Results are written to (and lifted from) a fixed return area
+    (func (export "lookup") (param i32) (result i32)
+      (call $lookup (local.get 0) (i32.const 64))
+      (i32.const 64))
+  )
+  (core instance $m (instantiate $m
+    (with "" (instance
+      (export "new" (func $new))
+      (export "next" (func $next))
+      (export "drop" (func $drop))
+      (export "lookup" (func $lookup))
+    ))
+  ))
+
+  (func (export "tick") (param "start" u64) (result u64)
+    (canon lift (core func $m "tick")))
+  (func (export "lookup") (param "x" u32) (result (result u32 (error string)))
+    (canon lift (core func $m "lookup") (memory $libc "memory") string-encoding=utf8))
+)
@@ -0,0 +1,75 @@
+;; A core module using a second memory, which is rewritten to access that
+;; memory through JS when transpiling without multi-memory support
+(component
+  (core module $libc
+    (memory (export "memory") 1)
+  )
+
+  (core module $m
+    (import "a" "memory" (memory $m0 1))
+    (import "b" "memory" (memory $m1 1))
+
+    (type $get (func (result i32)))
+    (table 2 funcref)
+    (elem (i32.const 0) $ten $twenty)
+    (global $base (mut i32) (i32.const 16))
+
+    (func $ten (result i32) (i32.const 10))
+    (func $twenty (result i32) (i32.const 20))
+
+    (func (export "active-data") (result i32)
+      (i32.load $m1 (global.get $base)))
+
+    (func (export "load8-s") (result i64)
+      (i64.load8_s $m1 offset=4 (global.get $base)))
+
+    (func (export "load32-u") (result i64)
+      (i64.load32_u $m1 offset=4 (global.get $base)))
+
+    (func (export "store16") (param i64) (result i64)
+      (i64.store16 $m1 (i32.const 0) (local.get 0))
+      (i64.load16_s $m1 (i32.const 0)))
+
+    (func (export "fill-copy") (result i32)
+      (memory.fill $m1 (i32.const 32) (i32.const 0xab) (i32.const 4))
+      (memory.copy $m0 $m1 (i32.const 200) (i32.const 32) (i32.const 4))
+      (i32.load $m0 (i32.const 200)))
+
+    (func (export "copy-from-primary") (result i32)
+      (memory.copy $m1 $m0 (i32.const 40) (i32.const 100) (i32.const 1))
+      (i32.load8_u $m1 (i32.const 40)))
+
+    (func (export "init") (result i32)
+      (memory.init $m1 $passive (i32.const 48) (i32.const 1) (i32.const 4))
+      (i32.load $m1 (i32.const 48)))
+
+    (func (export "grow") (result i32)
+      (i32.add
+        (i32.mul (memory.grow $m1 (i32.const 1)) (i32.const 100))
+        (memory.size $m1)))
+
+    (func (export "indirect") (param i32) (result i32)
+      (call_indirect (type $get) (local.get 0)))
+
+    (data (memory $m1) (i32.const 16) "\01\02\03\04\ff\fe\fd\fc")
+    (data $passive "hello")
+    (data (memory $m0) (i32.const 100) "\2a")
+  )
+
+  (core instance $a (instantiate $libc))
+  (core instance $b (instantiate $libc))
+  (core instance $i (instantiate $m
+    (with "a" (instance $a))
+    (with "b" (instance $b))
+  ))
+
+  (func (export "active-data") (result u32) (canon lift (core func $i "active-data")))
+  (func (export "load8-s") (result s64) (canon lift (core func $i "load8-s")))
+  (func (export "load32-u") (result u64) (canon lift (core func $i "load32-u")))
+  (func (export "store16") (param "x" s64) (result s64) (canon lift (core func $i "store16")))
+  (func (export "fill-copy") (result u32) (canon lift (core func $i "fill-copy")))
+  (func (export "copy-from-primary") (result u32) (canon lift (core func $i "copy-from-primary")))
+  (func (export "init") (result u32) (canon lift (core func $i "init")))
+  (func (export "grow") (result u32) (canon lift (core func $i "grow")))
+  (func (export "indirect") (param "i" u32) (result u32) (canon lift (core func $i "indirect")))
+)
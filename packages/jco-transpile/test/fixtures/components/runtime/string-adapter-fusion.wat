@@ -0,0 +1,115 @@
+;; Chains components that use different string encodings, so that the
+;; fused adapters between them need every kind of string transcoder
+(component $root
+  (type $test (instance
+    (export "echo" (func (param "x" string) (result string)))
+  ))
+  (import "test" (instance $test (type $test)))
+
+  (core module $libc
+    (memory (export "memory") 1)
+    (global $next (mut i32) i32.const 128)
+    (func (export "realloc") (param $old_ptr i32) (param $old_size i32) (param $align i32) (param $new_size i32) (result i32)
+      (local $ret i32)
+
+      ;; bump-allocate the new space, 8-byte aligned
+      (local.set $ret
+        (i32.and (i32.add (global.get $next) (i32.const 7)) (i32.const 0xfffffff8)))
+      (global.set $next (i32.add (local.get $ret) (local.get $new_size)))
+
+      (loop $grow
+        (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+          (then
+            (drop (memory.grow (i32.const 1)))
+            (br $grow))))
+
+      ;; the transcoding adapters shrink and grow strings, so copy over
+      ;; the contents of the old allocation
+      (if (local.get $old_ptr)
+        (then
+          (memory.copy
+            (local.get $ret)
+            (local.get $old_ptr)
+            (select
+              (local.get $old_size)
+              (local.get $new_size)
+              (i32.lt_u (local.get $old_size) (local.get $new_size))))))
+
+      (local.get $ret)
+    )
+  )
+
+  (core module $m
+    (import "" "echo" (func $echo (param i32 i32 i32)))
+    (func (export "echo") (param i32 i32) (result i32)
+      (call $echo (local.get 0) (local.get 1) (i32.const 8)) (i32.const 8))
+  )
+
+  (component $Utf8
+    (import "test" (instance $test (type $test)))
+    (alias outer $root $libc (core module $libc))
+    (alias outer $root $m (core module $m))
+
+    (core instance $libc (instantiate $libc))
+    (alias core export $libc "memory" (core memory $mem))
+    (alias core export $libc "realloc" (core func $realloc))
+
+    (core func $echo
+      (canon lower (func $test "echo") (memory $mem) (realloc (func $realloc)) string-encoding=utf8))
+    (core instance $i (instantiate $m
+      (with "" (instance (export "echo" (func $echo))))
+    ))
+    (func (export "echo") (param "x" string) (result string)
+      (canon lift (core func $i "echo") (memory $mem) (realloc (func $realloc)) string-encoding=utf8))
+  )
+
+  (component $Utf16
+    (import "test" (instance $test (type $test)))
+    (alias outer $root $libc (core module $libc))
+    (alias outer $root $m (core module $m))
+
+    (core instance $libc (instantiate $libc))
+    (alias core export $libc "memory" (core memory $mem))
+    (alias core export $libc "realloc" (core func $realloc))
+
+    (core func $echo
+      (canon lower (func $test "echo") (memory $mem) (realloc (func $realloc)) string-encoding=utf16))
+    (core instance $i (instantiate $m
+      (with "" (instance (export "echo" (func $echo))))
+    ))
+    (func (export "echo") (param "x" string) (result string)
+      (canon lift (core func $i "echo") (memory $mem) (realloc (func $realloc)) string-encoding=utf16))
+  )
+
+  (component $Compact
+    (import "test" (instance $test (type $test)))
+    (alias outer $root $libc (core module $libc))
+    (alias outer $root $m (core module $m))
+
+    (core instance $libc (instantiate $libc))
+    (alias core export $libc "memory" (core memory $mem))
+    (alias core export $libc "realloc" (core func $realloc))
+
+    (core func $echo
+      (canon lower (func $test "echo") (memory $mem) (realloc (func $realloc)) string-encoding=latin1+utf16))
+    (core instance $i (instantiate $m
+      (with "" (instance (export "echo" (func $echo))))
+    ))
+    (func (export "echo") (param "x" string) (result string)
+      (canon lift (core func $i "echo") (memory $mem) (realloc (func $realloc)) string-encoding=latin1+utf16))
+  )
+
+  ;; Each instance calls into the previous one, with the first calling the host
+  (instance $i1 (instantiate $Utf8 (with "test" (instance $test))))
+  (instance $i2 (instantiate $Compact (with "test" (instance $i1))))
+  (instance $i3 (instantiate $Utf16 (with "test" (instance $i2))))
+  (instance $i4 (instantiate $Compact (with "test" (instance $i3))))
+  (instance $i5 (instantiate $Compact (with "test" (instance $i4))))
+  (instance $i6 (instantiate $Utf8 (with "test" (instance $i5))))
+  (instance $i7 (instantiate $Utf16 (with "test" (instance $i6))))
+  (instance $i8 (instantiate $Utf16 (with "test" (instance $i7))))
+  (instance $i9 (instantiate $Utf8 (with "test" (instance $i8))))
+  (instance $i10 (instantiate $Utf8 (with "test" (instance $i9))))
+
+  (export "result" (instance $i10))
+)
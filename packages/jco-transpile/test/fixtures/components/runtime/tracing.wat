@@ -0,0 +1,39 @@
+;; Exports that call out to host imports, one of which throws, to produce tracing events
+(component
+  (import "test:tracing/host" (instance $host
+    (export "double" (func (param "x" u32) (result u32)))
+    (export "fail" (func (param "message" string)))
+  ))
+
+  (core module $libc
+    (memory (export "memory") 1)
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $double (canon lower (func $host "double")))
+  (core func $fail (canon lower (func $host "fail") (memory $libc "memory")))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "host" "double" (func $double (param i32) (result i32)))
+    (import "host" "fail" (func $fail (param i32 i32)))
+
+    (data (i32.const 0) "host failure")
+
+    (func (export "run") (param i32) (result i32)
+      (i32.add (call $double (local.get 0)) (i32.const 1)))
+
+    (func (export "trip")
+      (call $fail (i32.const 0) (i32.const 12)))
+  )
+  (core instance $m (instantiate $m
+    (with "libc" (instance $libc))
+    (with "host" (instance
+      (export "double" (func $double))
+      (export "fail" (func $fail))
+    ))
+  ))
+
+  (func (export "run") (param "x" u32) (result u32) (canon lift (core func $m "run")))
+  (func (export "trip") (canon lift (core func $m "trip")))
+)
@@ -0,0 +1,81 @@
+;; Passes enums, flags and variants between the host and the component using
+;; alternative JS representations: numeric enums, bitmask flags and class variants
+(component
+  (type $host (instance
+    (type $color' (enum "red" "green" "blue"))
+    (export "color" (type $color (eq $color')))
+    (type $perms' (flags "read" "write" "exec"))
+    (export "perms" (type $perms (eq $perms')))
+    (type $shape' (variant (case "circle" u32) (case "square" u32) (case "none")))
+    (export "shape" (type $shape (eq $shape')))
+    (export "next-color" (func (param "c" $color) (result $color)))
+    (export "toggle" (func (param "p" $perms) (result $perms)))
+    (export "grow" (func (param "s" $shape) (result $shape)))
+  ))
+  (import "local:reprs/host" (instance $host (type $host)))
+  (alias export $host "color" (type $color))
+  (alias export $host "perms" (type $perms))
+  (alias export $host "shape" (type $shape))
+
+  ;; A type of an exported interface, for which the bindings define the classes
+  (type $step (variant (case "up" u32) (case "stop")))
+
+  (core module $libc
+    (memory (export "memory") 1)
+  )
+  (core instance $libc (instantiate $libc))
+
+  (core func $next_color (canon lower (func $host "next-color")))
+  (core func $toggle (canon lower (func $host "toggle")))
+  (core func $grow (canon lower (func $host "grow") (memory $libc "memory")))
+
+  (core module $m
+    (import "libc" "memory" (memory 1))
+    (import "" "next-color" (func $next_color (param i32) (result i32)))
+    (import "" "toggle" (func $toggle (param i32) (result i32)))
+    (import "" "grow" (func $grow (param i32 i32 i32)))
+
+    (func (export "cycle") (param i32) (result i32)
+      (call $next_color (local.get 0)))
+    (func (export "toggle") (param i32) (result i32)
+      (call $toggle (local.get 0)))
+    ;; Variants are written to (and lifted from) a fixed return area
+    (func (export "grow") (param i32 i32) (result i32)
+      (call $grow (local.get 0) (local.get 1) (i32.const 64))
+      (i32.const 64))
+    (func (export "flip") (param i32 i32) (result i32)
+      (i32.store8 (i32.const 96) (i32.sub (i32.const 1) (local.get 0)))
+      (i32.store (i32.const 100) (local.get 1))
+      (i32.const 96))
+  )
+  (core instance $m (instantiate $m
+    (with "libc" (instance $libc))
+    (with "" (instance
+      (export "next-color" (func $next_color))
+      (export "toggle" (func $toggle))
+      (export "grow" (func $grow))
+    ))
+  ))
+
+  (func (export "cycle") (param "c" $color) (result $color)
+    (canon lift (core func $m "cycle")))
+  (func (export "toggle") (param "p" $perms) (result $perms)
+    (canon lift (core func $m "toggle")))
+  (func (export "grow") (param "s" $shape) (result $shape)
+    (canon lift (core func $m "grow") (memory $libc "memory")))
+  (func $flip (param "s" $step) (result $step)
+    (canon lift (core func $m "flip") (memory $libc "memory")))
+
+  (component $api
+    (type $step' (variant (case "up" u32) (case "stop")))
+    (import "import-type-step" (type $step (eq $step')))
+    (import "import-func-flip" (func $flip (param "s" $step) (result $step)))
+    (export $step-export "step" (type $step))
+    (export "flip" (func $flip) (func (param "s" $step-export) (result $step-export)))
+  )
+  (instance $api (instantiate $api
+    (with "import-type-step" (type $step))
+    (with "import-func-flip" (func $flip))
+  ))
+  (export "local:reprs/api" (instance $api))
+)
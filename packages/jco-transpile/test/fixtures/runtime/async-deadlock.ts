@@ -0,0 +1,47 @@
+// Flags: --tla-compat --async-deadlock-timeout 300
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/async-deadlock/async-deadlock.js';
+
+async function run() {
+    await wasm.$init;
+
+    const result = wasm.run();
+
+    // Before the deadlock is reported, the blocked task can be inspected
+    await new Promise((resolve) => setTimeout(resolve, 100));
+    const [task, ...others] = wasm._util.dumpTasks();
+    assert.deepStrictEqual(others, []);
+    assert.strictEqual(task.componentIdx, 0);
+    assert.strictEqual(task.suspended, true);
+    assert.deepStrictEqual(task.waitingOn.waitables, [
+        {
+            idx: task.waitingOn.waitables[0].idx,
+            target: `waitable for read end (waitable [${task.waitingOn.waitables[0].idx}])`,
+            hasPendingEvent: false,
+            end: {
+                kind: 'stream',
+                target: `stream read end (waitable [${task.waitingOn.waitables[0].idx}])`,
+                otherEnd: task.waitingOn.waitables[0].end.otherEnd,
+                hostDriven: false,
+            },
+        },
+    ]);
+    assert.match(task.waitingOn.waitables[0].end.otherEnd, /^stream write end/);
+
+    await assert.rejects(result, (err: any) => {
+        assert.match(err.message, /^deadlock detected: no async task can make progress/);
+        assert.ok(err.message.includes(`task [${task.taskID}] (component [0], fn [${task.entryFnName}]) is waiting on ${task.waitingOn.waitableSet}:`));
+        assert.ok(err.message.includes(`stream read end (waitable [${task.waitingOn.waitables[0].idx}]), other end stream write end`));
+        assert.deepStrictEqual(err.blockedTasks, [task]);
+        return true;
+    });
+
+    // The failed task is no longer live
+    assert.deepStrictEqual(wasm._util.dumpTasks(), []);
+}
+
+// TLA cycle avoidance
+setTimeout(run);
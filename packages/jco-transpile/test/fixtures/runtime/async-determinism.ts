@@ -0,0 +1,25 @@
+// Flags: --tla-compat --async-determinism deterministic
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/async-determinism/async-determinism.js';
+
+/** Run the component, returning the position (in join order) of each waitable, in delivery order */
+function deliveryOrder(): number[] {
+    const waitables = [...wasm.run()];
+    const joined = waitables.slice(0, 4);
+    return waitables.slice(4).map((waitable) => joined.indexOf(waitable));
+}
+
+async function run() {
+    await wasm.$init;
+
+    // Of the waitables with pending events, the earliest joined is always delivered first
+    for (let i = 0; i < 10; i++) {
+        assert.deepStrictEqual(deliveryOrder(), [0, 1, 2, 3]);
+    }
+}
+
+// TLA cycle avoidance
+setTimeout(run);
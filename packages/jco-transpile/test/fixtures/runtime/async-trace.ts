@@ -0,0 +1,47 @@
+// Flags: --tla-compat --async-trace
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/async-trace/async-trace.js';
+
+async function run() {
+    await wasm.$init;
+
+    // Returns the waitable set that the stream end was joined to
+    const waitableSet = wasm.run();
+
+    const { traceEvents } = JSON.parse(JSON.stringify(wasm._util.asyncTraceEvents()));
+    assert.deepStrictEqual(
+        traceEvents.map((e: any) => [e.ph, e.cat, e.name]),
+        [
+            ['M', undefined, 'thread_name'],
+            ['b', 'task', traceEvents[1].name],
+            ['C', 'backpressure', 'backpressure [component 0]'],
+            ['i', 'waitable', 'waitable.join'],
+            ['i', 'waitable', 'waitable.leave'],
+            ['C', 'backpressure', 'backpressure [component 0]'],
+            ['i', 'task', 'task.return'],
+            ['e', 'task', traceEvents[1].name],
+        ],
+    );
+
+    const [thread, taskStart, inc, join, leave, dec, , taskEnd] = traceEvents;
+    assert.deepStrictEqual(thread.args, { name: 'component [0]' });
+    assert.strictEqual(taskStart.id, taskEnd.id);
+    assert.deepStrictEqual(taskStart.args, { taskID: taskStart.args.taskID, isAsync: false, parentSubtaskID: null });
+    assert.deepStrictEqual(taskEnd.args, { state: 'resolved' });
+    assert.deepStrictEqual([inc.args, dec.args], [{ value: 1 }, { value: 0 }]);
+    assert.strictEqual(join.args.waitableSetRep, waitableSet);
+    assert.strictEqual(leave.args.waitableSetRep, 0);
+    assert.strictEqual(join.args.waitableRep, leave.args.waitableRep);
+    for (let i = 1; i < traceEvents.length; i++) {
+        assert.ok(traceEvents[i].ts >= traceEvents[i - 1].ts || traceEvents[i - 1].ph === 'M');
+    }
+
+    wasm._util.clearAsyncTrace();
+    assert.deepStrictEqual(wasm._util.asyncTraceEvents().traceEvents, []);
+}
+
+// TLA cycle avoidance
+setTimeout(run);
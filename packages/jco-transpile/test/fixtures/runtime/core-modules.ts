@@ -0,0 +1,35 @@
+// Flags: --tla-compat --map plugin=../core-modules.js
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/core-modules/core-modules.js';
+
+// A core module which calls `host.log` with its argument, and returns it doubled
+export default new WebAssembly.Module(
+    new Uint8Array([
+        0, 97, 115, 109, 1, 0, 0, 0, 1, 10, 2, 96, 1, 127, 0, 96, 1, 127, 1, 127, 2, 12, 1, 4, 104, 111, 115, 116, 3,
+        108, 111, 103, 0, 0, 3, 2, 1, 1, 7, 7, 1, 3, 114, 117, 110, 0, 1, 10, 13, 1, 11, 0, 32, 0, 16, 0, 32, 0, 65, 2,
+        108, 11,
+    ]),
+);
+
+async function run() {
+    await wasm.$init;
+
+    // The imported module is instantiated by the component
+    assert.strictEqual(wasm.run(21), 42);
+    assert.strictEqual(wasm.last(), 21);
+
+    // Exported modules can be instantiated by the host
+    assert.ok(wasm.answer instanceof WebAssembly.Module);
+    const { exports } = new WebAssembly.Instance(wasm.answer);
+    assert.strictEqual((exports.answer as () => number)(), 42);
+
+    // Re-exported imports are the module that was imported
+    assert.ok(wasm.plugin instanceof WebAssembly.Module);
+    assert.deepStrictEqual(WebAssembly.Module.exports(wasm.plugin), [{ name: 'run', kind: 'function' }]);
+}
+
+// Async cycle handling
+setTimeout(run);
@@ -0,0 +1,50 @@
+// Flags: --tla-compat --map local:journal/host=../import-journal.js --import-journal record
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/import-journal/import-journal.js';
+
+export class Counter {
+    constructor(public n: bigint) {}
+    next() {
+        return ++this.n;
+    }
+}
+
+export function lookup(x: number) {
+    if (x === 0) {
+        throw Object.assign(new Error('lookup failed'), { payload: 'not found' });
+    }
+    return x * 2;
+}
+
+async function run() {
+    await wasm.$init;
+
+    assert.strictEqual(wasm.tick(5n), 7n);
+    assert.strictEqual(wasm.lookup(2), 4);
+    assert.throws(() => wasm.lookup(0), (e: any) => e.payload === 'not found');
+
+    // Calls are recorded in order, as JSON-compatible entries
+    const journal = JSON.parse(JSON.stringify(wasm._util.importJournal()));
+    const counter = { $: 'resource', class: 'Counter', id: 0 };
+    assert.deepStrictEqual(journal, [
+        {
+            fn: 'local:journal/host#[constructor]counter',
+            args: [{ $: 'bigint', val: '5' }],
+            result: counter,
+        },
+        { fn: 'local:journal/host#[method]counter.next', args: [counter], result: { $: 'bigint', val: '6' } },
+        { fn: 'local:journal/host#[method]counter.next', args: [counter], result: { $: 'bigint', val: '7' } },
+        { fn: 'local:journal/host#lookup', args: [2], result: 4 },
+        {
+            fn: 'local:journal/host#lookup',
+            args: [0],
+            thrown: { message: 'lookup failed', payload: 'not found' },
+        },
+    ]);
+}
+
+// TLA cycle avoidance
+setTimeout(run);
@@ -0,0 +1,71 @@
+// Flags: --tla-compat --map local:reprs/host=../type-reprs.js --numeric-enums * --bitmask-flags local:reprs/host#perms --class-variants *
+
+import * as assert from 'assert';
+
+// @ts-expect-error
+import * as wasm from '../js-test-components/type-reprs/type-reprs.js';
+
+// Classes for variants of imported interfaces are provided by the host
+export abstract class Shape {}
+export namespace Shape {
+    export class Circle extends Shape {
+        constructor(public val: number) {
+            super();
+        }
+    }
+    export class Square extends Shape {
+        constructor(public val: number) {
+            super();
+        }
+    }
+    export class None extends Shape {}
+}
+
+export function nextColor(c: number) {
+    return (c + 1) % 3;
+}
+
+export function toggle(p: number) {
+    return ~p & 0b111;
+}
+
+export function grow(s: any) {
+    if (s instanceof Shape.Circle) {
+        return new Shape.Square(s.val * 2);
+    }
+    return new Shape.None();
+}
+
+async function run() {
+    await wasm.$init;
+
+    // Enums are numbers
+    assert.strictEqual(wasm.cycle(0), 1);
+    assert.strictEqual(wasm.cycle(2), 0);
+    assert.throws(() => wasm.cycle(3), TypeError);
+    assert.throws(() => wasm.cycle('red'), TypeError);
+
+    // Flags are bitmasks
+    assert.strictEqual(wasm.toggle(0b101), 0b010);
+    assert.strictEqual(wasm.toggle(0), 0b111);
+    assert.throws(() => wasm.toggle(0b1000), TypeError);
+
+    // Variants are instances of the case classes
+    const grown = wasm.grow(new Shape.Circle(3));
+    assert.ok(grown instanceof Shape.Square);
+    assert.strictEqual(grown.val, 6);
+    assert.ok(wasm.grow(new Shape.None()) instanceof Shape.None);
+    assert.throws(() => wasm.grow({ tag: 'circle', val: 3 }), TypeError);
+
+    // Classes of exported variants are defined by the bindings
+    const { Step, flip } = wasm.api;
+    const stopped = flip(new Step.Up(5));
+    assert.ok(stopped instanceof Step.Stop);
+    assert.strictEqual(stopped.tag, 'stop');
+    const up = flip(new Step.Stop());
+    assert.ok(up instanceof Step.Up);
+    assert.strictEqual(up.tag, 'up');
+}
+
+// TLA cycle avoidance
+setTimeout(run);
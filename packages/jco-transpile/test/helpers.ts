@@ -577,6 +577,13 @@ export async function startTestWebServer(args) {
     return await served;
 }
 
+/** Fixture flags that select alternative representations of WIT types */
+const TYPE_REPRESENTATION_FLAGS = {
+    '--numeric-enums': 'numericEnums',
+    '--bitmask-flags': 'bitmaskFlags',
+    '--class-variants': 'classVariants',
+} as const;
+
 /** Read the flags that should be set before running a given codegen fixture */
 export async function readFixtureFlags(fixturePath: string): Promise<TranspilationOptions> {
     let source;
@@ -618,6 +625,40 @@ export async function readFixtureFlags(fixturePath: string): Promise<Transpilati
             const [k, v] = mapping.split('=');
             opts.map[k] = v;
             idx++; // skip ahead
+        } else if (arg === '--result-imports' || arg === '--result-exports') {
+            const key = arg === '--result-imports' ? 'resultImports' : 'resultExports';
+            opts[key] = [...(opts[key] ?? []), args[idx + 1]];
+            idx++; // skip ahead
+        } else if (arg in TYPE_REPRESENTATION_FLAGS) {
+            const key = TYPE_REPRESENTATION_FLAGS[arg];
+            opts[key] = [...(opts[key] ?? []), args[idx + 1]];
+            idx++; // skip ahead
+        } else if (arg === '--import-journal') {
+            opts.importJournal = args[idx + 1] as TranspilationOptions['importJournal'];
+            idx++; // skip ahead
+        } else if (arg === '--async-determinism') {
+            const determinism = args[idx + 1];
+            opts.asyncDeterminism = determinism.startsWith('seed=')
+                ? { seed: parseInt(determinism.replace('seed=', ''), 10) }
+                : (determinism as TranspilationOptions['asyncDeterminism']);
+            idx++; // skip ahead
+        } else if (arg === '--async-trace') {
+            opts.asyncTrace = true;
+        } else if (arg === '--async-deadlock-timeout') {
+            opts.asyncDeadlockTimeout = parseInt(args[idx + 1], 10);
+            idx++; // skip ahead
+        } else if (arg === '--async-mode') {
+            opts.asyncMode = args[idx + 1] as TranspilationOptions['asyncMode'];
+            idx++; // skip ahead
+        } else if (arg === '--async-imports' || arg === '--async-exports') {
+            const key = arg === '--async-imports' ? 'asyncImports' : 'asyncExports';
+            opts[key] = [...(opts[key] ?? []), args[idx + 1]];
+            idx++; // skip ahead
+        } else if (arg === '--compile-core-to-js') {
+            opts.compileCoreToJs = true;
+        } else if (arg === '--module-format') {
+            opts.moduleFormat = args[idx + 1] as TranspilationOptions['moduleFormat'];
+            idx++; // skip ahead
         } else if (arg === '--js') {
             opts.js = true;
         } else if (arg === '--tla-compat') {
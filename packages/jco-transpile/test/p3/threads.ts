@@ -0,0 +1,30 @@
+import { join } from 'node:path';
+
+import { suite, test, assert } from 'vitest';
+
+import { WASIShim } from '@bytecodealliance/preview2-shim/instantiation';
+
+import { setupAsyncTest } from '../helpers.js';
+import { AsyncFunction, LOCAL_TEST_COMPONENTS_DIR } from '../common.js';
+
+suite('Threads (WASI P3)', () => {
+    test.concurrent('thread.new-indirect, suspend and switching between threads', async () => {
+        const name = 'thread-builtins';
+        const { instance, cleanup } = await setupAsyncTest({
+            component: {
+                name,
+                path: join(LOCAL_TEST_COMPONENTS_DIR, `${name}.wasm`),
+                imports: new WASIShim().getImportObject(),
+            },
+        });
+
+        assert.instanceOf(instance.runThreads, AsyncFunction);
+
+        // The main thread and the worker thread log as they hand control to each other,
+        // (see crates/test-components/src/bin/thread_builtins.rs)
+        const log = await instance.runThreads();
+        assert.deepEqual([...log], [1, 7, 1, 2, 8, 3]);
+
+        await cleanup();
+    });
+});
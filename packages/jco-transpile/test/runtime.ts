@@ -32,9 +32,37 @@ const CODEGEN_TRANSPILE_DEPS = {
     'strings.sync': ['strings.sync/strings.sync.js'],
     smoke: ['smoke/smoke.js'],
     strings: ['strings/strings.js'],
+    'string-adapter-fusion': ['string-adapter-fusion/string-adapter-fusion.js'],
+    'multi-memory': ['multi-memory/multi-memory.js'],
+    'core-modules': ['core-modules/core-modules.js'],
+    'error-context': ['error-context/error-context.js'],
+    'explicit-results': ['explicit-results/explicit-results.js'],
+    'type-reprs': ['type-reprs/type-reprs.js'],
+    'import-journal': ['import-journal/import-journal.js'],
+    tracing: ['tracing/tracing.js'],
+    'async-trace': ['async-trace/async-trace.js'],
+    'async-deadlock': ['async-deadlock/async-deadlock.js'],
+    'async-determinism': ['async-determinism/async-determinism.js'],
+    'async-determinism-seeded': ['async-determinism-seeded/async-determinism-seeded.js'],
+    asyncify: ['asyncify/asyncify.js'],
     variants: ['variants/variants.js', 'helpers.js'],
     resource_borrow_simple: ['resource_borrow_simple/resource_borrow_simple.js'],
     asmjs_bigint: ['asmjs_bigint/asmjs_bigint.js'],
+    commonjs: ['commonjs/commonjs.cjs'],
+    'commonjs-instantiation': ['commonjs-instantiation/commonjs-instantiation.cjs'],
+    'commonjs-compile-core-to-js': ['commonjs-compile-core-to-js/commonjs-compile-core-to-js.cjs'],
+    'compile-core-to-js': ['compile-core-to-js/compile-core-to-js.js'],
+};
+
+/**
+ * Runtime tests that reuse the component of another fixture, transpiled with
+ * the different flags given at the top of the test file
+ */
+const SHARED_COMPONENT_FIXTURES = {
+    'commonjs-instantiation': 'commonjs.wat',
+    'commonjs-compile-core-to-js': 'commonjs.wat',
+    'compile-core-to-js': 'numbers.component.wasm',
+    'async-determinism-seeded': 'async-determinism.wat',
 };
 
 // NOTE: if you find this test failing despite code changes, you may need to clear the test/output folder
@@ -53,6 +81,11 @@ suite('runtime', async () => {
                 .filter((f) => !f.startsWith('wasi-http-proxy'))
                 // Get the fixture, along with a runtime test name
                 .map((f) => [f, f.replace(/(\.component)?\.(wat|wasm)$/, '')])
+                .concat(
+                    Object.entries(SHARED_COMPONENT_FIXTURES)
+                        .filter(([_testName, filename]) => rawFixtures.includes(filename))
+                        .map(([testName, filename]) => [filename, testName]),
+                )
                 .map(([filename, testName]) => {
                     const tsPath = join(tsCodeDir, `${testName}.ts`);
                     return stat(tsPath)
@@ -2,6 +2,7 @@ import { Buffer } from 'node:buffer';
 
 import { suite, test, assert } from 'vitest';
 
+import { COMPONENT_FIXTURES_DIR } from './common.js';
 import { JCO_WIT_FIXTURE_DIR, WIT_FIXTURE_DIR } from './helpers.js';
 
 import { generateGuestTypes, generateHostTypes } from '../src/typegen.js';
@@ -72,6 +73,52 @@ suite('Type Generation', () => {
         assert(interfaceDeclarationContent.includes('function _delete'));
     });
 
+    test('binary WIT package with world selection', async () => {
+        // js-reserved-word.wasm is the binary encoding of js-reserved-word.wit
+        const importFiles = await generateHostTypes(`${WIT_FIXTURE_DIR}/js-reserved-word.wasm`, {
+            worldName: 'fixtures:js-reserved-word/imports',
+        });
+        assert.deepEqual(Object.keys(importFiles), [
+            'imports.d.ts',
+            'interfaces/fixtures-js-reserved-word-example.d.ts',
+        ]);
+        const importsDts = Buffer.from(importFiles['imports.d.ts']).toString();
+        assert.include(importsDts, 'export type * as FixturesJsReservedWordExample');
+        assert.notInclude(importsDts, 'export const example');
+
+        const exportFiles = await generateHostTypes(`${WIT_FIXTURE_DIR}/js-reserved-word.wasm`, {
+            worldName: 'fixtures:js-reserved-word/exports',
+        });
+        const exportsDts = Buffer.from(exportFiles['exports.d.ts']).toString();
+        assert.include(exportsDts, 'export * as example');
+        assert.include(
+            Buffer.from(exportFiles['interfaces/fixtures-js-reserved-word-example.d.ts']).toString(),
+            'export { _delete as delete };',
+        );
+    });
+
+    test('compiled component', async () => {
+        // Without a world name, the component's own world is used
+        const files = await generateHostTypes(`${COMPONENT_FIXTURES_DIR}/adder.component.wasm`, {
+            name: 'adder',
+        });
+        assert.deepEqual(Object.keys(files), ['adder.d.ts', 'interfaces/docs-adder-add.d.ts']);
+        assert.include(
+            Buffer.from(files['adder.d.ts']).toString(),
+            "export * as add from './interfaces/docs-adder-add.js'",
+        );
+        assert.include(
+            Buffer.from(files['interfaces/docs-adder-add.d.ts']).toString(),
+            'export function add(x: number, y: number): number;',
+        );
+
+        // The component's world can also be selected explicitly
+        const selected = await generateHostTypes(`${COMPONENT_FIXTURES_DIR}/adder.component.wasm`, {
+            worldName: 'root:component/root',
+        });
+        assert.deepEqual(Object.keys(selected), ['root.d.ts', 'interfaces/docs-adder-add.d.ts']);
+    });
+
     // https://github.com/bytecodealliance/jco/issues/627
     test('bare exports with ancillary types', async () => {
         const files = await generateHostTypes(`${WIT_FIXTURE_DIR}/bare-export-with-result.wit`, {
@@ -140,4 +187,20 @@ suite('Type Generation', () => {
             "export type MessageFuture = import('./test-named-async-types-types.js').MessageFuture;",
         );
     });
+
+    test('handle type aliases', async () => {
+        const files = await generateHostTypes(`${WIT_FIXTURE_DIR}/handle-aliases.wit`, {
+            worldName: 'test:handle-aliases/handle-aliases',
+        });
+
+        const worldDts = Buffer.from(files['handle-aliases.d.ts']).toString();
+        const typesDts = Buffer.from(files['interfaces/test-handle-aliases-types.d.ts']).toString();
+        const storeDts = Buffer.from(files['interfaces/test-handle-aliases-store.d.ts']).toString();
+
+        assert.include(worldDts, 'export type LocalBlob = Blob;');
+        assert.include(typesDts, 'export type OwnedBlob = Blob;');
+        assert.include(typesDts, 'export type BorrowedBlob = Blob;');
+        assert.include(storeDts, "export type OwnedBlob = import('./test-handle-aliases-types.js').OwnedBlob;");
+        assert.include(storeDts, 'export type StoredBlob = Blob;');
+    });
 });
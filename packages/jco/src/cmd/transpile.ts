@@ -15,6 +15,15 @@ export interface TranspileOpts {
     asyncMode?: string;
     asyncImports?: string[];
     asyncExports?: string[];
+    asyncDeterminism?: string | { seed: number };
+    importJournal?: "record" | "replay";
+    asyncTrace?: boolean;
+    asyncDeadlockTimeout?: number;
+    resultImports?: string[];
+    resultExports?: string[];
+    numericEnums?: string[];
+    bitmaskFlags?: string[];
+    classVariants?: string[];
     validLiftingOptimization?: boolean;
     tracing?: boolean;
     nodejsCompat?: boolean;
@@ -27,6 +36,11 @@ export interface TranspileOpts {
     outDir?: string;
     multiMemory?: boolean;
     bindgenEnableWasmExnref?: boolean;
+    sourceMap?: boolean;
+    sharedRuntime?: boolean;
+    moduleFormat?: "esm" | "commonjs";
+    sourcePhaseImports?: boolean;
+    compileCoreToJs?: boolean;
     experimentalIdlImports?: boolean;
     optArgs?: string[];
     wasmOptBin?: string[];
@@ -55,13 +69,13 @@ export async function transpileCmd(componentPath: string, opts: TranspileOpts, p
  *
  * @param {Uint8Array} component
  * @param {TranspileOpts} [opts]
- * @returns {Promise<{ files: { [filename: string]: Uint8Array }, imports: string[], exports: [string, 'function' | 'instance'][] }>}
+ * @returns {Promise<{ files: { [filename: string]: Uint8Array }, imports: string[], exports: [string, 'function' | 'instance' | 'module'][] }>}
  */
 export async function transpileComponent(component: Uint8Array, opts: TranspileOpts = {}) {
     return transpileBytes(component, prepOpts(opts)) as Promise<{
         files: Record<string, Uint8Array>;
         imports: string[];
-        exports: [string, "function" | "instance"][];
+        exports: [string, "function" | "instance" | "module"][];
     }>;
 }
 
@@ -84,6 +98,15 @@ function prepOpts(opts: any, program?: any) {
         }
     }
 
+    // Seeds are given as `seed=<n>` on the command line
+    if (typeof opts.asyncDeterminism === "string" && opts.asyncDeterminism.startsWith("seed=")) {
+        const seed = Number(opts.asyncDeterminism.slice("seed=".length));
+        if (!Number.isInteger(seed)) {
+            throw new Error(`invalid async determinism [${opts.asyncDeterminism}], expected 'seed=<integer>'`);
+        }
+        opts.asyncDeterminism = { seed };
+    }
+
     return opts;
 }
 
@@ -29,6 +29,11 @@ export interface TypesOptions {
     asyncMode?: string;
     asyncImports?: string[];
     asyncExports?: string[];
+    resultImports?: string[];
+    resultExports?: string[];
+    numericEnums?: string[];
+    bitmaskFlags?: string[];
+    classVariants?: string[];
     outDir?: string;
     allFeatures?: boolean;
     feature?: string[] | "all";
@@ -83,6 +88,11 @@ export async function guestTypes(witPath: string | undefined, opts: TypesOptions
  *   asyncMode?: string,
  *   asyncImports?: string[],
  *   asyncExports?: string[],
+ *   resultImports?: string[],
+ *   resultExports?: string[],
+ *   numericEnums?: string[],
+ *   bitmaskFlags?: string[],
+ *   classVariants?: string[],
  *   outDir?: string,
  *   allFeatures?: bool,
  *   feature?: string[] | 'all', // backwards compat
@@ -143,7 +153,7 @@ export async function typesComponent(witPath: string, opts: TypesOptions): Promi
     // be used to generate a guest that is never transpiled).
     let asyncMode = opts.asyncMode ?? DEFAULT_ASYNC_MODE;
     let asyncModeObj: any;
-    if (asyncMode === "jspi" || asyncExports.size > 0) {
+    if (asyncMode === "jspi" || asyncMode === "asyncify" || asyncExports.size > 0) {
         asyncModeObj = {
             tag: "jspi",
             val: {
@@ -172,6 +182,11 @@ export async function typesComponent(witPath: string, opts: TypesOptions): Promi
             guest,
             strict: opts.strict === true,
             asyncMode: asyncModeObj,
+            resultImports: opts.resultImports,
+            resultExports: opts.resultExports,
+            numericEnums: opts.numericEnums,
+            bitmaskFlags: opts.bitmaskFlags,
+            classVariants: opts.classVariants,
         } as any);
         types = Object.entries(generated).map(([name, bytes]) => [`${outDir}${name}`, bytes]);
     } catch (err) {
@@ -104,19 +104,58 @@ program
     )
     .addOption(
         new Option("--async-mode [mode]", "EXPERIMENTAL: use async imports and exports")
-            .choices(["sync", "jspi"])
+            .choices(["sync", "jspi", "asyncify"])
             .preset("sync"),
     )
     .option("--async-wasi-imports", "EXPERIMENTAL: async component imports from WASI interfaces")
     .option("--async-wasi-exports", "EXPERIMENTAL: async component exports from WASI interfaces")
     .option(
         "--async-imports <imports...>",
-        'EXPERIMENTAL: async component imports (examples: "wasi:io/poll@0.2.0#poll", "wasi:io/poll#[method]pollable.block")',
+        'EXPERIMENTAL: async component imports (examples: "wasi:io/poll@0.2.0#poll", "wasi:io/poll#[method]pollable.block", "wasi:filesystem/*#*")',
     )
     .option(
         "--async-exports <exports...>",
         'EXPERIMENTAL: async component exports (examples: "wasi:cli/run@#run", "handle")',
     )
+    .option(
+        "--async-determinism <profile>",
+        'EXPERIMENTAL: scheduling of async tasks ("random", "deterministic" or "seed=<n>" for a reproducible interleaving)',
+    )
+    .addOption(
+        new Option(
+            "--import-journal <mode>",
+            "EXPERIMENTAL: record calls to host imports into a journal, or replay them from one",
+        ).choices(["record", "replay"]),
+    )
+    .option(
+        "--async-trace",
+        "EXPERIMENTAL: record async task, subtask, waitable, backpressure and stream events as Chrome trace events",
+    )
+    .option(
+        "--async-deadlock-timeout <ms>",
+        "EXPERIMENTAL: milliseconds async tasks must stay deadlocked before they fail (default 1000, 0 disables)",
+        myParseInt,
+    )
+    .option(
+        "--result-imports <imports...>",
+        'component imports that return { tag, val } result objects rather than throwing (examples: "my:pkg/store#get", "my:pkg/store")',
+    )
+    .option(
+        "--result-exports <exports...>",
+        'component exports that return { tag, val } result objects rather than throwing (examples: "my:pkg/store#get", "handle")',
+    )
+    .option(
+        "--numeric-enums <enums...>",
+        'enums represented as numbers rather than strings (examples: "my:pkg/types#color", "color", "*")',
+    )
+    .option(
+        "--bitmask-flags <flags...>",
+        'flags represented as a numeric bitmask rather than a record of booleans (examples: "my:pkg/types#perms", "*")',
+    )
+    .option(
+        "--class-variants <variants...>",
+        'variants represented as class instances rather than { tag, val } objects (examples: "my:pkg/types#shape", "*")',
+    )
     .option("--tracing", "emit `tracing` calls on function entry/exit")
     .option(
         "-b, --base64-cutoff <bytes>",
@@ -142,6 +181,15 @@ program
     .option("--multi-memory", "optimized output for Wasm multi-memory")
     .option("--bindgen-enable-wasm-exnref", "enable bindgen output that uses Wasm exception references (exnref)")
     .option("--strict", "generate bindings with strict type checking")
+    .option("--source-map", "emit a source map for the generated bindings")
+    .option("--shared-runtime", "import intrinsics from a shared runtime module instead of inlining them")
+    .addOption(
+        new Option("--module-format <format>", "module format of the generated JS (commonjs implies --tla-compat)")
+            .choices(["esm", "commonjs"])
+            .default("esm"),
+    )
+    .option("--source-phase-imports", "load core Wasm modules through source phase imports")
+    .option("--compile-core-to-js", "compile core Wasm modules to JS, for engines without WebAssembly")
     .allowExcessArguments(true)
     .action(asyncAction(transpileCmd));
 
@@ -161,19 +209,39 @@ program
     )
     .addOption(
         new Option("--async-mode [mode]", "EXPERIMENTAL: use async imports and exports")
-            .choices(["sync", "jspi"])
+            .choices(["sync", "jspi", "asyncify"])
             .preset("sync"),
     )
     .option("--async-wasi-imports", "EXPERIMENTAL: async component imports from WASI interfaces")
     .option("--async-wasi-exports", "EXPERIMENTAL: async component exports from WASI interfaces")
     .option(
         "--async-imports <imports...>",
-        'EXPERIMENTAL: async component imports (examples: "wasi:io/poll@0.2.0#poll", "wasi:io/poll#[method]pollable.block")',
+        'EXPERIMENTAL: async component imports (examples: "wasi:io/poll@0.2.0#poll", "wasi:io/poll#[method]pollable.block", "wasi:filesystem/*#*")',
     )
     .option(
         "--async-exports <exports...>",
         'EXPERIMENTAL: async component exports (examples: "ns:pkg/iface#func", "wasi:cli/run@0.2.3#run", "handle")',
     )
+    .option(
+        "--result-imports <imports...>",
+        'component imports that return { tag, val } result objects rather than throwing (examples: "my:pkg/store#get", "my:pkg/store")',
+    )
+    .option(
+        "--result-exports <exports...>",
+        'component exports that return { tag, val } result objects rather than throwing (examples: "my:pkg/store#get", "handle")',
+    )
+    .option(
+        "--numeric-enums <enums...>",
+        'enums represented as numbers rather than strings (examples: "my:pkg/types#color", "color", "*")',
+    )
+    .option(
+        "--bitmask-flags <flags...>",
+        'flags represented as a numeric bitmask rather than a record of booleans (examples: "my:pkg/types#perms", "*")',
+    )
+    .option(
+        "--class-variants <variants...>",
+        'variants represented as class instances rather than { tag, val } objects (examples: "my:pkg/types#shape", "*")',
+    )
     .option("-q, --quiet", "disable output summary")
     .option("--feature <feature>", "enable one specific WIT feature (repeatable)", collectOptions, [])
     .option("--all-features", "enable all features")
@@ -198,7 +266,7 @@ program
     )
     .addOption(
         new Option("--async-mode [mode]", "EXPERIMENTAL: use async imports and exports")
-            .choices(["sync", "jspi"])
+            .choices(["sync", "jspi", "asyncify"])
             .preset("sync"),
     )
     .option("--strict", "generate bindings with strict type checking")
@@ -181,12 +181,18 @@ suite("CLI", () => {
         assert.strictEqual(stderr, "");
         const source = await readFile(`${outDir}/${name}.js`, "utf8");
         assert.ok(source.includes("function toResultString("));
-        assert.ok(source.includes('console.error(`[iface="test:flavorful/test", function="f-list-in-record1"] call a'));
         assert.ok(
             source.includes(
-                'console.error(`[iface="test:flavorful/test", function="list-of-variants"] return result=${toResultString(ret)}`);',
+                "_tracer.enter({ iface: 'test:flavorful/test', name: 'f-list-in-record1', kind: 'export', params: ['a'] }, task.id(), task.componentIdx(), [arg0]);",
             ),
         );
+        assert.ok(
+            source.includes(
+                "_tracer.enter({ iface: 'test:flavorful/test', name: 'list-of-variants', kind: 'import', params: ['a', 'b', 'c'] }",
+            ),
+        );
+        assert.ok(source.includes("_tracer.exit(traceSpan, ret);"));
+        assert.ok(source.includes("setTracer: (tracer) => _tracer.set(tracer),"));
 
         await cleanup();
     });